@@ -0,0 +1,93 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+
+pub mod animation;
+pub mod archive;
+pub mod artnet;
+pub mod beads;
+pub mod codegen;
+pub mod cross_stitch;
+pub mod eink;
+pub mod grid;
+pub mod indexed;
+pub mod led_matrix;
+pub mod masks;
+pub mod paint_by_number;
+pub mod pdf;
+pub mod spritesheet;
+pub mod supplies;
+pub mod svg;
+pub mod wire;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Bmp,
+    Gif,
+    WebP,
+    Jpeg,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 5] = [
+        OutputFormat::Png,
+        OutputFormat::Bmp,
+        OutputFormat::Gif,
+        OutputFormat::WebP,
+        OutputFormat::Jpeg,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "PNG",
+            OutputFormat::Bmp => "BMP",
+            OutputFormat::Gif => "GIF",
+            OutputFormat::WebP => "WebP (lossless)",
+            OutputFormat::Jpeg => "JPEG",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Bmp => "image/bmp",
+            OutputFormat::Gif => "image/gif",
+            OutputFormat::WebP => "image/webp",
+            OutputFormat::Jpeg => "image/jpeg",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Gif => "gif",
+            OutputFormat::WebP => "webp",
+            OutputFormat::Jpeg => "jpg",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Gif => ImageFormat::Gif,
+            OutputFormat::WebP => ImageFormat::WebP,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+        }
+    }
+
+    pub fn encode(&self, img: &DynamicImage) -> anyhow::Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        // JPEG has no alpha channel
+        if *self == OutputFormat::Jpeg && img.color().has_alpha() {
+            DynamicImage::ImageRgb8(img.to_rgb8()).write_to(&mut buf, self.image_format())?;
+        } else {
+            img.write_to(&mut buf, self.image_format())?;
+        }
+        Ok(buf.into_inner())
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::Cursor;
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, DynamicImage, Frame, RgbaImage,
+};
+
+/// Encodes frames with their display time in milliseconds as a looping GIF.
+/// Frames smaller than the largest one are placed in the top left corner
+pub fn gif(frames: &[(&DynamicImage, u32)]) -> anyhow::Result<Vec<u8>> {
+    let w = frames.iter().map(|(f, _)| f.width()).max().unwrap_or(0);
+    let h = frames.iter().map(|(f, _)| f.height()).max().unwrap_or(0);
+
+    let mut out = Cursor::new(Vec::new());
+    {
+        let mut enc = GifEncoder::new(&mut out);
+        enc.set_repeat(Repeat::Infinite)?;
+        for (frame, delay_ms) in frames {
+            let mut canvas = RgbaImage::new(w, h);
+            image::imageops::replace(&mut canvas, &frame.to_rgba8(), 0, 0);
+            let delay = Delay::from_numer_denom_ms(*delay_ms, 1);
+            enc.encode_frame(Frame::from_parts(canvas, 0, 0, delay))?;
+        }
+    }
+    Ok(out.into_inner())
+}
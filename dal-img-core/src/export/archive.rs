@@ -0,0 +1,30 @@
+use std::{
+    collections::HashSet,
+    io::{Cursor, Write},
+};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Packs files into a ZIP archive. Duplicate names get a numeric suffix
+pub fn zip_files(files: &[(String, Vec<u8>)]) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let opts = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut used = HashSet::new();
+
+    for (name, data) in files {
+        let mut unique = name.clone();
+        let mut n = 1;
+        while !used.insert(unique.clone()) {
+            unique = match name.rsplit_once('.') {
+                Some((stem, ext)) => format!("{stem}_{n}.{ext}"),
+                None => format!("{name}_{n}"),
+            };
+            n += 1;
+        }
+
+        zip.start_file(unique, opts)?;
+        zip.write_all(data)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
@@ -0,0 +1,164 @@
+//! Art-Net (DMX over UDP) output for LED walls. A fixture layout says which
+//! part of the image drives which universe and channel, the frame is then
+//! sent as one ArtDmx packet per universe.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+/// UDP port every Art-Net node listens on
+pub const PORT: u16 = 6454;
+pub const CHANNELS_PER_UNIVERSE: usize = 512;
+
+const OP_DMX: u16 = 0x5000;
+const PROTOCOL_VERSION: u16 = 14;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorOrder {
+    #[default]
+    Rgb,
+    Grb,
+    Bgr,
+}
+
+impl ColorOrder {
+    fn apply(&self, [r, g, b]: [u8; 3]) -> [u8; 3] {
+        match self {
+            ColorOrder::Rgb => [r, g, b],
+            ColorOrder::Grb => [g, r, b],
+            ColorOrder::Bgr => [b, g, r],
+        }
+    }
+}
+
+/// A rectangle of LEDs wired as one chain, starting at the top left
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fixture {
+    /// Top left corner in image pixels
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub universe: u16,
+    /// First DMX channel, 1-based as on the fixture's DIP switches
+    pub channel: u16,
+    /// Every other row runs right to left, as zig-zag wired panels do
+    pub serpentine: bool,
+    /// Columns instead of rows form the chain
+    pub vertical: bool,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            universe: 0,
+            channel: 1,
+            serpentine: false,
+            vertical: false,
+        }
+    }
+}
+
+/// Fixtures of a wall plus how they address their pixels
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FixtureLayout {
+    pub fixtures: Vec<Fixture>,
+    pub color_order: ColorOrder,
+    /// Pixels after which a chain moves on to the next universe, 170 fills
+    /// all 510 usable RGB channels
+    pub pixels_per_universe: Option<u16>,
+}
+
+impl FixtureLayout {
+    /// One fixture covering the whole image, starting at universe 0
+    pub fn whole_image(dim: (u32, u32)) -> Self {
+        Self {
+            fixtures: vec![Fixture {
+                width: dim.0,
+                height: dim.1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn pixels_per_universe(&self) -> usize {
+        let max = CHANNELS_PER_UNIVERSE / 3;
+        self.pixels_per_universe.map_or(max, |p| (p as usize).clamp(1, max))
+    }
+
+    /// DMX data of every universe the layout touches
+    pub fn map(&self, img: &RgbImage) -> anyhow::Result<BTreeMap<u16, Vec<u8>>> {
+        let per_universe = self.pixels_per_universe();
+        let mut universes: BTreeMap<u16, Vec<u8>> = BTreeMap::new();
+        for fixture in &self.fixtures {
+            let right = fixture.x.checked_add(fixture.width);
+            let bottom = fixture.y.checked_add(fixture.height);
+            ensure!(
+                right.is_some_and(|r| r <= img.width())
+                    && bottom.is_some_and(|b| b <= img.height()),
+                "Fixture at {},{} reaches outside the {}x{} image",
+                fixture.x,
+                fixture.y,
+                img.width(),
+                img.height()
+            );
+            ensure!(fixture.channel >= 1, "DMX channels start at 1");
+
+            let (lines, len) = if fixture.vertical {
+                (fixture.width, fixture.height)
+            } else {
+                (fixture.height, fixture.width)
+            };
+            let mut universe = fixture.universe;
+            let mut channel = fixture.channel as usize - 1;
+            let mut in_universe = 0;
+            for line in 0..lines {
+                for i in 0..len {
+                    let i = if fixture.serpentine && line % 2 == 1 { len - 1 - i } else { i };
+                    let (x, y) = if fixture.vertical { (line, i) } else { (i, line) };
+                    let pixel = img.get_pixel(fixture.x + x, fixture.y + y).0;
+
+                    if in_universe == per_universe || channel + 3 > CHANNELS_PER_UNIVERSE {
+                        universe = universe.checked_add(1).context("Out of universes")?;
+                        channel = 0;
+                        in_universe = 0;
+                    }
+                    let data = universes.entry(universe).or_default();
+                    if data.len() < channel + 3 {
+                        data.resize(channel + 3, 0);
+                    }
+                    data[channel..channel + 3].copy_from_slice(&self.color_order.apply(pixel));
+                    channel += 3;
+                    in_universe += 1;
+                }
+            }
+        }
+        Ok(universes)
+    }
+}
+
+/// An ArtDmx packet carrying `data` for `universe` (15 bits: net, subnet, universe)
+pub fn dmx_packet(universe: u16, sequence: u8, data: &[u8]) -> Vec<u8> {
+    // Length has to be even and at least 2
+    let len = data.len().clamp(2, CHANNELS_PER_UNIVERSE).next_multiple_of(2);
+    let mut packet = Vec::with_capacity(18 + len);
+    packet.extend(b"Art-Net\0");
+    packet.extend(OP_DMX.to_le_bytes());
+    packet.extend(PROTOCOL_VERSION.to_be_bytes());
+    packet.push(sequence);
+    packet.push(0);
+    packet.extend((universe & 0x7fff).to_le_bytes());
+    packet.extend((len as u16).to_be_bytes());
+    packet.extend(&data[..data.len().min(len)]);
+    packet.resize(18 + len, 0);
+    packet
+}
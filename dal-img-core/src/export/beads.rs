@@ -0,0 +1,145 @@
+use super::supplies::{self, supply, Legend, Supply};
+
+/// Pegs per side of a standard square pegboard
+pub const PEGBOARD_SIZE: u32 = 29;
+
+const HAMA: [Supply; 30] = [
+    supply!("H01", "White", 255, 255, 255),
+    supply!("H02", "Cream", 240, 234, 200),
+    supply!("H03", "Yellow", 255, 215, 0),
+    supply!("H04", "Orange", 237, 106, 33),
+    supply!("H05", "Red", 205, 25, 45),
+    supply!("H06", "Pink", 240, 150, 180),
+    supply!("H07", "Purple", 105, 60, 140),
+    supply!("H08", "Blue", 30, 60, 150),
+    supply!("H09", "Light Blue", 80, 140, 210),
+    supply!("H10", "Green", 20, 130, 60),
+    supply!("H11", "Light Green", 110, 200, 90),
+    supply!("H12", "Brown", 100, 60, 35),
+    supply!("H17", "Grey", 140, 140, 140),
+    supply!("H18", "Black", 20, 20, 20),
+    supply!("H20", "Reddish Brown", 140, 50, 30),
+    supply!("H21", "Light Brown", 170, 110, 60),
+    supply!("H22", "Dark Red", 150, 20, 30),
+    supply!("H26", "Flesh", 240, 170, 140),
+    supply!("H27", "Beige", 210, 180, 130),
+    supply!("H28", "Dark Green", 30, 70, 40),
+    supply!("H29", "Claret", 160, 30, 80),
+    supply!("H30", "Burgundy", 110, 20, 40),
+    supply!("H31", "Turquoise", 50, 170, 190),
+    supply!("H43", "Pastel Yellow", 250, 240, 130),
+    supply!("H44", "Pastel Red", 240, 110, 110),
+    supply!("H45", "Pastel Purple", 170, 130, 200),
+    supply!("H46", "Pastel Blue", 120, 170, 230),
+    supply!("H47", "Pastel Green", 140, 220, 140),
+    supply!("H70", "Light Grey", 200, 200, 200),
+    supply!("H71", "Dark Grey", 80, 80, 80),
+];
+
+const PERLER: [Supply; 24] = [
+    supply!("P01", "White", 241, 241, 241),
+    supply!("P02", "Cream", 224, 222, 169),
+    supply!("P03", "Yellow", 236, 216, 0),
+    supply!("P04", "Orange", 237, 97, 32),
+    supply!("P05", "Red", 191, 38, 53),
+    supply!("P06", "Bubblegum", 221, 102, 154),
+    supply!("P07", "Purple", 96, 64, 137),
+    supply!("P08", "Dark Blue", 43, 63, 135),
+    supply!("P09", "Light Blue", 51, 112, 192),
+    supply!("P10", "Dark Green", 28, 117, 62),
+    supply!("P11", "Light Green", 86, 186, 159),
+    supply!("P12", "Brown", 81, 57, 49),
+    supply!("P17", "Grey", 138, 141, 145),
+    supply!("P18", "Black", 46, 47, 50),
+    supply!("P20", "Rust", 140, 55, 44),
+    supply!("P21", "Light Brown", 129, 93, 52),
+    supply!("P33", "Peach", 238, 186, 178),
+    supply!("P35", "Tan", 204, 163, 115),
+    supply!("P38", "Magenta", 242, 46, 123),
+    supply!("P52", "Pastel Blue", 102, 162, 224),
+    supply!("P53", "Pastel Green", 130, 219, 143),
+    supply!("P56", "Pastel Yellow", 254, 248, 146),
+    supply!("P61", "Kiwi Lime", 108, 190, 19),
+    supply!("P62", "Turquoise", 0, 156, 187),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BeadBrand {
+    #[default]
+    Hama,
+    Perler,
+}
+
+impl BeadBrand {
+    pub const ALL: [BeadBrand; 2] = [BeadBrand::Hama, BeadBrand::Perler];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BeadBrand::Hama => "Hama",
+            BeadBrand::Perler => "Perler",
+        }
+    }
+
+    pub fn colors(&self) -> &'static [Supply] {
+        match self {
+            BeadBrand::Hama => &HAMA,
+            BeadBrand::Perler => &PERLER,
+        }
+    }
+
+    /// The bead color closest to `c` by CIEDE2000
+    pub fn nearest(&self, c: [u8; 3]) -> &'static Supply {
+        supplies::nearest(self.colors(), c)
+    }
+}
+
+/// Text pattern split into 29x29 pegboards, with a key and bead counts
+pub fn pattern(
+    title: &str,
+    brand: BeadBrand,
+    indices: &[usize],
+    dim: (u32, u32),
+    palette: &[[u8; 3]],
+) -> anyhow::Result<String> {
+    let legend = Legend::new(brand.colors(), indices, palette)?;
+
+    let boards = (dim.0.div_ceil(PEGBOARD_SIZE), dim.1.div_ceil(PEGBOARD_SIZE));
+    let mut out = format!(
+        "{title}\n{} x {} beads ({}), {} x {} pegboards of {PEGBOARD_SIZE}x{PEGBOARD_SIZE}\n\nBead Counts\n",
+        dim.0,
+        dim.1,
+        brand.label(),
+        boards.0,
+        boards.1,
+    );
+    for (symbol, b, n) in legend.entries() {
+        out.push_str(&format!("  {}  {:<4} {:<16} {:>6}\n", symbol, b.code, b.name, n));
+    }
+    out.push_str(&format!("  Total: {}\n", indices.len()));
+
+    for by in 0..boards.1 {
+        for bx in 0..boards.0 {
+            let (x0, y0) = (bx * PEGBOARD_SIZE, by * PEGBOARD_SIZE);
+            let x1 = (x0 + PEGBOARD_SIZE).min(dim.0);
+            let y1 = (y0 + PEGBOARD_SIZE).min(dim.1);
+            out.push_str(&format!(
+                "\nBoard r{} c{} (columns {}-{}, rows {}-{})\n",
+                by + 1,
+                bx + 1,
+                x0 + 1,
+                x1,
+                y0 + 1,
+                y1
+            ));
+            for y in y0..y1 {
+                out.push_str(&format!("{:>4} ", y + 1));
+                for x in x0..x1 {
+                    let i = indices[(y * dim.0 + x) as usize];
+                    out.push(legend.symbol(i));
+                }
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
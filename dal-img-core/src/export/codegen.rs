@@ -0,0 +1,80 @@
+use anyhow::ensure;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Turns a file name into a valid C/Rust identifier
+pub fn identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    id
+}
+
+fn to_bytes(indices: &[usize]) -> anyhow::Result<Vec<u8>> {
+    ensure!(indices.iter().all(|&i| i < 256), "Palette index exceeds 8 bits");
+    Ok(indices.iter().map(|&i| i as u8).collect())
+}
+
+fn byte_lines(data: &[u8]) -> String {
+    data.chunks(BYTES_PER_LINE)
+        .map(|chunk| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{b:02x}")).collect();
+            format!("    {},\n", bytes.join(", "))
+        })
+        .collect()
+}
+
+/// A `const uint8_t[]` with one palette index per byte, row-major
+pub fn to_c_array(name: &str, indices: &[usize], dim: (u32, u32)) -> anyhow::Result<String> {
+    let data = to_bytes(indices)?;
+    Ok(format!(
+        "#include <stdint.h>\n\n// {}x{} palette indices, row-major\nconst uint8_t {}[{}] = {{\n{}}};\n",
+        dim.0,
+        dim.1,
+        identifier(name),
+        data.len(),
+        byte_lines(&data)
+    ))
+}
+
+/// A Rust `[u8; N]` constant with one palette index per byte, row-major
+pub fn to_rust_array(name: &str, indices: &[usize], dim: (u32, u32)) -> anyhow::Result<String> {
+    let data = to_bytes(indices)?;
+    Ok(format!(
+        "// {}x{} palette indices, row-major\npub const {}: [u8; {}] = [\n{}];\n",
+        dim.0,
+        dim.1,
+        identifier(name).to_ascii_uppercase(),
+        data.len(),
+        byte_lines(&data)
+    ))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Packing {
+    /// One index per byte
+    Bits8,
+    /// Two indices per byte, the first pixel in the high nibble
+    Bits4,
+}
+
+/// Raw palette indices, row-major without any header
+pub fn to_binary(indices: &[usize], packing: Packing) -> anyhow::Result<Vec<u8>> {
+    match packing {
+        Packing::Bits8 => to_bytes(indices),
+        Packing::Bits4 => {
+            ensure!(
+                indices.iter().all(|&i| i < 16),
+                "4-bit packing needs a palette of at most 16 colors"
+            );
+            Ok(indices
+                .chunks(2)
+                .map(|p| ((p[0] as u8) << 4) | p.get(1).map_or(0, |&i| i as u8))
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::supplies::{self, supply, Legend, Supply};
+
+/// A selection of common DMC stranded cotton colors
+pub const DMC: [Supply; 76] = [
+    supply!("B5200", "Snow White", 255, 255, 255),
+    supply!("BLANC", "White", 252, 251, 248),
+    supply!("3865", "Winter White", 249, 247, 241),
+    supply!("ECRU", "Ecru", 240, 234, 218),
+    supply!("310", "Black", 0, 0, 0),
+    supply!("762", "Pearl Gray Very Light", 236, 236, 236),
+    supply!("415", "Pearl Gray", 211, 211, 214),
+    supply!("318", "Steel Gray Light", 171, 171, 171),
+    supply!("414", "Steel Gray Dark", 140, 140, 140),
+    supply!("317", "Pewter Gray", 108, 108, 108),
+    supply!("413", "Pewter Gray Dark", 86, 86, 86),
+    supply!("844", "Beaver Gray Ultra Dark", 72, 72, 72),
+    supply!("3799", "Pewter Gray Very Dark", 66, 66, 66),
+    supply!("666", "Bright Red", 227, 29, 66),
+    supply!("321", "Red", 199, 43, 59),
+    supply!("304", "Red Medium", 183, 31, 51),
+    supply!("498", "Red Dark", 167, 19, 43),
+    supply!("815", "Garnet Medium", 135, 7, 31),
+    supply!("350", "Coral Medium", 224, 72, 72),
+    supply!("351", "Coral", 233, 106, 103),
+    supply!("3705", "Melon Dark", 255, 121, 146),
+    supply!("760", "Salmon", 245, 173, 173),
+    supply!("3713", "Salmon Very Light", 255, 226, 226),
+    supply!("3326", "Rose Light", 251, 173, 180),
+    supply!("600", "Cranberry Very Dark", 205, 47, 99),
+    supply!("602", "Cranberry Medium", 226, 72, 116),
+    supply!("603", "Cranberry", 255, 164, 190),
+    supply!("604", "Cranberry Light", 255, 176, 190),
+    supply!("946", "Burnt Orange Medium", 235, 99, 7),
+    supply!("947", "Burnt Orange", 255, 123, 77),
+    supply!("970", "Pumpkin Light", 247, 139, 19),
+    supply!("740", "Tangerine", 255, 139, 0),
+    supply!("741", "Tangerine Medium", 255, 163, 43),
+    supply!("742", "Tangerine Light", 255, 191, 87),
+    supply!("972", "Canary Deep", 255, 181, 21),
+    supply!("973", "Canary Bright", 255, 227, 0),
+    supply!("444", "Lemon Dark", 255, 214, 0),
+    supply!("307", "Lemon", 253, 237, 84),
+    supply!("445", "Lemon Light", 255, 251, 139),
+    supply!("727", "Topaz Very Light", 255, 241, 175),
+    supply!("890", "Pistachio Green Ultra Dark", 23, 73, 35),
+    supply!("699", "Green", 5, 101, 23),
+    supply!("700", "Green Bright", 7, 115, 27),
+    supply!("702", "Kelly Green", 71, 167, 47),
+    supply!("703", "Chartreuse", 123, 181, 71),
+    supply!("704", "Chartreuse Bright", 158, 207, 52),
+    supply!("907", "Parrot Green Light", 199, 230, 102),
+    supply!("954", "Nile Green", 136, 186, 145),
+    supply!("955", "Nile Green Light", 162, 214, 173),
+    supply!("939", "Navy Blue Very Dark", 27, 40, 83),
+    supply!("336", "Navy Blue", 37, 59, 115),
+    supply!("820", "Royal Blue Very Dark", 14, 54, 92),
+    supply!("796", "Royal Blue Dark", 17, 65, 109),
+    supply!("797", "Royal Blue", 19, 71, 125),
+    supply!("798", "Delft Blue Dark", 70, 106, 142),
+    supply!("799", "Delft Blue Medium", 116, 142, 182),
+    supply!("800", "Delft Blue Pale", 192, 204, 222),
+    supply!("3843", "Electric Blue", 20, 170, 208),
+    supply!("995", "Electric Blue Dark", 38, 150, 182),
+    supply!("996", "Electric Blue Medium", 48, 194, 236),
+    supply!("550", "Violet Very Dark", 92, 24, 78),
+    supply!("552", "Violet Medium", 128, 58, 107),
+    supply!("553", "Violet", 163, 99, 139),
+    supply!("208", "Lavender Very Dark", 131, 91, 139),
+    supply!("209", "Lavender Dark", 163, 123, 167),
+    supply!("210", "Lavender Medium", 195, 159, 195),
+    supply!("938", "Coffee Brown Ultra Dark", 54, 31, 14),
+    supply!("898", "Coffee Brown Very Dark", 73, 42, 19),
+    supply!("801", "Coffee Brown Dark", 101, 57, 25),
+    supply!("433", "Brown Medium", 122, 69, 31),
+    supply!("434", "Brown Light", 152, 94, 51),
+    supply!("435", "Brown Very Light", 184, 119, 72),
+    supply!("436", "Tan", 203, 144, 81),
+    supply!("738", "Tan Very Light", 236, 204, 158),
+    supply!("3371", "Black Brown", 30, 17, 8),
+    supply!("3072", "Beaver Gray Very Light", 230, 232, 232),
+];
+
+// Full cross stitches one 8m skein covers with two strands on 14-count Aida
+const STITCHES_PER_SKEIN: usize = 1600;
+
+/// The DMC color closest to `c` by CIEDE2000
+pub fn nearest_floss(c: [u8; 3]) -> &'static Supply {
+    supplies::nearest(&DMC, c)
+}
+
+/// Text pattern with a symbol chart, a key and a shopping list of skeins.
+/// Palette colors sharing the same floss are merged into one symbol
+pub fn chart(
+    title: &str,
+    indices: &[usize],
+    dim: (u32, u32),
+    palette: &[[u8; 3]],
+) -> anyhow::Result<String> {
+    let legend = Legend::new(&DMC, indices, palette)?;
+
+    let mut out = format!("{title}\n{} x {} stitches\n\nKey\n", dim.0, dim.1);
+    for (symbol, f, n) in legend.entries() {
+        out.push_str(&format!(
+            "  {}  DMC {:<6} {:<28} {:>6} stitches  {} skein(s)\n",
+            symbol,
+            f.code,
+            f.name,
+            n,
+            n.div_ceil(STITCHES_PER_SKEIN)
+        ));
+    }
+
+    out.push_str("\nChart\n     ");
+    for x in 0..dim.0 {
+        out.push(if (x + 1) % 10 == 0 { '|' } else { ' ' });
+    }
+    out.push('\n');
+    for (y, row) in indices.chunks(dim.0.max(1) as usize).enumerate() {
+        out.push_str(&format!("{:>4} ", y + 1));
+        out.extend(row.iter().map(|&i| legend.symbol(i)));
+        out.push('\n');
+    }
+
+    out.push_str("\nShopping List\n");
+    for (_, f, n) in legend.entries() {
+        let skeins = n.div_ceil(STITCHES_PER_SKEIN);
+        out.push_str(&format!("  [ ] {skeins} x DMC {} {}\n", f.code, f.name));
+    }
+    Ok(out)
+}
@@ -0,0 +1,137 @@
+//! Packed framebuffers as e-paper controllers expect them, straight from the
+//! palette indices. Palettes have to be in panel order, e.g. `palettes::EINK_7`
+//! for ACeP panels or black to white for the gray ones.
+
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EinkFormat {
+    /// 1 bit per pixel, black and white
+    #[default]
+    Mono,
+    /// 2 bits per pixel, 4 gray levels
+    Gray4,
+    /// 4 bits per pixel, the 7 ACeP colors
+    Acep7,
+}
+
+impl EinkFormat {
+    pub const ALL: [EinkFormat; 3] = [EinkFormat::Mono, EinkFormat::Gray4, EinkFormat::Acep7];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EinkFormat::Mono => "1bpp (Black/White)",
+            EinkFormat::Gray4 => "2bpp (4 Gray)",
+            EinkFormat::Acep7 => "4bpp (7-Color ACeP)",
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
+        match self {
+            EinkFormat::Mono => 1,
+            EinkFormat::Gray4 => 2,
+            EinkFormat::Acep7 => 4,
+        }
+    }
+
+    pub fn max_colors(&self) -> usize {
+        match self {
+            EinkFormat::Mono => 2,
+            EinkFormat::Gray4 => 4,
+            EinkFormat::Acep7 => 7,
+        }
+    }
+}
+
+/// Where in a byte the first of its pixels goes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BitOrder {
+    /// First pixel in the highest bits, what most controllers use
+    #[default]
+    MsbFirst,
+    LsbFirst,
+}
+
+impl BitOrder {
+    pub const ALL: [BitOrder; 2] = [BitOrder::MsbFirst, BitOrder::LsbFirst];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BitOrder::MsbFirst => "MSB First",
+            BitOrder::LsbFirst => "LSB First",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EinkOptions {
+    pub format: EinkFormat,
+    pub bit_order: BitOrder,
+    /// Every scanline is zero padded to a multiple of this many bytes,
+    /// 0 packs rows back to back
+    pub row_align: u32,
+    /// Flips every value, for 1bpp and gray panels where 0 is white
+    pub invert: bool,
+}
+
+impl Default for EinkOptions {
+    fn default() -> Self {
+        Self {
+            format: EinkFormat::default(),
+            bit_order: BitOrder::default(),
+            row_align: 1,
+            invert: false,
+        }
+    }
+}
+
+/// Packs row-major palette indices of an image `width` pixels wide
+pub fn pack(indices: &[usize], width: u32, options: &EinkOptions) -> anyhow::Result<Vec<u8>> {
+    let format = options.format;
+    ensure!(
+        indices.iter().all(|&i| i < format.max_colors()),
+        "{} needs a palette of at most {} colors",
+        format.label(),
+        format.max_colors()
+    );
+    let bits = format.bits();
+    let max = (1u8 << bits) - 1;
+    // ACeP colors are fixed codes, inverting them would just pick other colors
+    let invert = options.invert && format != EinkFormat::Acep7;
+
+    let mut out = vec![];
+    let mut byte = 0u8;
+    let mut filled = 0;
+    for row in indices.chunks(width.max(1) as usize) {
+        let row_start = out.len();
+        for &i in row {
+            let value = if invert { max - i as u8 } else { i as u8 };
+            let shift = match options.bit_order {
+                BitOrder::MsbFirst => 8 - bits - filled,
+                BitOrder::LsbFirst => filled,
+            };
+            byte |= value << shift;
+            filled += bits;
+            if filled == 8 {
+                out.push(byte);
+                byte = 0;
+                filled = 0;
+            }
+        }
+        if options.row_align > 0 {
+            if filled > 0 {
+                out.push(byte);
+                byte = 0;
+                filled = 0;
+            }
+            let align = options.row_align as usize;
+            out.resize(row_start + (out.len() - row_start).div_ceil(align) * align, 0);
+        }
+    }
+    if filled > 0 {
+        out.push(byte);
+    }
+    Ok(out)
+}
@@ -0,0 +1,56 @@
+/// Palette index rows of an image, transparent pixels become -1
+pub fn index_rows(indices: &[usize], width: u32, transparent: Option<&[bool]>) -> Vec<Vec<i32>> {
+    indices
+        .iter()
+        .enumerate()
+        .map(|(i, &idx)| match transparent {
+            Some(t) if t[i] => -1,
+            _ => idx as i32,
+        })
+        .collect::<Vec<_>>()
+        .chunks(width.max(1) as usize)
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+/// One line of comma separated palette indices per pixel row
+pub fn to_csv(indices: &[usize], width: u32, transparent: Option<&[bool]>) -> String {
+    let mut out = String::new();
+    for row in index_rows(indices, width, transparent) {
+        let line: Vec<String> = row.iter().map(|i| i.to_string()).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// A JSON array of pixel rows, each an array of palette indices
+pub fn to_json(indices: &[usize], width: u32, transparent: Option<&[bool]>) -> String {
+    serde_json::to_string(&index_rows(indices, width, transparent))
+        .expect("Index rows are always serializable")
+}
+
+/// Reads back a CSV written by [`to_csv`] as row-major indices and its
+/// dimensions, transparent pixels are `None`
+pub fn parse_csv(text: &str) -> anyhow::Result<(Vec<Option<usize>>, (u32, u32))> {
+    let mut indices = vec![];
+    let mut width = None;
+    let mut height = 0;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let row = line
+            .split(',')
+            .map(|v| match v.trim().parse::<i32>()? {
+                -1 => Ok(None),
+                i => Ok(Some(usize::try_from(i)?)),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        match width {
+            Some(w) if w != row.len() => anyhow::bail!("Row {} has {} values, expected {}", height + 1, row.len(), w),
+            _ => width = Some(row.len()),
+        }
+        indices.extend(row);
+        height += 1;
+    }
+    let width = width.ok_or_else(|| anyhow::anyhow!("CSV contains no rows"))?;
+    Ok((indices, (width as u32, height)))
+}
@@ -0,0 +1,43 @@
+use std::io::Cursor;
+
+/// Encodes palette indices as an 8-bit indexed PNG with the palette in PLTE.
+/// Pixels marked in `transparent` get an extra fully transparent palette entry
+pub fn indexed_png(
+    indices: &[usize],
+    dim: (u32, u32),
+    palette: &[[u8; 3]],
+    transparent: Option<&[bool]>,
+) -> anyhow::Result<Vec<u8>> {
+    let has_transparency = transparent.is_some_and(|t| t.iter().any(|&t| t));
+    let entries = palette.len() + usize::from(has_transparency);
+    anyhow::ensure!(entries <= 256, "Indexed PNG supports at most 256 colors");
+
+    let mut plte: Vec<u8> = palette.iter().flatten().copied().collect();
+    let mut data: Vec<u8> = indices.iter().map(|&i| i as u8).collect();
+    if let Some(mask) = transparent.filter(|_| has_transparency) {
+        let t = palette.len() as u8;
+        plte.extend([0, 0, 0]);
+        for (d, &m) in data.iter_mut().zip(mask) {
+            if m {
+                *d = t;
+            }
+        }
+    }
+
+    let mut out = Cursor::new(Vec::new());
+    let mut enc = png::Encoder::new(&mut out, dim.0, dim.1);
+    enc.set_color(png::ColorType::Indexed);
+    enc.set_depth(png::BitDepth::Eight);
+    enc.set_palette(plte);
+    if has_transparency {
+        // tRNS may be shorter than PLTE, only the last entry is transparent
+        let mut trns = vec![255u8; palette.len()];
+        trns.push(0);
+        enc.set_trns(trns);
+    }
+
+    let mut writer = enc.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(out.into_inner())
+}
@@ -0,0 +1,43 @@
+//! Request bodies for off-the-shelf LED matrices, built from the converted
+//! pixels rather than the palette indices.
+
+use anyhow::ensure;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use image::RgbImage;
+use serde_json::json;
+
+/// Body for WLED's `POST /json/state`, setting every LED in row-major order.
+/// Matrices need their 2D layout configured in WLED so rows map correctly
+pub fn wled_state(img: &RgbImage) -> String {
+    let colors: Vec<String> = img
+        .pixels()
+        .map(|p| format!("{:02X}{:02X}{:02X}", p[0], p[1], p[2]))
+        .collect();
+    json!({ "on": true, "seg": { "i": colors } }).to_string()
+}
+
+/// Edge lengths a Divoom Pixoo accepts
+pub const DIVOOM_SIZES: [u32; 3] = [16, 32, 64];
+
+/// Bodies for the Pixoo's `POST /post`: a reset of the animation counter,
+/// then the image as a single frame animation
+pub fn divoom_commands(img: &RgbImage) -> anyhow::Result<Vec<String>> {
+    let (w, h) = img.dimensions();
+    ensure!(
+        w == h && DIVOOM_SIZES.contains(&w),
+        "Divoom Pixoo needs a square image of 16, 32 or 64 pixels"
+    );
+    Ok(vec![
+        json!({ "Command": "Draw/ResetHttpGifId" }).to_string(),
+        json!({
+            "Command": "Draw/SendHttpGif",
+            "PicNum": 1,
+            "PicWidth": w,
+            "PicOffset": 0,
+            "PicID": 1,
+            "PicSpeed": 1000,
+            "PicData": BASE64_STANDARD.encode(img.as_raw()),
+        })
+        .to_string(),
+    ])
+}
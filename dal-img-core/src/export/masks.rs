@@ -0,0 +1,31 @@
+use image::{DynamicImage, GrayImage, Luma};
+
+use super::{archive, OutputFormat};
+
+/// One black and white mask per used palette color, white where the color
+/// is used. Returned as (palette index, mask)
+pub fn masks(indices: &[usize], dim: (u32, u32), palette: &[[u8; 3]]) -> Vec<(usize, GrayImage)> {
+    (0..palette.len())
+        .filter(|&p| indices.contains(&p))
+        .map(|p| {
+            let mask = GrayImage::from_fn(dim.0, dim.1, |x, y| {
+                let used = indices[(y * dim.0 + x) as usize] == p;
+                Luma([if used { 255 } else { 0 }])
+            });
+            (p, mask)
+        })
+        .collect()
+}
+
+/// All masks as PNGs in one ZIP, named after palette index and color
+pub fn masks_zip(stem: &str, indices: &[usize], dim: (u32, u32), palette: &[[u8; 3]]) -> anyhow::Result<Vec<u8>> {
+    let files = masks(indices, dim, palette)
+        .into_iter()
+        .map(|(p, mask)| {
+            let c = palette[p];
+            let name = format!("{stem}_{p:02}_{:02x}{:02x}{:02x}.png", c[0], c[1], c[2]);
+            Ok((name, OutputFormat::Png.encode(&DynamicImage::ImageLuma8(mask))?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    archive::zip_files(&files)
+}
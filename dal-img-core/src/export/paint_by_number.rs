@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::img_conv::regions::{label_regions, Region};
+
+// Size of one board pixel in SVG user units
+const CELL: usize = 20;
+const LEGEND_ROW: usize = 28;
+
+// The region pixel closest to the region's centroid, so labels of
+// concave shapes still end up inside
+fn label_position(region: &Region, w: usize) -> (usize, usize) {
+    let n = region.pixels.len() as f32;
+    let cx = region.pixels.iter().map(|&i| (i % w) as f32).sum::<f32>() / n;
+    let cy = region.pixels.iter().map(|&i| (i / w) as f32).sum::<f32>() / n;
+    region
+        .pixels
+        .iter()
+        .map(|&i| (i % w, i / w))
+        .min_by(|a, b| {
+            let da = (a.0 as f32 - cx).powi(2) + (a.1 as f32 - cy).powi(2);
+            let db = (b.0 as f32 - cx).powi(2) + (b.1 as f32 - cy).powi(2);
+            da.total_cmp(&db)
+        })
+        .expect("Regions are never empty")
+}
+
+/// An SVG outline of all same-color regions, each labeled with its palette
+/// index, followed by a legend of the numbered colors
+pub fn outline_svg(indices: &[usize], dim: (u32, u32), palette: &[[u8; 3]]) -> String {
+    let (w, h) = (dim.0 as usize, dim.1 as usize);
+    let regions = label_regions(indices, dim);
+    let labels = &regions.labels;
+
+    let used: Vec<usize> = (0..palette.len()).filter(|p| indices.contains(p)).collect();
+    let (sw, sh) = (w * CELL, h * CELL + LEGEND_ROW * (used.len() + 1));
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{sw}\" height=\"{sh}\" viewBox=\"0 0 {sw} {sh}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n"
+    );
+
+    // Trace region borders as the edges between differently labeled pixels
+    let mut path = format!("M0 0H{}V{}H0Z", w * CELL, h * CELL);
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            if x + 1 < w && labels[i] != labels[i + 1] {
+                write!(path, "M{} {}v{CELL}", (x + 1) * CELL, y * CELL).unwrap();
+            }
+            if y + 1 < h && labels[i] != labels[i + w] {
+                write!(path, "M{} {}h{CELL}", x * CELL, (y + 1) * CELL).unwrap();
+            }
+        }
+    }
+    writeln!(out, "<path d=\"{path}\" fill=\"none\" stroke=\"black\" stroke-width=\"1\"/>").unwrap();
+
+    out.push_str("<g font-family=\"sans-serif\" font-size=\"9\" text-anchor=\"middle\" fill=\"#555\">\n");
+    for region in &regions.regions {
+        let (x, y) = label_position(region, w);
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{}</text>",
+            x * CELL + CELL / 2,
+            y * CELL + CELL / 2 + 3,
+            region.color
+        )
+        .unwrap();
+    }
+    out.push_str("</g>\n");
+
+    out.push_str("<g font-family=\"sans-serif\" font-size=\"14\">\n");
+    for (row, &p) in used.iter().enumerate() {
+        let y = h * CELL + LEGEND_ROW * (row + 1);
+        let c = palette[p];
+        writeln!(
+            out,
+            "<rect x=\"0\" y=\"{}\" width=\"20\" height=\"20\" fill=\"#{:02x}{:02x}{:02x}\" stroke=\"black\"/><text x=\"28\" y=\"{}\">{p}</text>",
+            y - 16,
+            c[0],
+            c[1],
+            c[2],
+            y
+        )
+        .unwrap();
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
@@ -0,0 +1,233 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+// A4 landscape in points
+const PAGE_W: f32 = 842.0;
+const PAGE_H: f32 = 595.0;
+const MARGIN: f32 = 36.0;
+const FONT: Name = Name(b"F1");
+// Cell labels below this size can't be read anyway and only bloat the file
+const MIN_LABEL_SIZE: f32 = 4.0;
+
+fn rgb(c: [u8; 3]) -> (f32, f32, f32) {
+    (c[0] as f32 / 255.0, c[1] as f32 / 255.0, c[2] as f32 / 255.0)
+}
+
+// Black or white, whichever reads better on `c`
+fn text_color(c: [u8; 3]) -> (f32, f32, f32) {
+    let luma = 0.299 * c[0] as f32 + 0.587 * c[1] as f32 + 0.114 * c[2] as f32;
+    if luma > 128.0 {
+        (0.0, 0.0, 0.0)
+    } else {
+        (1.0, 1.0, 1.0)
+    }
+}
+
+// The standard Helvetica only covers WinAnsi, which matches Latin-1 apart
+// from a few punctuation marks. Anything else is shown as '?'
+fn win_ansi(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '‚' => 0x82,
+            '„' => 0x84,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '™' => 0x99,
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn text(content: &mut Content, x: f32, y: f32, size: f32, s: &str) {
+    content
+        .begin_text()
+        .set_font(FONT, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(s)))
+        .end_text();
+}
+
+// Largest cell size at which a `dim` grid fits into the given box
+fn cell_size(dim: (u32, u32), w: f32, h: f32) -> f32 {
+    (w / dim.0 as f32).min(h / dim.1 as f32)
+}
+
+/// Full page preview of the board, every pixel drawn as a round dot
+fn mockup_page(title: &str, indices: &[usize], dim: (u32, u32), palette: &[[u8; 3]]) -> Content {
+    let mut c = Content::new();
+    c.set_fill_rgb(0.0, 0.0, 0.0);
+    text(&mut c, MARGIN, PAGE_H - MARGIN, 16.0, title);
+
+    let area_h = PAGE_H - 3.0 * MARGIN;
+    let cell = cell_size(dim, PAGE_W - 2.0 * MARGIN, area_h);
+    let (bw, bh) = (cell * dim.0 as f32, cell * dim.1 as f32);
+    let (x0, y0) = ((PAGE_W - bw) / 2.0, MARGIN + (area_h - bh) / 2.0);
+
+    // Board housing
+    c.set_fill_rgb(0.1, 0.1, 0.12);
+    c.rect(x0 - cell, y0 - cell, bw + 2.0 * cell, bh + 2.0 * cell);
+    c.fill_nonzero();
+
+    // Approximate each dot with a square inset into its cell
+    let inset = cell * 0.12;
+    for (i, &idx) in indices.iter().enumerate() {
+        let (x, y) = ((i as u32 % dim.0) as f32, (i as u32 / dim.0) as f32);
+        let (r, g, b) = rgb(palette[idx]);
+        c.set_fill_rgb(r, g, b);
+        c.rect(
+            x0 + x * cell + inset,
+            y0 + bh - (y + 1.0) * cell + inset,
+            cell - 2.0 * inset,
+            cell - 2.0 * inset,
+        );
+        c.fill_nonzero();
+    }
+    c
+}
+
+/// Numbered grid with row/column coordinates, every cell labeled with its
+/// palette index as long as the cells are large enough to read it
+fn grid_page(indices: &[usize], dim: (u32, u32), palette: &[[u8; 3]]) -> Content {
+    let mut c = Content::new();
+    let label = 12.0;
+    let cell = cell_size(
+        dim,
+        PAGE_W - 2.0 * MARGIN - label,
+        PAGE_H - 2.0 * MARGIN - label,
+    );
+    let x0 = MARGIN + label;
+    let top = PAGE_H - MARGIN - label;
+    let font = (cell * 0.5).min(8.0);
+
+    for (i, &idx) in indices.iter().enumerate() {
+        let (x, y) = (i as u32 % dim.0, i as u32 / dim.0);
+        let (px, py) = (x0 + x as f32 * cell, top - (y + 1) as f32 * cell);
+        let (r, g, b) = rgb(palette[idx]);
+        c.set_fill_rgb(r, g, b);
+        c.rect(px, py, cell, cell);
+        c.fill_nonzero();
+
+        if font >= MIN_LABEL_SIZE {
+            let (r, g, b) = text_color(palette[idx]);
+            c.set_fill_rgb(r, g, b);
+            text(&mut c, px + cell * 0.15, py + cell * 0.3, font, &idx.to_string());
+        }
+    }
+
+    // Grid lines, bold every 10 cells
+    c.set_stroke_rgb(0.5, 0.5, 0.5);
+    for x in 0..=dim.0 {
+        c.set_line_width(if x % 10 == 0 { 0.8 } else { 0.2 });
+        let px = x0 + x as f32 * cell;
+        c.move_to(px, top).line_to(px, top - dim.1 as f32 * cell).stroke();
+    }
+    for y in 0..=dim.1 {
+        c.set_line_width(if y % 10 == 0 { 0.8 } else { 0.2 });
+        let py = top - y as f32 * cell;
+        c.move_to(x0, py).line_to(x0 + dim.0 as f32 * cell, py).stroke();
+    }
+
+    // Coordinates, 1-based like the board documentation
+    c.set_fill_rgb(0.0, 0.0, 0.0);
+    let step = if cell < 6.0 { 10 } else { 5 };
+    for x in (0..dim.0).filter(|x| (x + 1) % step == 0 || *x == 0) {
+        text(&mut c, x0 + x as f32 * cell, top + 3.0, 6.0, &(x + 1).to_string());
+    }
+    for y in (0..dim.1).filter(|y| (y + 1) % step == 0 || *y == 0) {
+        text(&mut c, MARGIN, top - (y + 1) as f32 * cell + 1.0, 6.0, &(y + 1).to_string());
+    }
+    c
+}
+
+/// Swatch, index, hex code and pixel count of every used palette color,
+/// continued on as many pages as needed
+fn legend_pages(palette: &[[u8; 3]], histogram: &[usize]) -> Vec<Content> {
+    let row = 18.0;
+    let top = PAGE_H - MARGIN - 2.0 * row;
+    let per_page = ((top - MARGIN) / row) as usize + 1;
+    let used: Vec<(usize, [u8; 3], usize)> = palette
+        .iter()
+        .zip(histogram)
+        .enumerate()
+        .filter(|(_, (_, &count))| count > 0)
+        .map(|(i, (&color, &count))| (i, color, count))
+        .collect();
+
+    // An empty legend still gets its titled page
+    let chunks: Vec<&[(usize, [u8; 3], usize)]> = if used.is_empty() {
+        vec![&[]]
+    } else {
+        used.chunks(per_page).collect()
+    };
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let mut c = Content::new();
+            c.set_fill_rgb(0.0, 0.0, 0.0);
+            text(&mut c, MARGIN, PAGE_H - MARGIN, 16.0, "Color Legend");
+            let mut y = top;
+            for &(i, color, count) in chunk {
+                let (r, g, b) = rgb(color);
+                c.set_fill_rgb(r, g, b);
+                c.set_stroke_rgb(0.0, 0.0, 0.0);
+                c.set_line_width(0.5);
+                c.rect(MARGIN, y, 24.0, 12.0);
+                c.fill_nonzero_and_stroke();
+
+                c.set_fill_rgb(0.0, 0.0, 0.0);
+                let hex = format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2]);
+                text(&mut c, MARGIN + 32.0, y + 2.0, 10.0, &format!("{i:>3}   {hex}   {count} px"));
+                y -= row;
+            }
+            c
+        })
+        .collect()
+}
+
+/// Renders a printable pattern: board mockup, numbered grid and color legend
+pub fn pattern_sheet(
+    title: &str,
+    indices: &[usize],
+    dim: (u32, u32),
+    palette: &[[u8; 3]],
+    histogram: &[usize],
+) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let mut pages = vec![
+        mockup_page(title, indices, dim, palette),
+        grid_page(indices, dim, palette),
+    ];
+    pages.extend(legend_pages(palette, histogram));
+    let page_ids: Vec<Ref> = (0..pages.len() as i32).map(|i| Ref::new(4 + i * 2)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    for (content, &page_id) in pages.into_iter().zip(&page_ids) {
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_W, PAGE_H));
+        page.parent(tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(FONT, font_id);
+        page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
@@ -0,0 +1,37 @@
+use image::{DynamicImage, RgbaImage};
+use serde_json::json;
+
+/// Packs frames row by row into one image with `columns` cells per row.
+/// Returns the sheet and a JSON atlas with the name and rect of every frame
+pub fn pack(frames: &[(String, &DynamicImage)], columns: u32) -> (RgbaImage, String) {
+    let columns = columns.clamp(1, frames.len().max(1) as u32);
+    let rows = (frames.len() as u32).div_ceil(columns);
+    // Cells fit the largest frame, so mixed board sizes still line up
+    let cw = frames.iter().map(|(_, f)| f.width()).max().unwrap_or(0);
+    let ch = frames.iter().map(|(_, f)| f.height()).max().unwrap_or(0);
+
+    let mut sheet = RgbaImage::new(cw * columns, ch * rows);
+    let mut atlas = vec![];
+    for (i, (name, frame)) in frames.iter().enumerate() {
+        let (x, y) = (i as u32 % columns * cw, i as u32 / columns * ch);
+        image::imageops::replace(&mut sheet, &frame.to_rgba8(), x as i64, y as i64);
+        atlas.push(json!({
+            "name": name,
+            "x": x,
+            "y": y,
+            "w": frame.width(),
+            "h": frame.height(),
+        }));
+    }
+
+    let atlas = json!({
+        "frames": atlas,
+        "meta": {
+            "width": sheet.width(),
+            "height": sheet.height(),
+            "columns": columns,
+            "rows": rows,
+        },
+    });
+    (sheet, serde_json::to_string_pretty(&atlas).expect("Atlas is always serializable"))
+}
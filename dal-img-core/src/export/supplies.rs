@@ -0,0 +1,98 @@
+//! Named colors of craft supplies, like embroidery floss or fuse beads, and
+//! the symbols text charts print for them
+
+use std::collections::BTreeMap;
+
+use anyhow::ensure;
+
+use crate::img_conv::ColorMetric;
+
+// Plain ASCII symbols for text charts, so they survive any editor or printer
+const CHART_SYMBOLS: &[u8] = b"#@%&*+=oxOX/\\|<>^v~$?!ABCDEFGHJKLMNPQRSTUVWYZ";
+
+/// One color of a manufacturer's range
+pub struct Supply {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub rgb: [u8; 3],
+}
+
+macro_rules! supply {
+    ($code:expr, $name:expr, $r:expr, $g:expr, $b:expr) => {
+        $crate::export::supplies::Supply {
+            code: $code,
+            name: $name,
+            rgb: [$r, $g, $b],
+        }
+    };
+}
+pub(crate) use supply;
+
+/// The color of `table` closest to `c` by CIEDE2000
+pub fn nearest(table: &'static [Supply], c: [u8; 3]) -> &'static Supply {
+    let metric = ColorMetric::Ciede2000;
+    let p = metric.coords(c);
+    table
+        .iter()
+        .min_by(|a, b| {
+            let da = metric.distance(&p, &metric.coords(a.rgb));
+            let db = metric.distance(&p, &metric.coords(b.rgb));
+            da.total_cmp(&db)
+        })
+        .expect("Supply table is not empty")
+}
+
+/// Supplies an image needs with a chart symbol each. Palette colors sharing
+/// the same supply are merged into one symbol
+pub struct Legend {
+    supplies: Vec<&'static Supply>,
+    // Supplies in order of first appearance, with their count
+    used: Vec<(&'static Supply, usize)>,
+    symbols: BTreeMap<&'static str, char>,
+}
+
+impl Legend {
+    /// Fails if the image needs more supplies than there are chart symbols
+    pub fn new(
+        table: &'static [Supply],
+        indices: &[usize],
+        palette: &[[u8; 3]],
+    ) -> anyhow::Result<Self> {
+        let supplies: Vec<&Supply> = palette.iter().map(|&c| nearest(table, c)).collect();
+        let mut used: Vec<(&Supply, usize)> = vec![];
+        for &i in indices {
+            let code = supplies[i].code;
+            match used.iter_mut().find(|(s, _)| s.code == code) {
+                Some((_, n)) => *n += 1,
+                None => used.push((supplies[i], 1)),
+            }
+        }
+        ensure!(
+            used.len() <= CHART_SYMBOLS.len(),
+            "A chart can show at most {} colors, this one needs {}",
+            CHART_SYMBOLS.len(),
+            used.len()
+        );
+        let symbols = used
+            .iter()
+            .zip(CHART_SYMBOLS)
+            .map(|((s, _), &c)| (s.code, c as char))
+            .collect();
+        Ok(Self { supplies, used, symbols })
+    }
+
+    /// Supply the palette entry `i` maps to
+    pub fn supply(&self, i: usize) -> &'static Supply {
+        self.supplies[i]
+    }
+
+    /// Chart symbol of palette entry `i`
+    pub fn symbol(&self, i: usize) -> char {
+        self.symbols.get(self.supplies[i].code).copied().unwrap_or(' ')
+    }
+
+    /// Symbol, supply and count of every supply the image uses
+    pub fn entries(&self) -> impl Iterator<Item = (char, &'static Supply, usize)> + '_ {
+        self.used.iter().map(|&(s, n)| (self.symbols[s.code], s, n))
+    }
+}
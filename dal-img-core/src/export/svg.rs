@@ -0,0 +1,41 @@
+use std::fmt::Write;
+
+// Size of one board pixel in SVG user units
+const CELL: u32 = 10;
+
+/// One `<rect>` per pixel in its palette color, transparent pixels are skipped.
+/// With `grid`, every cell gets a thin outline
+pub fn to_svg(
+    indices: &[usize],
+    dim: (u32, u32),
+    palette: &[[u8; 3]],
+    transparent: Option<&[bool]>,
+    grid: bool,
+) -> String {
+    let (w, h) = (dim.0 * CELL, dim.1 * CELL);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" shape-rendering=\"crispEdges\">\n"
+    );
+    if grid {
+        out.push_str("<g stroke=\"#808080\" stroke-width=\"0.5\">\n");
+    } else {
+        out.push_str("<g>\n");
+    }
+
+    for (i, &idx) in indices.iter().enumerate() {
+        if transparent.is_some_and(|t| t[i]) {
+            continue;
+        }
+        let c = palette[idx];
+        let (x, y) = (i as u32 % dim.0 * CELL, i as u32 / dim.0 * CELL);
+        writeln!(
+            out,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{CELL}\" height=\"{CELL}\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+            c[0], c[1], c[2]
+        )
+        .expect("Writing to a String never fails");
+    }
+
+    out.push_str("</g>\n</svg>\n");
+    out
+}
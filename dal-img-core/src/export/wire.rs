@@ -0,0 +1,51 @@
+//! Frame format boards receive over serial, USB and similar byte streams.
+//!
+//! All integers are little-endian:
+//!
+//! | Offset      | Size    | Field                                             |
+//! |-------------|---------|---------------------------------------------------|
+//! | 0           | 4       | Magic `DALF`                                      |
+//! | 4           | 1       | Format version, currently 1                       |
+//! | 5           | 2       | Width in pixels                                   |
+//! | 7           | 2       | Height in pixels                                  |
+//! | 9           | w × h   | Palette indices, one byte per pixel, row-major    |
+//! | 9 + w × h   | 4       | CRC-32 (IEEE) of every byte before it             |
+//!
+//! A board should drop frames with a wrong magic, version, size or checksum.
+
+use anyhow::ensure;
+
+pub const MAGIC: &[u8; 4] = b"DALF";
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 9;
+
+/// CRC-32 as used by zlib and PNG, bitwise so firmware can port it verbatim
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Wraps row-major palette indices into a frame
+pub fn encode_frame(indices: &[usize], dim: (u32, u32)) -> anyhow::Result<Vec<u8>> {
+    ensure!(
+        dim.0 <= u16::MAX.into() && dim.1 <= u16::MAX.into(),
+        "Frames are at most 65535 pixels wide and high"
+    );
+    ensure!(indices.len() == (dim.0 * dim.1) as usize, "Index count doesn't match the size");
+    ensure!(indices.iter().all(|&i| i < 256), "Frames support at most 256 colors");
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + indices.len() + 4);
+    frame.extend(MAGIC);
+    frame.push(VERSION);
+    frame.extend((dim.0 as u16).to_le_bytes());
+    frame.extend((dim.1 as u16).to_le_bytes());
+    frame.extend(indices.iter().map(|&i| i as u8));
+    frame.extend(crc32(&frame).to_le_bytes());
+    Ok(frame)
+}
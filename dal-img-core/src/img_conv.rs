@@ -0,0 +1,1189 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+};
+use image::{
+    codecs::{gif::GifDecoder, webp::WebPDecoder},
+    imageops::FilterType,
+    AnimationDecoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader,
+};
+use kiddo::{KdTree, SquaredEuclidean};
+use serde::{Deserialize, Serialize};
+
+pub mod adjust;
+pub mod cleanup;
+pub mod dither;
+pub mod metric;
+pub mod palettes;
+pub mod presets;
+pub mod quality;
+pub mod quantize;
+pub mod regions;
+pub mod stepwise;
+pub mod subset;
+
+pub use adjust::{
+    Adjustment, AutoEnhance, Curve, Denoise, DenoiseMode, Equalize, EqualizeMode, Levels, Noise,
+    NoiseKind, Saturation, Sharpen, Tone,
+};
+pub use dither::{BayerSize, DitherKernel};
+pub use metric::ColorMetric;
+pub use presets::DevicePreset;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dither {
+    #[default]
+    None,
+    ErrorDiffusion,
+    Ordered,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 3] = [Dither::None, Dither::ErrorDiffusion, Dither::Ordered];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dither::None => "None",
+            Dither::ErrorDiffusion => "Error Diffusion",
+            Dither::Ordered => "Ordered (Bayer)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FitMode {
+    /// Scale to the exact target size, distorting the aspect ratio
+    #[default]
+    Stretch,
+    /// Scale to fit inside the target, padding with the fill color
+    Letterbox,
+    /// Scale to cover the target, cropping the overflow
+    CoverCrop,
+    /// Keep the original scale, centered on the fill color
+    Center,
+}
+
+impl FitMode {
+    pub const ALL: [FitMode; 4] = [
+        FitMode::Stretch,
+        FitMode::Letterbox,
+        FitMode::CoverCrop,
+        FitMode::Center,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FitMode::Stretch => "Stretch",
+            FitMode::Letterbox => "Letterbox",
+            FitMode::CoverCrop => "Crop to Fill",
+            FitMode::Center => "Center",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    pub const ALL: [ResizeFilter; 4] = [
+        ResizeFilter::Nearest,
+        ResizeFilter::Triangle,
+        ResizeFilter::CatmullRom,
+        ResizeFilter::Lanczos3,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResizeFilter::Nearest => "Nearest",
+            ResizeFilter::Triangle => "Triangle",
+            ResizeFilter::CatmullRom => "Catmull-Rom",
+            ResizeFilter::Lanczos3 => "Lanczos3",
+        }
+    }
+
+    pub fn filter_type(&self) -> FilterType {
+        match self {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PixelArtMode {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl PixelArtMode {
+    pub const ALL: [PixelArtMode; 3] = [PixelArtMode::Auto, PixelArtMode::On, PixelArtMode::Off];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PixelArtMode::Auto => "Auto",
+            PixelArtMode::On => "On",
+            PixelArtMode::Off => "Off",
+        }
+    }
+}
+
+// Sources with at most this many colors and at most twice the target size count as pixel art
+const PIXEL_ART_MAX_COLORS: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlphaMode {
+    /// Pixels below the alpha threshold become the transparent color
+    #[default]
+    Threshold,
+    /// Blend over the background color before quantization
+    Composite,
+    /// Like threshold, but transparent pixels keep alpha 0 in the output
+    Keep,
+}
+
+impl AlphaMode {
+    pub const ALL: [AlphaMode; 3] = [AlphaMode::Threshold, AlphaMode::Composite, AlphaMode::Keep];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlphaMode::Threshold => "Threshold",
+            AlphaMode::Composite => "Composite",
+            AlphaMode::Keep => "Keep Transparency",
+        }
+    }
+}
+
+/// Manual per-image orientation, applied before any other processing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transform {
+    /// Clockwise quarter turns
+    pub quarter_turns: u8,
+    pub flip_h: bool,
+    pub flip_v: bool,
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn rotated(self, quarter_turns: u8) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + quarter_turns) % 4,
+            ..self
+        }
+    }
+
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        let img = match self.quarter_turns % 4 {
+            1 => img.rotate90(),
+            2 => img.rotate180(),
+            3 => img.rotate270(),
+            _ => img,
+        };
+        let img = if self.flip_h { img.fliph() } else { img };
+        if self.flip_v {
+            img.flipv()
+        } else {
+            img
+        }
+    }
+}
+
+// Settings saved by older versions lack newer fields, those take their defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvSettings {
+    pub auto_rotate: bool,
+    pub dither: Dither,
+    pub kernel: DitherKernel,
+    pub serpentine: bool,
+    pub bayer_size: BayerSize,
+    pub linear_light: bool,
+    pub fit: FitMode,
+    pub fill_color: [u8; 3],
+    pub filter: ResizeFilter,
+    pub pixel_art: PixelArtMode,
+    /// Columns and rows of boards the image is split across
+    pub tiles: (u32, u32),
+    /// Palette color transparent pixels are replaced with
+    pub transparent_color: [u8; 3],
+    pub alpha_mode: AlphaMode,
+    /// Minimum alpha for a pixel to count as opaque in threshold mode
+    pub alpha_threshold: u8,
+    pub alpha_background: [u8; 3],
+    pub levels: Levels,
+    pub curve: Curve,
+    pub denoise: Denoise,
+    pub auto_enhance: AutoEnhance,
+    pub equalize: Equalize,
+    pub tone: Tone,
+    pub saturation: Saturation,
+    pub sharpen: Sharpen,
+    pub noise: Noise,
+    /// Replace single pixels without a same colored neighbor after mapping
+    pub despeckle: bool,
+    /// Connected areas of one color with fewer pixels are merged into their
+    /// largest neighbor after mapping, 0 or 1 keeps everything
+    pub min_region: u32,
+    /// Most palette colors one image may use, picked per image so they
+    /// represent it best. 0 allows every enabled color
+    pub max_colors: u32,
+}
+
+impl Default for ConvSettings {
+    fn default() -> Self {
+        Self {
+            auto_rotate: true,
+            dither: Dither::None,
+            kernel: DitherKernel::default(),
+            serpentine: false,
+            bayer_size: BayerSize::default(),
+            linear_light: false,
+            fit: FitMode::default(),
+            fill_color: [0, 0, 0],
+            filter: ResizeFilter::default(),
+            pixel_art: PixelArtMode::default(),
+            tiles: (1, 1),
+            transparent_color: [0, 0, 0],
+            alpha_mode: AlphaMode::default(),
+            alpha_threshold: 255,
+            alpha_background: [255, 255, 255],
+            levels: Levels::default(),
+            curve: Curve::default(),
+            denoise: Denoise::default(),
+            auto_enhance: AutoEnhance::default(),
+            equalize: Equalize::default(),
+            tone: Tone::default(),
+            saturation: Saturation::default(),
+            sharpen: Sharpen::default(),
+            noise: Noise::default(),
+            despeckle: false,
+            min_region: 0,
+            max_colors: 0,
+        }
+    }
+}
+
+impl ConvSettings {
+    /// Steps between resizing and quantization, in the order they run.
+    /// Denoise runs first, so neither contrast nor sharpening amplify the noise,
+    /// and the automatic corrections come before the manual ones that refine them.
+    /// Added noise runs last, so nothing smooths it away again
+    pub fn adjustments(&self) -> [&dyn Adjustment; 9] {
+        [
+            &self.denoise,
+            &self.auto_enhance,
+            &self.equalize,
+            &self.tone,
+            &self.levels,
+            &self.curve,
+            &self.saturation,
+            &self.sharpen,
+            &self.noise,
+        ]
+    }
+
+    /// Whether any adjustment changes the resized image
+    pub fn has_adjustments(&self) -> bool {
+        self.adjustments().iter().any(|stage| !stage.is_identity())
+    }
+
+    /// The same settings with every adjustment turned off
+    pub fn without_adjustments(&self) -> Self {
+        Self {
+            levels: Levels::default(),
+            curve: Curve::default(),
+            denoise: Denoise::default(),
+            auto_enhance: AutoEnhance::default(),
+            equalize: Equalize::default(),
+            tone: Tone::default(),
+            saturation: Saturation::default(),
+            sharpen: Sharpen::default(),
+            noise: Noise::default(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DalImageConverter {
+    tree: KdTree<f32, 3>,
+    palette: Vec<[u8; 3]>,
+    // Colors excluded from matching keep their index, so exports stay stable
+    enabled: Vec<bool>,
+    // Added to the distance of each palette entry, so it's only picked when clearly closest
+    penalties: Vec<f32>,
+    // Enabled palette indices with their color in the coordinate space of `metric`
+    coords: Vec<(usize, [f32; 3])>,
+    metric: ColorMetric,
+    // Optional RGB -> palette index lookup table, LUT_BITS per channel
+    lut: Option<Vec<u8>>,
+    dim: (u32, u32),
+}
+
+// Rows each parallel worker maps at a time, sharing one color cache
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const PARALLEL_ROWS: usize = 16;
+
+const LUT_BITS: u32 = 5;
+const LUT_SIZE: usize = 1 << LUT_BITS;
+
+// Decode an sRGB channel to linear light, scaled to 0..255
+fn srgb_to_linear(v: f32) -> f32 {
+    let v = v / 255.0;
+    let l = if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    };
+    l * 255.0
+}
+
+// Encode a linear-light channel (0..255) back to sRGB
+fn linear_to_srgb(v: f32) -> f32 {
+    let v = (v / 255.0).clamp(0.0, 1.0);
+    let s = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    s * 255.0
+}
+
+// Working buffer for error diffusion, so the diffused error isn't clamped away
+// between pixels. Error accumulates in linear light when requested, else in sRGB
+fn diffusion_buffer(img: &image::RgbImage, linear_light: bool) -> Vec<[f32; 3]> {
+    let decode = |v: f32| if linear_light { srgb_to_linear(v) } else { v };
+    img.pixels().map(|px| px.0.map(|v| decode(v as f32))).collect()
+}
+
+// Colors of an image with transparency as they go into the palette mapping
+fn alpha_fill(img: &image::RgbaImage, trans_color: [u8; 3], settings: &ConvSettings) -> image::RgbImage {
+    let opaque = |a: u8| a >= settings.alpha_threshold.max(1);
+    image::RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let px = img.get_pixel(x, y).0;
+        image::Rgb(match settings.alpha_mode {
+            AlphaMode::Threshold | AlphaMode::Keep if opaque(px[3]) => [px[0], px[1], px[2]],
+            // Pre-fill transparent pixels, so dithering doesn't diffuse their hidden color
+            AlphaMode::Threshold | AlphaMode::Keep => trans_color,
+            AlphaMode::Composite => {
+                let a = px[3] as f32 / 255.0;
+                let bg = settings.alpha_background;
+                [0, 1, 2].map(|i| (px[i] as f32 * a + bg[i] as f32 * (1.0 - a)).round() as u8)
+            }
+        })
+    })
+}
+
+// Writes the mapped colors back into the image with transparency
+fn alpha_restore(
+    img: &mut image::RgbaImage,
+    rgb: &image::RgbImage,
+    trans_color: [u8; 3],
+    settings: &ConvSettings,
+) {
+    let opaque = |a: u8| a >= settings.alpha_threshold.max(1);
+    for (px, c) in img.pixels_mut().zip(rgb.pixels()) {
+        let (c, a) = match settings.alpha_mode {
+            AlphaMode::Threshold if !opaque(px.0[3]) => (trans_color, 255),
+            AlphaMode::Keep if !opaque(px.0[3]) => (trans_color, 0),
+            _ => (c.0, 255),
+        };
+        px.0 = [c[0], c[1], c[2], a];
+    }
+}
+
+// Cuts a converted wall into one image per board, row by row
+fn cut_tiles(
+    reference: &DynamicImage,
+    img: &DynamicImage,
+    tiles: (u32, u32),
+    (tw, th): (u32, u32),
+) -> Vec<((u32, u32), DynamicImage, DynamicImage)> {
+    let (cols, rows) = tiles;
+    let mut out = Vec::with_capacity((cols * rows) as usize);
+    for r in 0..rows {
+        for c in 0..cols {
+            out.push((
+                (r, c),
+                reference.crop_imm(c * tw, r * th, tw, th),
+                img.crop_imm(c * tw, r * th, tw, th),
+            ));
+        }
+    }
+    out
+}
+
+/// Decodes an image and applies its EXIF orientation, so phone photos
+/// come out upright before any auto rotation
+pub fn load_image(buf: &[u8]) -> anyhow::Result<DynamicImage> {
+    let mut decoder = ImageReader::new(std::io::Cursor::new(buf))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Decodes every frame of an animated GIF or WebP together with its delay in
+/// milliseconds. Still images come back as a single frame with no delay.
+pub fn load_frames(buf: &[u8]) -> anyhow::Result<Vec<(DynamicImage, u32)>> {
+    let cursor = std::io::Cursor::new(buf);
+    let frames = match ImageReader::new(cursor.clone()).with_guessed_format()?.format() {
+        Some(ImageFormat::Gif) => GifDecoder::new(cursor)?.into_frames().collect_frames()?,
+        Some(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(cursor)?;
+            if !decoder.has_animation() {
+                return Ok(vec![(load_image(buf)?, 0)]);
+            }
+            decoder.into_frames().collect_frames()?
+        }
+        _ => return Ok(vec![(load_image(buf)?, 0)]),
+    };
+
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let (num, denom) = frame.delay().numer_denom_ms();
+            let delay = num / denom.max(1);
+            (DynamicImage::ImageRgba8(frame.into_buffer()), delay)
+        })
+        .collect())
+}
+
+fn resample(img: DynamicImage, w: u32, h: u32, settings: &ConvSettings) -> DynamicImage {
+    let filter = settings.filter.filter_type();
+    if !settings.linear_light {
+        return img.resize_exact(w, h, filter);
+    }
+
+    // Resample in linear light, so averaged pixels keep their brightness
+    let mut buf = img.to_rgba32f();
+    for px in buf.pixels_mut() {
+        for c in &mut px.0[..3] {
+            *c = srgb_to_linear(*c * 255.0) / 255.0;
+        }
+    }
+    let mut buf = image::imageops::resize(&buf, w, h, filter);
+    for px in buf.pixels_mut() {
+        for c in &mut px.0[..3] {
+            *c = linear_to_srgb(*c * 255.0) / 255.0;
+        }
+    }
+
+    DynamicImage::ImageRgba32F(buf)
+}
+
+/// Runs the resized image through every adjustment before quantization
+pub fn prefilter(img: DynamicImage, settings: &ConvSettings) -> DynamicImage {
+    settings
+        .adjustments()
+        .into_iter()
+        .filter(|stage| !stage.is_identity())
+        .fold(img, |img, stage| stage.apply(img))
+}
+
+// Memoizes nearest-color lookups for the duration of one conversion pass
+struct NearestCache<'a> {
+    conv: &'a DalImageConverter,
+    map: HashMap<[u8; 3], [u8; 3]>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<'a> NearestCache<'a> {
+    fn new(conv: &'a DalImageConverter) -> Self {
+        Self {
+            conv,
+            map: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, rgb: [u8; 3]) -> [u8; 3] {
+        if let Some(&c) = self.map.get(&rgb) {
+            self.hits += 1;
+            return c;
+        }
+
+        self.misses += 1;
+        let c = self.conv.get_nearest(rgb);
+        self.map.insert(rgb, c);
+        c
+    }
+}
+
+impl DalImageConverter {
+    pub fn new(palette: &[[u8; 3]], dim: (u32, u32)) -> Self {
+        Self::with_metric(palette, dim, ColorMetric::default())
+    }
+
+    pub fn with_metric(palette: &[[u8; 3]], dim: (u32, u32), metric: ColorMetric) -> Self {
+        let mut conv = Self {
+            tree: KdTree::new(),
+            palette: palette.to_vec(),
+            enabled: vec![true; palette.len()],
+            penalties: vec![0.0; palette.len()],
+            coords: vec![],
+            metric,
+            lut: None,
+            dim,
+        };
+        conv.rebuild(false);
+        conv
+    }
+
+    /// Like `with_metric`, but precomputes a 32x32x32 lookup table so
+    /// per-pixel mapping is a single array access
+    pub fn with_lut(palette: &[[u8; 3]], dim: (u32, u32), metric: ColorMetric) -> Self {
+        let mut conv = Self::with_metric(palette, dim, metric);
+        conv.rebuild(true);
+        conv
+    }
+
+    // Recomputes the lookup structures from the palette, enabled mask and metric
+    fn rebuild(&mut self, lut: bool) {
+        self.coords = self
+            .palette
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.enabled[i])
+            .map(|(i, &c)| (i, self.metric.coords(c)))
+            .collect();
+        self.tree = KdTree::new();
+        for (i, c) in &self.coords {
+            self.tree.add(c, *i as u64);
+        }
+
+        self.lut = None;
+        // Indices are stored as u8, larger palettes fall back to the direct search
+        if !lut || self.palette.len() > 256 {
+            return;
+        }
+
+        let shift = 8 - LUT_BITS;
+        let center = 1u8 << (shift - 1);
+        let mut table = Vec::with_capacity(LUT_SIZE * LUT_SIZE * LUT_SIZE);
+        for r in 0..LUT_SIZE {
+            for g in 0..LUT_SIZE {
+                for b in 0..LUT_SIZE {
+                    let rgb = [r, g, b].map(|v| ((v as u8) << shift) + center);
+                    table.push(self.nearest_index(rgb) as u8);
+                }
+            }
+        }
+        self.lut = Some(table);
+    }
+
+    /// A copy that searches the palette for every color instead of using
+    /// the quantized lookup table
+    pub fn without_lut(&self) -> Self {
+        let mut conv = self.clone();
+        conv.rebuild(false);
+        conv
+    }
+
+    /// Replaces the palette, keeping dimensions and metric. The enabled
+    /// mask and penalties are kept as long as the palette size doesn't change
+    pub fn set_palette(&mut self, palette: &[[u8; 3]]) {
+        if palette.len() != self.palette.len() {
+            self.enabled = vec![true; palette.len()];
+            self.penalties = vec![0.0; palette.len()];
+        }
+        self.palette = palette.to_vec();
+        self.rebuild(self.lut.is_some());
+    }
+
+    /// Excludes palette entries from matching, at least one has to stay enabled
+    pub fn set_enabled(&mut self, enabled: &[bool]) {
+        assert_eq!(enabled.len(), self.palette.len());
+        assert!(enabled.iter().any(|&e| e), "No palette color enabled");
+        self.enabled = enabled.to_vec();
+        self.rebuild(self.lut.is_some());
+    }
+
+    /// Sets a penalty per palette entry that is added to its color distance,
+    /// in ΔE or the closest equivalent of the metric. 0 treats it normally
+    pub fn set_penalties(&mut self, penalties: &[f32]) {
+        assert_eq!(penalties.len(), self.palette.len());
+        self.penalties = penalties.iter().map(|p| p.max(0.0)).collect();
+        self.rebuild(self.lut.is_some());
+    }
+
+    pub fn set_dim(&mut self, dim: (u32, u32)) {
+        self.dim = (dim.0.max(1), dim.1.max(1));
+    }
+
+    /// Switches dimensions and palette in one go
+    pub fn set_device(&mut self, device: &DevicePreset) {
+        self.set_dim(device.dim);
+        self.set_palette(device.palette);
+    }
+
+    pub fn set_metric(&mut self, metric: ColorMetric) {
+        self.metric = metric;
+        self.rebuild(self.lut.is_some());
+    }
+
+    pub fn palette(&self) -> &[[u8; 3]] {
+        &self.palette
+    }
+
+    pub fn enabled(&self) -> &[bool] {
+        &self.enabled
+    }
+
+    pub fn penalties(&self) -> &[f32] {
+        &self.penalties
+    }
+
+    pub fn dim(&self) -> (u32, u32) {
+        self.dim
+    }
+
+    pub fn metric(&self) -> ColorMetric {
+        self.metric
+    }
+
+    fn nearest_index(&self, rgb: [u8; 3]) -> usize {
+        if let Some(lut) = &self.lut {
+            let [r, g, b] = rgb.map(|v| (v >> (8 - LUT_BITS)) as usize);
+            return lut[(r * LUT_SIZE + g) * LUT_SIZE + b] as usize;
+        }
+
+        // Enabled palette colors map to themselves, whatever their penalty
+        if let Some((i, _)) = self.coords.iter().find(|(i, _)| self.palette[*i] == rgb) {
+            return *i;
+        }
+
+        let p = self.metric.coords(rgb);
+        let penalized = self.penalties.iter().any(|&p| p > 0.0);
+        // The kd-tree is only exact for euclidean metrics without penalties,
+        // scan the palette otherwise
+        if self.metric.is_euclidean() && !penalized {
+            return self.tree.nearest_one::<SquaredEuclidean>(&p).item as usize;
+        }
+
+        let scale = self.metric.penalty_scale();
+        self.coords
+            .iter()
+            .map(|(i, c)| (*i, self.metric.distance(&p, c) + self.penalties[*i] * scale))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+            .expect("Empty palette")
+    }
+
+    fn get_nearest(&self, rgb: [u8; 3]) -> [u8; 3] {
+        self.palette[self.nearest_index(rgb)]
+    }
+
+    /// Palette index of every pixel of a converted image, row-major.
+    /// Colors that aren't part of the palette map to their nearest entry
+    pub fn indices(&self, img: &image::RgbImage) -> Vec<usize> {
+        let mut lookup: HashMap<[u8; 3], usize> = HashMap::new();
+        // Iterate backwards, so duplicate colors resolve to their first index
+        for (i, &c) in self.palette.iter().enumerate().rev() {
+            lookup.insert(c, i);
+        }
+
+        img.pixels()
+            .map(|px| match lookup.get(&px.0) {
+                Some(&i) => i,
+                None => self.nearest_index(px.0),
+            })
+            .collect()
+    }
+
+    /// A copy that only maps to the `max` enabled colors which represent `img`
+    /// with the least total distance. Fully transparent pixels don't count,
+    /// without any others the copy keeps every enabled color
+    pub fn limit_colors(&self, img: &DynamicImage, max: usize) -> Self {
+        // Sorted, so ties resolve the same way every time
+        let mut counts: BTreeMap<[u8; 3], f32> = BTreeMap::new();
+        for px in img.to_rgba8().pixels().filter(|px| px.0[3] > 0) {
+            let [r, g, b, _] = px.0;
+            *counts.entry([r, g, b]).or_default() += 1.0;
+        }
+        let scale = self.metric.penalty_scale();
+        let (costs, weights): (Vec<Vec<f32>>, Vec<f32>) = counts
+            .into_iter()
+            .map(|(rgb, n)| {
+                let p = self.metric.coords(rgb);
+                let row = self
+                    .coords
+                    .iter()
+                    .map(|(i, c)| self.metric.distance(&p, c) + self.penalties[*i] * scale)
+                    .collect();
+                (row, n)
+            })
+            .unzip();
+
+        // Nothing visible to choose for, e.g. a fully transparent image
+        let chosen = subset::best_subset(&costs, &weights, max.max(1));
+        if costs.is_empty() || chosen.is_empty() {
+            return self.clone();
+        }
+        let mut enabled = vec![false; self.palette.len()];
+        for c in chosen {
+            enabled[self.coords[c].0] = true;
+        }
+        // Used for a single image, filling a lookup table would cost far more
+        // than the nearest searches it saves
+        let mut conv = self.without_lut();
+        conv.set_enabled(&enabled);
+        conv
+    }
+
+    /// Counts how often each palette entry is used in a converted image
+    pub fn histogram(&self, img: &image::RgbImage) -> Vec<usize> {
+        let mut counts = vec![0; self.palette.len()];
+        for i in self.indices(img) {
+            counts[i] += 1;
+        }
+        counts
+    }
+
+    pub fn resize_and_rotate(&self, img: DynamicImage, settings: &ConvSettings) -> DynamicImage {
+        // If width is smaller than height, rotate the image
+        let img = if img.width() < img.height() && settings.auto_rotate {
+            img.rotate90()
+        } else {
+            img
+        };
+
+        let (tw, th) = self.dim;
+        let (w, h) = (img.width() as f32, img.height() as f32);
+        match settings.fit {
+            FitMode::Stretch => resample(img, tw, th, settings),
+            FitMode::CoverCrop => {
+                let scale = (tw as f32 / w).max(th as f32 / h);
+                let sw = ((w * scale).round() as u32).max(tw);
+                let sh = ((h * scale).round() as u32).max(th);
+                resample(img, sw, sh, settings).crop_imm(
+                    (sw - tw) / 2,
+                    (sh - th) / 2,
+                    tw,
+                    th,
+                )
+            }
+            FitMode::Letterbox => {
+                let scale = (tw as f32 / w).min(th as f32 / h);
+                let sw = ((w * scale).round() as u32).clamp(1, tw);
+                let sh = ((h * scale).round() as u32).clamp(1, th);
+                let img = resample(img, sw, sh, settings);
+                self.pad(&img, settings.fill_color)
+            }
+            FitMode::Center => self.pad(&img, settings.fill_color),
+        }
+    }
+
+    // Centers an image on a canvas of the target size, cropping what doesn't fit
+    fn pad(&self, img: &DynamicImage, fill: [u8; 3]) -> DynamicImage {
+        let (tw, th) = self.dim;
+        let mut canvas =
+            image::RgbaImage::from_pixel(tw, th, image::Rgba([fill[0], fill[1], fill[2], 255]));
+        let x = (tw as i64 - img.width() as i64) / 2;
+        let y = (th as i64 - img.height() as i64) / 2;
+        image::imageops::overlay(&mut canvas, &img.to_rgba8(), x, y);
+        DynamicImage::ImageRgba8(canvas)
+    }
+
+    pub fn convert(&self, mut img: image::RgbImage, settings: &ConvSettings) -> image::RgbImage {
+        // Error diffusion carries state from pixel to pixel, everything else maps independently
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if settings.dither != Dither::ErrorDiffusion {
+            return self.convert_parallel(img, settings);
+        }
+
+        let mut cache = NearestCache::new(self);
+        let mut diffusion = None;
+        let rows = 0..img.height();
+        self.map_rows(&mut img, rows, settings, &mut cache, &mut diffusion);
+        tracing::debug!(
+            "color cache: {} hits, {} misses, {} distinct",
+            cache.hits,
+            cache.misses,
+            cache.map.len()
+        );
+
+        img
+    }
+
+    // Maps bands of rows on all cores, each band with its own color cache
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn convert_parallel(&self, mut img: image::RgbImage, settings: &ConvSettings) -> image::RgbImage {
+        use rayon::prelude::*;
+
+        let w = img.width() as usize;
+        let ordered = settings.dither == Dither::Ordered;
+        let n = settings.bayer_size.size();
+        let matrix = dither::bayer_matrix(settings.bayer_size);
+        img.par_chunks_mut(w * 3 * PARALLEL_ROWS)
+            .enumerate()
+            .for_each(|(band, chunk)| {
+                let mut cache = NearestCache::new(self);
+                for (i, px) in chunk.chunks_exact_mut(3).enumerate() {
+                    let (x, y) = (i % w, band * PARALLEL_ROWS + i / w);
+                    let mut c = [px[0], px[1], px[2]];
+                    if ordered {
+                        let t = matrix[(y % n) * n + x % n] * dither::ORDERED_SPREAD;
+                        c = c.map(|v| (v as f32 + t).round().clamp(0.0, 255.0) as u8);
+                    }
+                    px.copy_from_slice(&cache.get(c));
+                }
+            });
+
+        img
+    }
+
+    // Maps `rows` of the image to the palette. Error diffusion keeps its working
+    // buffer in `diffusion` between calls, so an image can be mapped band by band
+    fn map_rows(
+        &self,
+        img: &mut image::RgbImage,
+        rows: Range<u32>,
+        settings: &ConvSettings,
+        cache: &mut NearestCache,
+        diffusion: &mut Option<Vec<[f32; 3]>>,
+    ) {
+        match settings.dither {
+            Dither::None => self.convert_nearest(img, rows, cache),
+            Dither::ErrorDiffusion => {
+                let buf =
+                    diffusion.get_or_insert_with(|| diffusion_buffer(img, settings.linear_light));
+                self.convert_error_diffusion(img, rows, buf, cache, settings)
+            }
+            Dither::Ordered => self.convert_ordered(img, rows, cache, settings.bayer_size),
+        }
+    }
+
+    fn convert_nearest(
+        &self,
+        img: &mut image::RgbImage,
+        rows: Range<u32>,
+        cache: &mut NearestCache,
+    ) {
+        for y in rows {
+            for x in 0..img.width() {
+                let px = img.get_pixel_mut(x, y);
+                px.0 = cache.get(px.0);
+            }
+        }
+    }
+
+    fn convert_error_diffusion(
+        &self,
+        img: &mut image::RgbImage,
+        rows: Range<u32>,
+        buf: &mut [[f32; 3]],
+        cache: &mut NearestCache,
+        settings: &ConvSettings,
+    ) {
+        let (w, h) = (img.width() as usize, img.height() as usize);
+        let (weights, div) = settings.kernel.weights();
+        let (serpentine, linear_light) = (settings.serpentine, settings.linear_light);
+        let decode = |v: f32| if linear_light { srgb_to_linear(v) } else { v };
+        let encode = |v: f32| if linear_light { linear_to_srgb(v) } else { v };
+
+        for y in rows.start as usize..rows.end as usize {
+            // Serpentine scanning walks every other row right-to-left and mirrors the kernel
+            let reverse = serpentine && y % 2 == 1;
+            for i in 0..w {
+                let x = if reverse { w - 1 - i } else { i };
+                let old = buf[y * w + x];
+                let c = cache.get(old.map(|v| encode(v).round().clamp(0.0, 255.0) as u8));
+                let err = [
+                    old[0] - decode(c[0] as f32),
+                    old[1] - decode(c[1] as f32),
+                    old[2] - decode(c[2] as f32),
+                ];
+                img.get_pixel_mut(x as u32, y as u32).0 = c;
+
+                for &(dx, dy, f) in weights {
+                    let dx = if reverse { -dx } else { dx };
+                    let nx = x as isize + dx;
+                    let ny = y + dy;
+                    if nx < 0 || nx as usize >= w || ny >= h {
+                        continue;
+                    }
+                    let p = &mut buf[ny * w + nx as usize];
+                    for c in 0..3 {
+                        p[c] += err[c] * f / div;
+                    }
+                }
+            }
+        }
+    }
+
+    fn convert_ordered(
+        &self,
+        img: &mut image::RgbImage,
+        rows: Range<u32>,
+        cache: &mut NearestCache,
+        size: BayerSize,
+    ) {
+        let n = size.size();
+        let matrix = dither::bayer_matrix(size);
+        for y in rows {
+            for x in 0..img.width() {
+                let px = img.get_pixel_mut(x, y);
+                let t = matrix[(y as usize % n) * n + x as usize % n] * dither::ORDERED_SPREAD;
+                let c = px.0.map(|v| (v as f32 + t).round().clamp(0.0, 255.0) as u8);
+                px.0 = cache.get(c);
+            }
+        }
+    }
+
+    /// Converts an image with transparency according to `settings.alpha_mode`.
+    /// Only `AlphaMode::Keep` leaves pixels with an alpha other than 255
+    pub fn convert_alpha(
+        &self,
+        mut img: image::RgbaImage,
+        trans_color: [u8; 3],
+        settings: &ConvSettings,
+    ) -> image::RgbaImage {
+        let rgb = alpha_fill(&img, trans_color, settings);
+        let rgb = self.convert(rgb, settings);
+        alpha_restore(&mut img, &rgb, trans_color, settings);
+        img
+    }
+
+    // Runs the cleanup passes on the palette indices of a converted image
+    fn postprocess(&self, img: DynamicImage, settings: &ConvSettings) -> DynamicImage {
+        if !settings.despeckle && settings.min_region <= 1 {
+            return img;
+        }
+        let dim = (img.width(), img.height());
+        let had_alpha = img.color().has_alpha();
+        let mut rgba = img.to_rgba8();
+        let mut indices = self.indices(&DynamicImage::ImageRgba8(rgba.clone()).to_rgb8());
+        // Kept transparent pixels carry no palette color
+        let skip: Vec<bool> = rgba.pixels().map(|px| px.0[3] == 0).collect();
+        if settings.despeckle {
+            cleanup::despeckle(&mut indices, dim, &skip);
+        }
+        if settings.min_region > 1 {
+            cleanup::merge_small_regions(&mut indices, dim, settings.min_region as usize, &skip);
+        }
+
+        for ((px, &i), &skip) in rgba.pixels_mut().zip(&indices).zip(&skip) {
+            if !skip {
+                let [r, g, b] = self.palette[i];
+                px.0 = [r, g, b, px.0[3]];
+            }
+        }
+        if had_alpha {
+            DynamicImage::ImageRgba8(rgba)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba).to_rgb8())
+        }
+    }
+
+    /// Detects low resolution sources with few colors, like sprites
+    pub fn is_pixel_art(&self, img: &DynamicImage) -> bool {
+        let (w, h) = (img.width().min(img.height()), img.width().max(img.height()));
+        let (tw, th) = (self.dim.0.min(self.dim.1), self.dim.0.max(self.dim.1));
+        if w > tw * 2 || h > th * 2 {
+            return false;
+        }
+
+        let mut colors = HashSet::new();
+        for px in img.to_rgb8().pixels() {
+            colors.insert(px.0);
+            if colors.len() > PIXEL_ART_MAX_COLORS {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Scales by a whole factor with nearest neighbor, so every source pixel
+    // stays a crisp block, then centers the result on the target
+    fn resize_integer(&self, img: DynamicImage, settings: &ConvSettings) -> DynamicImage {
+        let img = if img.width() < img.height() && settings.auto_rotate {
+            img.rotate90()
+        } else {
+            img
+        };
+
+        let (tw, th) = self.dim;
+        let (w, h) = (img.width(), img.height());
+        let img = if w <= tw && h <= th {
+            let k = (tw / w).min(th / h).max(1);
+            img.resize_exact(w * k, h * k, FilterType::Nearest)
+        } else {
+            let k = w.div_ceil(tw).max(h.div_ceil(th));
+            img.resize_exact((w / k).max(1), (h / k).max(1), FilterType::Nearest)
+        };
+        self.pad(&img, settings.fill_color)
+    }
+
+    /// Converts the image for a wall of `settings.tiles` boards and cuts it into
+    /// one image per board, returned row by row as ((row, col), reference, image),
+    /// see `process_with_reference`.
+    /// The whole wall is converted at once, so dithering is seamless across tiles
+    pub fn process_tiled(
+        &self,
+        img: DynamicImage,
+        settings: &ConvSettings,
+    ) -> Vec<((u32, u32), DynamicImage, DynamicImage)> {
+        let tiles = (settings.tiles.0.max(1), settings.tiles.1.max(1));
+        let (reference, img) = self.wall(tiles).process_with_reference(img, settings);
+        cut_tiles(&reference, &img, tiles, self.dim)
+    }
+
+    // The converter for a wall of (columns, rows) boards
+    fn wall(&self, (cols, rows): (u32, u32)) -> Self {
+        let (tw, th) = self.dim;
+        Self {
+            dim: (tw * cols, th * rows),
+            ..self.clone()
+        }
+    }
+
+    pub fn process(&self, img: DynamicImage, settings: &ConvSettings) -> DynamicImage {
+        self.process_with_reference(img, settings).1
+    }
+
+    /// Like `process`, but also returns the resized image before quantization,
+    /// which the conversion error is measured against
+    pub fn process_with_reference(
+        &self,
+        img: DynamicImage,
+        settings: &ConvSettings,
+    ) -> (DynamicImage, DynamicImage) {
+        let mut prepared = self.prepare(img, settings);
+        prepared.rgb = prepared.conv.convert(std::mem::take(&mut prepared.rgb), &prepared.settings);
+        prepared.finish()
+    }
+
+    // Everything before the palette mapping: detects pixel art, resizes, keeps
+    // the reference, runs the adjustments and narrows the palette
+    fn prepare(&self, img: DynamicImage, settings: &ConvSettings) -> Prepared<'_> {
+        let has_alpha = img.color().has_alpha();
+        let pixel_art = match settings.pixel_art {
+            PixelArtMode::Auto => self.is_pixel_art(&img),
+            PixelArtMode::On => true,
+            PixelArtMode::Off => false,
+        };
+        // Sprites are matched exactly, dithering would only add noise and
+        // cleanup would remove deliberate single pixels
+        let settings = ConvSettings {
+            dither: if pixel_art { Dither::None } else { settings.dither },
+            despeckle: settings.despeckle && !pixel_art,
+            min_region: if pixel_art { 0 } else { settings.min_region },
+            ..settings.clone()
+        };
+
+        let img = if pixel_art {
+            self.resize_integer(img, &settings)
+        } else {
+            self.resize_and_rotate(img, &settings)
+        };
+        tracing::info!("resized: {} {}", img.height(), img.width());
+        // The error is measured against the resized source, before any adjustments
+        let reference = img.clone();
+        let img = prefilter(img, &settings);
+
+        // The lookup table quantizes colors, sprites need theirs matched exactly
+        let conv = if pixel_art && self.lut.is_some() {
+            Cow::Owned(self.without_lut())
+        } else {
+            Cow::Borrowed(self)
+        };
+        // Indices of the full palette stay valid, only the choice is narrowed
+        let conv = match settings.max_colors as usize {
+            max if max > 0 && max < conv.coords.len() => Cow::Owned(conv.limit_colors(&img, max)),
+            _ => conv,
+        };
+        let (rgb, alpha) = if has_alpha {
+            let rgba = img.to_rgba8();
+            (alpha_fill(&rgba, settings.transparent_color, &settings), Some(rgba))
+        } else {
+            (img.to_rgb8(), None)
+        };
+        Prepared {
+            conv,
+            settings,
+            reference,
+            rgb,
+            alpha,
+        }
+    }
+}
+
+// An image ready for the palette mapping, with the converter and settings it runs with
+struct Prepared<'a> {
+    conv: Cow<'a, DalImageConverter>,
+    settings: ConvSettings,
+    reference: DynamicImage,
+    // Colors to map, mapped in place
+    rgb: image::RgbImage,
+    // The resized image with its transparency, if it has any
+    alpha: Option<image::RgbaImage>,
+}
+
+impl Prepared<'_> {
+    fn into_owned(self) -> Prepared<'static> {
+        Prepared {
+            conv: Cow::Owned(self.conv.into_owned()),
+            settings: self.settings,
+            reference: self.reference,
+            rgb: self.rgb,
+            alpha: self.alpha,
+        }
+    }
+
+    // Puts the transparency back and runs the cleanup passes once `rgb` is
+    // mapped, returns the reference and the converted image
+    fn finish(self) -> (DynamicImage, DynamicImage) {
+        let converted = match self.alpha {
+            Some(mut rgba) => {
+                alpha_restore(&mut rgba, &self.rgb, self.settings.transparent_color, &self.settings);
+                if self.settings.alpha_mode == AlphaMode::Keep {
+                    DynamicImage::ImageRgba8(rgba)
+                } else {
+                    DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba).to_rgb8())
+                }
+            }
+            None => DynamicImage::ImageRgb8(self.rgb),
+        };
+        let converted = self.conv.postprocess(converted, &self.settings);
+        tracing::info!("converted: {} {}", converted.height(), converted.width());
+        (self.reference, converted)
+    }
+}
+
+const DIM: (u32, u32) = (87, 60);
+
+impl Default for DalImageConverter {
+    fn default() -> Self {
+        Self::with_lut(&palettes::DALE_18, DIM, ColorMetric::default())
+    }
+}
+
+/// Converters are equal when they map colors the same way, whether or not
+/// they use a lookup table
+impl PartialEq for DalImageConverter {
+    fn eq(&self, other: &Self) -> bool {
+        self.palette == other.palette
+            && self.enabled == other.enabled
+            && self.penalties == other.penalties
+            && self.metric == other.metric
+            && self.dim == other.dim
+    }
+}
\ No newline at end of file
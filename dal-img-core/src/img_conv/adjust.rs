@@ -0,0 +1,743 @@
+use std::collections::HashMap;
+
+use image::DynamicImage;
+use palette::{IntoColor, Oklch, Srgb};
+use serde::{Deserialize, Serialize};
+
+/// A step of the pipeline between resizing and quantization. Steps are
+/// chained in the order of `ConvSettings::adjustments`
+pub trait Adjustment {
+    /// Whether the step leaves every image as it is
+    fn is_identity(&self) -> bool;
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage;
+}
+
+/// Black and white point stretch with a midtone gamma, applied to all color
+/// channels before quantization
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Levels {
+    /// Input value that maps to 0
+    pub black: u8,
+    /// Input value that maps to 255, always above `black`
+    pub white: u8,
+    /// Above 1 brightens the midtones, below 1 darkens them
+    pub gamma: f32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            black: 0,
+            white: 255,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl Levels {
+    fn map(&self, v: u8) -> u8 {
+        let (black, white) = (self.black as f32, (self.white as f32).max(self.black as f32 + 1.0));
+        let v = ((v as f32 - black) / (white - black)).clamp(0.0, 1.0);
+        (v.powf(1.0 / self.gamma.max(0.01)) * 255.0).round() as u8
+    }
+}
+
+impl Adjustment for Levels {
+    fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        map_channels(img, std::array::from_fn(|v| self.map(v as u8)))
+    }
+}
+
+/// Tone curve on luminance, each pixel is shifted by the change of its
+/// luminance so hue and saturation stay about the same
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Curve {
+    /// Control points as `[input, output]`. Unless a point sits on either
+    /// end, the curve starts at `[0, 0]` and ends at `[255, 255]`
+    pub points: Vec<[u8; 2]>,
+}
+
+impl Curve {
+    /// Points the curve passes through sorted by input, including the implied ends
+    pub fn knots(&self) -> Vec<[u8; 2]> {
+        let mut knots = self.points.clone();
+        knots.sort_by_key(|p| p[0]);
+        knots.dedup_by_key(|p| p[0]);
+        if knots.first().map_or(true, |p| p[0] > 0) {
+            knots.insert(0, [0, 0]);
+        }
+        if knots.last().map_or(true, |p| p[0] < 255) {
+            knots.push([255, 255]);
+        }
+        knots
+    }
+
+    /// Output for every input value. Monotone cubic interpolation, so the
+    /// curve never overshoots between two points
+    pub fn lut(&self) -> [u8; 256] {
+        let knots: Vec<[f32; 2]> = self.knots().iter().map(|p| p.map(|v| v as f32)).collect();
+        let n = knots.len();
+        let slopes: Vec<f32> = knots
+            .windows(2)
+            .map(|w| (w[1][1] - w[0][1]) / (w[1][0] - w[0][0]))
+            .collect();
+        // Fritsch-Carlson tangents
+        let mut tangents = vec![0.0; n];
+        tangents[0] = slopes[0];
+        tangents[n - 1] = slopes[n - 2];
+        for k in 1..n - 1 {
+            if slopes[k - 1] * slopes[k] > 0.0 {
+                tangents[k] = (slopes[k - 1] + slopes[k]) / 2.0;
+            }
+        }
+        for k in 0..n - 1 {
+            if slopes[k] == 0.0 {
+                tangents[k] = 0.0;
+                tangents[k + 1] = 0.0;
+                continue;
+            }
+            let (a, b) = (tangents[k] / slopes[k], tangents[k + 1] / slopes[k]);
+            let len = (a * a + b * b).sqrt();
+            if len > 3.0 {
+                tangents[k] = 3.0 / len * a * slopes[k];
+                tangents[k + 1] = 3.0 / len * b * slopes[k];
+            }
+        }
+
+        std::array::from_fn(|x| {
+            let x = x as f32;
+            let k = knots.windows(2).position(|w| x <= w[1][0]).unwrap_or(n - 2);
+            let ([x0, y0], [x1, y1]) = (knots[k], knots[k + 1]);
+            let h = x1 - x0;
+            let t = ((x - x0) / h).clamp(0.0, 1.0);
+            let (t2, t3) = (t * t, t * t * t);
+            let y = (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                + (-2.0 * t3 + 3.0 * t2) * y1
+                + (t3 - t2) * h * tangents[k + 1];
+            y.round().clamp(0.0, 255.0) as u8
+        })
+    }
+}
+
+impl Adjustment for Curve {
+    fn is_identity(&self) -> bool {
+        self.points.iter().all(|p| p[0] == p[1])
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        let lut = self.lut();
+        map_luma(img, |_, _, luma| lut[luma as usize])
+    }
+}
+
+// Moves every pixel by the change `f(x, y, luma)` makes to its luminance
+fn map_luma(img: DynamicImage, f: impl Fn(u32, u32, u8) -> u8) -> DynamicImage {
+    let mut img = img.to_rgba8();
+    for (x, y, px) in img.enumerate_pixels_mut() {
+        let [r, g, b, _] = px.0;
+        let luma = luma([r, g, b]);
+        let shift = f(x, y, luma) as f32 - luma as f32;
+        for c in &mut px.0[..3] {
+            *c = (*c as f32 + shift).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
+
+// Rec. 601 luminance, as shown by the histogram
+fn luma([r, g, b]: [u8; 3]) -> u8 {
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round().min(255.0) as u8
+}
+
+// Runs every color channel through a lookup table, alpha is left alone
+fn map_channels(img: DynamicImage, lut: [u8; 256]) -> DynamicImage {
+    let mut img = img.to_rgba8();
+    for px in img.pixels_mut() {
+        for c in &mut px.0[..3] {
+            *c = lut[*c as usize];
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DenoiseMode {
+    #[default]
+    None,
+    /// Per channel median, removes salt and pepper noise
+    Median,
+    /// Averages neighbors of similar color only, so edges stay sharp
+    Bilateral,
+}
+
+impl DenoiseMode {
+    pub const ALL: [DenoiseMode; 3] = [
+        DenoiseMode::None,
+        DenoiseMode::Median,
+        DenoiseMode::Bilateral,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DenoiseMode::None => "None",
+            DenoiseMode::Median => "Median",
+            DenoiseMode::Bilateral => "Bilateral",
+        }
+    }
+}
+
+/// Edge preserving noise reduction, so sensor noise doesn't scatter into
+/// single pixels of unrelated palette colors
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Denoise {
+    pub mode: DenoiseMode,
+    /// Pixels on each side of the center that are looked at
+    pub radius: u32,
+    /// Color difference (0..255) still averaged by the bilateral filter
+    pub strength: f32,
+}
+
+impl Default for Denoise {
+    fn default() -> Self {
+        Self {
+            mode: DenoiseMode::None,
+            radius: 1,
+            strength: 30.0,
+        }
+    }
+}
+
+impl Adjustment for Denoise {
+    fn is_identity(&self) -> bool {
+        self.mode == DenoiseMode::None || self.radius == 0
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        let src = img.to_rgba8();
+        let (w, h) = (src.width() as i64, src.height() as i64);
+        let r = self.radius as i64;
+        // Neighbors clamped to the image, so borders aren't darkened
+        let at = |x: i64, y: i64| {
+            src.get_pixel(x.clamp(0, w - 1) as u32, y.clamp(0, h - 1) as u32).0
+        };
+
+        let mut out = src.clone();
+        let mut window: [Vec<u8>; 3] = Default::default();
+        let sigma_space = 2.0 * (r as f32 / 2.0).max(0.5).powi(2);
+        let sigma_color = 2.0 * self.strength.max(1.0).powi(2);
+        for (x, y, px) in out.enumerate_pixels_mut() {
+            let (x, y) = (x as i64, y as i64);
+            match self.mode {
+                DenoiseMode::None => {}
+                DenoiseMode::Median => {
+                    window.iter_mut().for_each(Vec::clear);
+                    for dy in -r..=r {
+                        for dx in -r..=r {
+                            let n = at(x + dx, y + dy);
+                            for (c, v) in window.iter_mut().zip(n) {
+                                c.push(v);
+                            }
+                        }
+                    }
+                    for (c, v) in window.iter_mut().zip(&mut px.0[..3]) {
+                        let mid = c.len() / 2;
+                        *v = *c.select_nth_unstable(mid).1;
+                    }
+                }
+                DenoiseMode::Bilateral => {
+                    let center = at(x, y);
+                    let (mut sum, mut total) = ([0.0f32; 3], 0.0);
+                    for dy in -r..=r {
+                        for dx in -r..=r {
+                            let n = at(x + dx, y + dy);
+                            let color: f32 =
+                                (0..3).map(|c| (n[c] as f32 - center[c] as f32).powi(2)).sum();
+                            let space = (dx * dx + dy * dy) as f32;
+                            let weight = (-space / sigma_space - color / sigma_color).exp();
+                            for c in 0..3 {
+                                sum[c] += n[c] as f32 * weight;
+                            }
+                            total += weight;
+                        }
+                    }
+                    for c in 0..3 {
+                        px.0[c] = (sum[c] / total).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(out)
+    }
+}
+
+/// Corrections measured on the image itself, so each upload gets its own
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoEnhance {
+    /// Gray-world white balance, scales the channels until their averages match
+    pub white_balance: bool,
+    /// Stretches the luminance histogram and moves its median to middle gray
+    pub exposure: bool,
+}
+
+// Share of pixels ignored at either end of the histogram, so a few specular
+// highlights don't hold back the stretch
+const AUTO_CLIP: f32 = 0.005;
+
+impl Adjustment for AutoEnhance {
+    fn is_identity(&self) -> bool {
+        !self.white_balance && !self.exposure
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        let img = if self.white_balance { white_balance(img) } else { img };
+        if self.exposure {
+            auto_exposure(img)
+        } else {
+            img
+        }
+    }
+}
+
+// Gains are applied in linear light, where gray-world holds
+fn white_balance(img: DynamicImage) -> DynamicImage {
+    let mut img = img.to_rgba8();
+    let linear: [f32; 256] = std::array::from_fn(|v| super::srgb_to_linear(v as f32));
+    let (mut sums, mut n) = ([0.0f64; 3], 0usize);
+    for px in img.pixels().filter(|px| px.0[3] > 0) {
+        for (sum, &v) in sums.iter_mut().zip(&px.0[..3]) {
+            *sum += linear[v as usize] as f64;
+        }
+        n += 1;
+    }
+    let gray = sums.iter().sum::<f64>() / 3.0;
+    if n == 0 || gray <= 0.0 {
+        return DynamicImage::ImageRgba8(img);
+    }
+    // Limited, so a picture of a red wall isn't turned gray
+    let gains = sums.map(|sum| (gray / sum.max(1e-6)).clamp(0.5, 2.0) as f32);
+    let luts: [[u8; 256]; 3] = std::array::from_fn(|c| {
+        std::array::from_fn(|v| super::linear_to_srgb(linear[v] * gains[c]).round() as u8)
+    });
+    for px in img.pixels_mut() {
+        for (c, lut) in px.0[..3].iter_mut().zip(&luts) {
+            *c = lut[*c as usize];
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
+
+fn auto_exposure(img: DynamicImage) -> DynamicImage {
+    let luma = channel_histograms(&img)[3];
+    let total: u32 = luma.iter().sum();
+    if total == 0 {
+        return img;
+    }
+    // Value below which the given share of pixels lies
+    let percentile = |share: f32| {
+        let target = (total as f32 * share) as u32;
+        let mut seen = 0;
+        luma.iter()
+            .position(|&n| {
+                seen += n;
+                seen > target
+            })
+            .unwrap_or(255) as f32
+    };
+    let (low, high) = (percentile(AUTO_CLIP), percentile(1.0 - AUTO_CLIP));
+    if high - low < 1.0 {
+        return img;
+    }
+    let median = ((percentile(0.5) - low) / (high - low)).clamp(0.05, 0.95);
+    let gamma = (0.5f32.ln() / median.ln()).clamp(0.5, 2.0);
+    let lut = std::array::from_fn(|v| {
+        let v = ((v as f32 - low) / (high - low)).clamp(0.0, 1.0);
+        (v.powf(gamma) * 255.0).round() as u8
+    });
+    map_channels(img, lut)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EqualizeMode {
+    #[default]
+    None,
+    /// One mapping for the whole image that flattens the luminance histogram
+    Global,
+    /// Contrast limited adaptive equalization, one mapping per tile blended
+    /// between tiles, so local detail gains contrast without blowing out
+    Clahe,
+}
+
+impl EqualizeMode {
+    pub const ALL: [EqualizeMode; 3] = [
+        EqualizeMode::None,
+        EqualizeMode::Global,
+        EqualizeMode::Clahe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EqualizeMode::None => "None",
+            EqualizeMode::Global => "Histogram Equalization",
+            EqualizeMode::Clahe => "CLAHE",
+        }
+    }
+}
+
+/// Histogram equalization of the luminance, spreads low contrast images
+/// over more palette colors
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Equalize {
+    pub mode: EqualizeMode,
+    /// Tiles along each side of the image for CLAHE
+    pub tiles: u32,
+    /// How far above an even distribution a CLAHE histogram bin may rise
+    pub clip_limit: f32,
+}
+
+impl Default for Equalize {
+    fn default() -> Self {
+        Self {
+            mode: EqualizeMode::None,
+            tiles: 4,
+            clip_limit: 2.0,
+        }
+    }
+}
+
+// Maps a luminance histogram onto the full range by its cumulative sum
+fn equalize_lut(hist: &[u32; 256]) -> [u8; 256] {
+    let total: u32 = hist.iter().sum();
+    let first = hist.iter().copied().find(|&n| n > 0).unwrap_or(0);
+    let mut sum = 0;
+    std::array::from_fn(|v| {
+        sum += hist[v];
+        if total <= first {
+            return v as u8;
+        }
+        ((sum.saturating_sub(first)) as f32 * 255.0 / (total - first) as f32).round() as u8
+    })
+}
+
+impl Equalize {
+    fn clahe(&self, img: DynamicImage) -> DynamicImage {
+        let (w, h) = (img.width(), img.height());
+        // Tiles need a few pixels each for a meaningful histogram
+        let (tx, ty) = (self.tiles.clamp(1, (w / 4).max(1)), self.tiles.clamp(1, (h / 4).max(1)));
+        let (tw, th) = (w.div_ceil(tx), h.div_ceil(ty));
+        let rgba = img.to_rgba8();
+        let mut hists = vec![[0u32; 256]; (tx * ty) as usize];
+        for (x, y, px) in rgba.enumerate_pixels() {
+            let [r, g, b, _] = px.0;
+            hists[((y / th) * tx + x / tw) as usize][luma([r, g, b]) as usize] += 1;
+        }
+        let luts: Vec<[u8; 256]> = hists
+            .iter_mut()
+            .map(|hist| {
+                let total: u32 = hist.iter().sum();
+                let limit = ((self.clip_limit.max(1.0) * total as f32 / 256.0) as u32).max(1);
+                // Cut every bin at the limit and spread the excess over all bins
+                let mut excess = 0;
+                for n in hist.iter_mut() {
+                    excess += n.saturating_sub(limit);
+                    *n = (*n).min(limit);
+                }
+                for (v, n) in hist.iter_mut().enumerate() {
+                    *n += excess / 256 + u32::from((v as u32) < excess % 256);
+                }
+                equalize_lut(hist)
+            })
+            .collect();
+
+        // Blends the mappings of the four tiles whose centers surround a pixel
+        let lookup = |x: u32, y: u32, luma: u8| {
+            let gx = ((x as f32 + 0.5) / tw as f32 - 0.5).clamp(0.0, (tx - 1) as f32);
+            let gy = ((y as f32 + 0.5) / th as f32 - 0.5).clamp(0.0, (ty - 1) as f32);
+            let (x0, y0) = (gx.floor() as u32, gy.floor() as u32);
+            let (x1, y1) = ((x0 + 1).min(tx - 1), (y0 + 1).min(ty - 1));
+            let (fx, fy) = (gx - x0 as f32, gy - y0 as f32);
+            let at = |tx0: u32, ty0: u32| luts[(ty0 * tx + tx0) as usize][luma as usize] as f32;
+            let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+            let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+            (top * (1.0 - fy) + bottom * fy).round() as u8
+        };
+        map_luma(DynamicImage::ImageRgba8(rgba), lookup)
+    }
+}
+
+impl Adjustment for Equalize {
+    fn is_identity(&self) -> bool {
+        self.mode == EqualizeMode::None
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        match self.mode {
+            EqualizeMode::None => img,
+            EqualizeMode::Global => {
+                let lut = equalize_lut(&channel_histograms(&img)[3]);
+                map_luma(img, |_, _, luma| lut[luma as usize])
+            }
+            EqualizeMode::Clahe => self.clahe(img),
+        }
+    }
+}
+
+/// Exposure, brightness and contrast, applied to all color channels before
+/// levels. Lifts dark photos into range of the saturated board colors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tone {
+    /// Stops of exposure, applied in linear light like a camera would
+    pub exposure: f32,
+    /// Offset from -1 to 1, a fraction of the full range
+    pub brightness: f32,
+    /// From -1 (flat gray) to 1 (doubled), pivoting around middle gray
+    pub contrast: f32,
+}
+
+impl Tone {
+    fn map(&self, v: u8) -> u8 {
+        let v = super::linear_to_srgb(super::srgb_to_linear(v as f32) * self.exposure.exp2());
+        let v = v + self.brightness * 255.0;
+        let v = (v - 127.5) * (1.0 + self.contrast.clamp(-1.0, 1.0)) + 127.5;
+        v.round().clamp(0.0, 255.0) as u8
+    }
+}
+
+impl Adjustment for Tone {
+    fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        map_channels(img, std::array::from_fn(|v| self.map(v as u8)))
+    }
+}
+
+/// Saturation and hue, changed in Oklch so lightness stays put. Saturated
+/// sources find the vivid board colors instead of falling back to grays
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Saturation {
+    /// From -1 (grayscale) to 1 (doubled chroma)
+    pub saturation: f32,
+    /// Hue rotation in degrees
+    pub hue: f32,
+}
+
+impl Saturation {
+    fn map(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let srgb = Srgb::new(rgb[0], rgb[1], rgb[2]).into_format::<f32>();
+        let mut lch: Oklch = srgb.into_color();
+        lch.chroma *= 1.0 + self.saturation.clamp(-1.0, 1.0);
+        lch.hue += self.hue;
+        let srgb: Srgb = lch.into_color();
+        let (r, g, b) = srgb.into_components();
+        [r, g, b].map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+impl Adjustment for Saturation {
+    fn is_identity(&self) -> bool {
+        self.saturation == 0.0 && self.hue % 360.0 == 0.0
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        let mut img = img.to_rgba8();
+        let mut cache = HashMap::new();
+        for px in img.pixels_mut() {
+            let [r, g, b, a] = px.0;
+            let [r, g, b] = *cache.entry([r, g, b]).or_insert_with(|| self.map([r, g, b]));
+            px.0 = [r, g, b, a];
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+/// Unsharp mask, applied after downscaling so edges that the resize softened
+/// still stand out once mapped to the palette
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sharpen {
+    /// Strength of the mask, 0 leaves the image alone
+    pub amount: f32,
+    /// Gaussian blur sigma in target pixels
+    pub radius: f32,
+    /// Differences below this are left alone, so flat areas don't get grainy
+    pub threshold: u8,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self {
+            amount: 0.0,
+            radius: 1.0,
+            threshold: 0,
+        }
+    }
+}
+
+impl Adjustment for Sharpen {
+    fn is_identity(&self) -> bool {
+        self.amount <= 0.0 || self.radius <= 0.0
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        let mut img = img.to_rgba8();
+        let blurred = image::imageops::blur(&img, self.radius);
+        for (px, b) in img.pixels_mut().zip(blurred.pixels()) {
+            for (c, &b) in px.0[..3].iter_mut().zip(&b.0[..3]) {
+                let diff = *c as f32 - b as f32;
+                if diff.abs() >= self.threshold as f32 {
+                    *c = (*c as f32 + diff * self.amount).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoiseKind {
+    #[default]
+    None,
+    /// Sum of two uniform values, the usual dither noise for audio and video
+    Triangular,
+    /// Noise without low frequencies (R2 sequence), reads as finer grain
+    BlueNoise,
+}
+
+impl NoiseKind {
+    pub const ALL: [NoiseKind; 3] = [NoiseKind::None, NoiseKind::Triangular, NoiseKind::BlueNoise];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoiseKind::None => "None",
+            NoiseKind::Triangular => "Triangular",
+            NoiseKind::BlueNoise => "Blue Noise",
+        }
+    }
+}
+
+/// Noise added right before quantization, so smooth gradients break up into
+/// mixed palette colors instead of hard bands. Much cheaper than dithering
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Noise {
+    pub kind: NoiseKind,
+    /// Peak offset in channel values (0..255)
+    pub amount: f32,
+    /// The same seed gives the same noise, so conversions are repeatable
+    pub seed: u32,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::None,
+            amount: 16.0,
+            seed: 0,
+        }
+    }
+}
+
+// SplitMix64 finalizer, spreads a pixel position and seed into uniform bits
+fn hash(mut v: u64) -> u64 {
+    v = v.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    v = (v ^ (v >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    v ^ (v >> 31)
+}
+
+// Uniform value in [0, 1)
+fn unit(v: u64) -> f32 {
+    (v >> 40) as f32 / (1u64 << 24) as f32
+}
+
+impl Noise {
+    /// Offset in [-1, 1] for a pixel, scaled by `amount` when applied
+    fn sample(&self, x: u32, y: u32) -> f32 {
+        let key = ((self.seed as u64) << 40) ^ ((y as u64) << 20) ^ x as u64;
+        match self.kind {
+            NoiseKind::None => 0.0,
+            NoiseKind::Triangular => {
+                let bits = hash(key);
+                unit(bits) - unit(bits << 24)
+            }
+            NoiseKind::BlueNoise => {
+                // The seed only shifts the pattern, which keeps its spectrum
+                let shift = hash(self.seed as u64);
+                let (x, y) = (x as f32 + (shift & 0xffff) as f32, y as f32 + (shift >> 48) as f32);
+                let v = (0.5 + x * 0.754_877_7 + y * 0.569_840_3).fract();
+                // Folded into a triangle, so it averages out like the other kinds
+                2.0 * (2.0 * v - 1.0).abs() - 1.0
+            }
+        }
+    }
+}
+
+impl Adjustment for Noise {
+    fn is_identity(&self) -> bool {
+        self.kind == NoiseKind::None || self.amount <= 0.0
+    }
+
+    fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+        let mut img = img.to_rgba8();
+        for (x, y, px) in img.enumerate_pixels_mut() {
+            // One offset for all channels, so the grain doesn't add colors
+            let offset = self.sample(x, y) * self.amount;
+            for c in &mut px.0[..3] {
+                *c = (*c as f32 + offset).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+/// Value counts of the red, green, blue and luminance (Rec. 601) channels.
+/// Fully transparent pixels are not counted
+pub fn channel_histograms(img: &DynamicImage) -> [[u32; 256]; 4] {
+    let mut hist = [[0; 256]; 4];
+    for px in img.to_rgba8().pixels() {
+        let [r, g, b, a] = px.0;
+        if a == 0 {
+            continue;
+        }
+        hist[0][r as usize] += 1;
+        hist[1][g as usize] += 1;
+        hist[2][b as usize] += 1;
+        hist[3][luma([r, g, b]) as usize] += 1;
+    }
+    hist
+}
@@ -0,0 +1,112 @@
+//! Passes over the mapped palette indices that remove detail a physical
+//! board can't show well
+
+use super::regions::{label_regions, Regions};
+
+/// Replaces every pixel that shares its color with none of its 8 neighbors
+/// by the color most of them have. Pixels marked in `skip` neither change
+/// nor vote, e.g. transparent ones
+pub fn despeckle(indices: &mut [usize], dim: (u32, u32), skip: &[bool]) {
+    let (w, h) = (dim.0 as usize, dim.1 as usize);
+    let src = indices.to_vec();
+    let mut votes: Vec<(usize, usize)> = Vec::with_capacity(8);
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            if skip[i] {
+                continue;
+            }
+            votes.clear();
+            let mut isolated = true;
+            for ny in y.saturating_sub(1)..(y + 2).min(h) {
+                for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                    let n = ny * w + nx;
+                    if n == i || skip[n] {
+                        continue;
+                    }
+                    if src[n] == src[i] {
+                        isolated = false;
+                        break;
+                    }
+                    match votes.iter_mut().find(|(color, _)| *color == src[n]) {
+                        Some((_, count)) => *count += 1,
+                        None => votes.push((src[n], 1)),
+                    }
+                }
+            }
+            // Ties go to the color seen first, scanning from the top left
+            let majority = votes.iter().rev().max_by_key(|(_, count)| *count);
+            if let (true, Some(&(color, _))) = (isolated, majority) {
+                indices[i] = color;
+            }
+        }
+    }
+}
+
+fn find(parent: &mut [usize], mut r: usize) -> usize {
+    while parent[r] != r {
+        parent[r] = parent[parent[r]];
+        r = parent[r];
+    }
+    r
+}
+
+/// Merges connected regions of fewer than `min_size` pixels into their
+/// largest neighbor, smallest regions first. Regions touching a pixel marked
+/// in `skip` stay as they are and don't absorb others
+pub fn merge_small_regions(
+    indices: &mut [usize],
+    dim: (u32, u32),
+    min_size: usize,
+    skip: &[bool],
+) {
+    let (w, h) = (dim.0 as usize, dim.1 as usize);
+    let Regions { labels, regions } = label_regions(indices, dim);
+    let mut parent: Vec<usize> = (0..regions.len()).collect();
+    let mut members: Vec<Vec<usize>> = regions.iter().map(|r| r.pixels.clone()).collect();
+    let fixed: Vec<bool> = regions
+        .iter()
+        .map(|r| r.pixels.iter().any(|&p| skip[p]))
+        .collect();
+
+    let mut small: Vec<usize> = (0..regions.len())
+        .filter(|&r| members[r].len() < min_size && !fixed[r])
+        .collect();
+    small.sort_by_key(|&r| members[r].len());
+    for r in small {
+        // Absorbing smaller neighbors may have grown it big enough already
+        let r = find(&mut parent, r);
+        if members[r].len() >= min_size {
+            continue;
+        }
+        let mut target = None::<usize>;
+        for &i in &members[r] {
+            let (x, y) = (i % w, i / w);
+            let neighbors = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w),
+                (y + 1 < h).then(|| i + w),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                let n = find(&mut parent, labels[n]);
+                if n == r || fixed[n] {
+                    continue;
+                }
+                if target.map_or(true, |t| members[n].len() > members[t].len()) {
+                    target = Some(n);
+                }
+            }
+        }
+        // A region enclosed by skipped pixels has nowhere to go
+        if let Some(t) = target {
+            let pixels = std::mem::take(&mut members[r]);
+            members[t].extend(pixels);
+            parent[r] = t;
+        }
+    }
+
+    for (i, label) in labels.into_iter().enumerate() {
+        indices[i] = regions[find(&mut parent, label)].color;
+    }
+}
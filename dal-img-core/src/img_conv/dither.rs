@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BayerSize {
+    X2,
+    #[default]
+    X4,
+    X8,
+}
+
+impl BayerSize {
+    pub const ALL: [BayerSize; 3] = [BayerSize::X2, BayerSize::X4, BayerSize::X8];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BayerSize::X2 => "2x2",
+            BayerSize::X4 => "4x4",
+            BayerSize::X8 => "8x8",
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            BayerSize::X2 => 2,
+            BayerSize::X4 => 4,
+            BayerSize::X8 => 8,
+        }
+    }
+}
+
+// Amount (in 0..255 units) the threshold map may shift a channel
+pub const ORDERED_SPREAD: f32 = 64.0;
+
+/// Builds a normalized Bayer threshold map with values in [-0.5, 0.5)
+pub fn bayer_matrix(size: BayerSize) -> Vec<f32> {
+    let n = size.size();
+    let mut m = vec![0u32];
+    let mut cur = 1;
+    // Recursively expand M(2n) = [[4M, 4M+2], [4M+3, 4M+1]]
+    while cur < n {
+        let next = cur * 2;
+        let mut out = vec![0u32; next * next];
+        for y in 0..cur {
+            for x in 0..cur {
+                let v = 4 * m[y * cur + x];
+                out[y * next + x] = v;
+                out[y * next + x + cur] = v + 2;
+                out[(y + cur) * next + x] = v + 3;
+                out[(y + cur) * next + x + cur] = v + 1;
+            }
+        }
+        m = out;
+        cur = next;
+    }
+
+    let len = (n * n) as f32;
+    m.into_iter().map(|v| (v as f32 + 0.5) / len - 0.5).collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DitherKernel {
+    #[default]
+    FloydSteinberg,
+    Atkinson,
+    JarvisJudiceNinke,
+    Stucki,
+    Sierra,
+}
+
+impl DitherKernel {
+    pub const ALL: [DitherKernel; 5] = [
+        DitherKernel::FloydSteinberg,
+        DitherKernel::Atkinson,
+        DitherKernel::JarvisJudiceNinke,
+        DitherKernel::Stucki,
+        DitherKernel::Sierra,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DitherKernel::FloydSteinberg => "Floyd-Steinberg",
+            DitherKernel::Atkinson => "Atkinson",
+            DitherKernel::JarvisJudiceNinke => "Jarvis-Judice-Ninke",
+            DitherKernel::Stucki => "Stucki",
+            DitherKernel::Sierra => "Sierra",
+        }
+    }
+
+    /// Diffusion offsets as (dx, dy, weight) and the common divisor.
+    /// Atkinson intentionally only propagates 6/8 of the error.
+    pub fn weights(&self) -> (&'static [(isize, usize, f32)], f32) {
+        match self {
+            DitherKernel::FloydSteinberg => (
+                &[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)],
+                16.0,
+            ),
+            DitherKernel::Atkinson => (
+                &[
+                    (1, 0, 1.0),
+                    (2, 0, 1.0),
+                    (-1, 1, 1.0),
+                    (0, 1, 1.0),
+                    (1, 1, 1.0),
+                    (0, 2, 1.0),
+                ],
+                8.0,
+            ),
+            DitherKernel::JarvisJudiceNinke => (
+                &[
+                    (1, 0, 7.0),
+                    (2, 0, 5.0),
+                    (-2, 1, 3.0),
+                    (-1, 1, 5.0),
+                    (0, 1, 7.0),
+                    (1, 1, 5.0),
+                    (2, 1, 3.0),
+                    (-2, 2, 1.0),
+                    (-1, 2, 3.0),
+                    (0, 2, 5.0),
+                    (1, 2, 3.0),
+                    (2, 2, 1.0),
+                ],
+                48.0,
+            ),
+            DitherKernel::Stucki => (
+                &[
+                    (1, 0, 8.0),
+                    (2, 0, 4.0),
+                    (-2, 1, 2.0),
+                    (-1, 1, 4.0),
+                    (0, 1, 8.0),
+                    (1, 1, 4.0),
+                    (2, 1, 2.0),
+                    (-2, 2, 1.0),
+                    (-1, 2, 2.0),
+                    (0, 2, 4.0),
+                    (1, 2, 2.0),
+                    (2, 2, 1.0),
+                ],
+                42.0,
+            ),
+            DitherKernel::Sierra => (
+                &[
+                    (1, 0, 5.0),
+                    (2, 0, 3.0),
+                    (-2, 1, 2.0),
+                    (-1, 1, 4.0),
+                    (0, 1, 5.0),
+                    (1, 1, 4.0),
+                    (2, 1, 2.0),
+                    (-1, 2, 2.0),
+                    (0, 2, 3.0),
+                    (1, 2, 2.0),
+                ],
+                32.0,
+            ),
+        }
+    }
+}
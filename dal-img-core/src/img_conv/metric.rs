@@ -0,0 +1,110 @@
+use palette::{
+    color_difference::{Ciede2000, DeltaE},
+    IntoColor, Lab, Oklab, Srgb,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMetric {
+    #[default]
+    Ciede2000,
+    Cie76,
+    Cie94,
+    Oklab,
+    WeightedRgb,
+}
+
+impl ColorMetric {
+    pub const ALL: [ColorMetric; 5] = [
+        ColorMetric::Ciede2000,
+        ColorMetric::Cie76,
+        ColorMetric::Cie94,
+        ColorMetric::Oklab,
+        ColorMetric::WeightedRgb,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorMetric::Ciede2000 => "CIEDE2000",
+            ColorMetric::Cie76 => "CIE76",
+            ColorMetric::Cie94 => "CIE94",
+            ColorMetric::Oklab => "OKLab",
+            ColorMetric::WeightedRgb => "Weighted RGB",
+        }
+    }
+
+    /// Whether the metric is a plain euclidean distance in its color space,
+    /// so a kd-tree query is exact
+    pub fn is_euclidean(&self) -> bool {
+        matches!(self, ColorMetric::Cie76 | ColorMetric::Oklab)
+    }
+
+    /// Rough size of one CIELAB ΔE in this metric's distances, so palette
+    /// penalties mean about the same with every metric
+    pub fn penalty_scale(&self) -> f32 {
+        match self {
+            ColorMetric::Ciede2000 | ColorMetric::Cie76 | ColorMetric::Cie94 => 1.0,
+            ColorMetric::Oklab => 0.01,
+            ColorMetric::WeightedRgb => 3.0,
+        }
+    }
+
+    /// Maps an sRGB color into the space the metric operates in
+    pub fn coords(&self, rgb: [u8; 3]) -> [f32; 3] {
+        let srgb = Srgb::new(
+            rgb[0] as f32 / 255.0,
+            rgb[1] as f32 / 255.0,
+            rgb[2] as f32 / 255.0,
+        );
+        match self {
+            ColorMetric::Ciede2000 | ColorMetric::Cie76 | ColorMetric::Cie94 => {
+                let lab: Lab = srgb.into_color();
+                [lab.l, lab.a, lab.b]
+            }
+            ColorMetric::Oklab => {
+                let lab: Oklab = srgb.into_color();
+                [lab.l, lab.a, lab.b]
+            }
+            ColorMetric::WeightedRgb => rgb.map(|v| v as f32),
+        }
+    }
+
+    /// Distance between a source color `a` and a palette color `b`, both in `coords` space
+    pub fn distance(&self, a: &[f32; 3], b: &[f32; 3]) -> f32 {
+        match self {
+            ColorMetric::Ciede2000 => {
+                Lab::new(a[0], a[1], a[2]).difference(Lab::new(b[0], b[1], b[2]))
+            }
+            ColorMetric::Cie76 => Lab::new(a[0], a[1], a[2]).delta_e(Lab::new(b[0], b[1], b[2])),
+            ColorMetric::Cie94 => cie94(a, b),
+            ColorMetric::Oklab => euclidean(a, b),
+            ColorMetric::WeightedRgb => redmean(a, b),
+        }
+    }
+}
+
+fn euclidean(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+// CIE94 with graphic-arts weights, `a` being the reference color
+fn cie94(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let dl = a[0] - b[0];
+    let c1 = (a[1] * a[1] + a[2] * a[2]).sqrt();
+    let c2 = (b[1] * b[1] + b[2] * b[2]).sqrt();
+    let dc = c1 - c2;
+    let dh2 = ((a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2) - dc * dc).max(0.0);
+    let sc = 1.0 + 0.045 * c1;
+    let sh = 1.0 + 0.015 * c1;
+    (dl * dl + (dc / sc).powi(2) + dh2 / (sh * sh)).sqrt()
+}
+
+// "Redmean" weighted RGB distance
+fn redmean(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let rmean = (a[0] + b[0]) / 2.0;
+    let dr = a[0] - b[0];
+    let dg = a[1] - b[1];
+    let db = a[2] - b[2];
+    ((2.0 + rmean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - rmean) / 256.0) * db * db)
+        .sqrt()
+}
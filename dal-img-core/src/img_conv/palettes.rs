@@ -0,0 +1,179 @@
+use anyhow::{anyhow, bail, Context};
+
+/// The 18 colors of the Dale & Dawson board
+pub const DALE_18: [[u8; 3]; 18] = [
+    [0, 0, 0],
+    [0, 0, 0],
+    [255, 255, 255],
+    [255, 0, 0],
+    [255, 124, 123],
+    [120, 0, 2],
+    [10, 13, 255],
+    [125, 134, 255],
+    [3, 0, 122],
+    [0, 255, 10],
+    [150, 255, 154],
+    [0, 115, 4],
+    [255, 232, 0],
+    [255, 245, 140],
+    [110, 94, 0],
+    [255, 99, 0],
+    [255, 179, 131],
+    [113, 55, 18],
+];
+
+/// 7-color ACeP e-ink panels
+pub const EINK_7: [[u8; 3]; 7] = [
+    [0, 0, 0],
+    [255, 255, 255],
+    [0, 255, 0],
+    [0, 0, 255],
+    [255, 0, 0],
+    [255, 255, 0],
+    [255, 128, 0],
+];
+
+pub const GRAYSCALE_8: [[u8; 3]; 8] = [
+    [0, 0, 0],
+    [36, 36, 36],
+    [73, 73, 73],
+    [109, 109, 109],
+    [146, 146, 146],
+    [182, 182, 182],
+    [219, 219, 219],
+    [255, 255, 255],
+];
+
+pub const MONOCHROME: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+pub const PICO_8: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [29, 43, 83],
+    [126, 37, 83],
+    [0, 135, 81],
+    [171, 82, 54],
+    [95, 87, 79],
+    [194, 195, 199],
+    [255, 241, 232],
+    [255, 0, 77],
+    [255, 163, 0],
+    [255, 236, 39],
+    [0, 228, 54],
+    [41, 173, 255],
+    [131, 118, 156],
+    [255, 119, 168],
+    [255, 204, 170],
+];
+
+pub struct PalettePreset {
+    pub name: &'static str,
+    pub colors: &'static [[u8; 3]],
+}
+
+pub const PRESETS: [PalettePreset; 5] = [
+    PalettePreset {
+        name: "Dale & Dawson (18 colors)",
+        colors: &DALE_18,
+    },
+    PalettePreset {
+        name: "E-Ink (7 colors)",
+        colors: &EINK_7,
+    },
+    PalettePreset {
+        name: "Grayscale (8 levels)",
+        colors: &GRAYSCALE_8,
+    },
+    PalettePreset {
+        name: "Monochrome",
+        colors: &MONOCHROME,
+    },
+    PalettePreset {
+        name: "PICO-8",
+        colors: &PICO_8,
+    },
+];
+
+/// Formats a color as `#rrggbb`, as used by `<input type="color">`
+pub fn to_hex(c: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+/// Parses `#rrggbb` or `rrggbb`
+pub fn parse_hex(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim().trim_start_matches('#');
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+
+    let mut c = [0u8; 3];
+    for (i, v) in c.iter_mut().enumerate() {
+        *v = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(c)
+}
+
+/// Serializes a palette as a GIMP palette file
+pub fn to_gpl(palette: &[[u8; 3]], name: &str) -> String {
+    let mut out = format!("GIMP Palette\nName: {name}\nColumns: 6\n#\n");
+    for c in palette {
+        out.push_str(&format!("{:>3} {:>3} {:>3}\t{}\n", c[0], c[1], c[2], to_hex(*c)));
+    }
+    out
+}
+
+/// Serializes a palette as a JSON array of RGB triples
+pub fn to_json(palette: &[[u8; 3]]) -> String {
+    serde_json::to_string(palette).expect("Palette is always serializable")
+}
+
+/// Parses a palette file, picking the format from the file extension:
+/// GIMP `.gpl`, a JSON array of RGB triples or a plain list of hex colors
+pub fn parse_palette(name: &str, text: &str) -> anyhow::Result<Vec<[u8; 3]>> {
+    let ext = name.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    let palette = match ext.as_str() {
+        "gpl" => parse_gpl(text)?,
+        "json" => serde_json::from_str(text).context("Invalid JSON palette")?,
+        _ => parse_hex_list(text)?,
+    };
+
+    if palette.is_empty() {
+        bail!("Palette {name} contains no colors");
+    }
+    Ok(palette)
+}
+
+fn parse_gpl(text: &str) -> anyhow::Result<Vec<[u8; 3]>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        bail!("Missing GIMP Palette header");
+    }
+
+    let mut palette = Vec::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+
+        // "R G B [name]"
+        let mut parts = line.split_whitespace();
+        let mut c = [0u8; 3];
+        for v in c.iter_mut() {
+            let part = parts.next().ok_or_else(|| anyhow!("Invalid line: {line}"))?;
+            *v = part.parse().with_context(|| format!("Invalid line: {line}"))?;
+        }
+        palette.push(c);
+    }
+    Ok(palette)
+}
+
+fn parse_hex_list(text: &str) -> anyhow::Result<Vec<[u8; 3]>> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_hex(s).ok_or_else(|| anyhow!("Invalid hex color: {s}")))
+        .collect()
+}
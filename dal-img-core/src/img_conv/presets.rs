@@ -0,0 +1,31 @@
+use super::palettes;
+
+/// A board model with its native resolution and color set
+pub struct DevicePreset {
+    pub name: &'static str,
+    pub dim: (u32, u32),
+    pub palette: &'static [[u8; 3]],
+}
+
+pub const DEVICES: [DevicePreset; 4] = [
+    DevicePreset {
+        name: "Dale & Dawson Board",
+        dim: (87, 60),
+        palette: &palettes::DALE_18,
+    },
+    DevicePreset {
+        name: "7.3\" ACeP E-Ink (800x480)",
+        dim: (800, 480),
+        palette: &palettes::EINK_7,
+    },
+    DevicePreset {
+        name: "4.2\" E-Paper (400x300)",
+        dim: (400, 300),
+        palette: &palettes::MONOCHROME,
+    },
+    DevicePreset {
+        name: "Flip-Dot Panel (28x14)",
+        dim: (28, 14),
+        palette: &palettes::MONOCHROME,
+    },
+];
@@ -0,0 +1,78 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::metric::ColorMetric;
+
+/// ΔE mapped to the hottest heatmap color, larger errors are clamped.
+/// Fixed so heatmaps of different images can be compared
+pub const HEATMAP_MAX: f32 = 20.0;
+
+// Blue (exact) through cyan, green and yellow to red (worst)
+const HEATMAP_STOPS: [[f32; 3]; 5] = [
+    [0.0, 0.0, 255.0],
+    [0.0, 255.0, 255.0],
+    [0.0, 255.0, 0.0],
+    [255.0, 255.0, 0.0],
+    [255.0, 0.0, 0.0],
+];
+
+/// CIEDE2000 error of a conversion against its resized source
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quality {
+    pub mean: f32,
+    pub max: f32,
+}
+
+impl Quality {
+    pub fn from_errors(errors: &[Option<f32>]) -> Self {
+        let mut sum = 0.0;
+        let mut max = 0.0f32;
+        let mut count = 0;
+        for e in errors.iter().flatten() {
+            sum += *e as f64;
+            max = max.max(*e);
+            count += 1;
+        }
+        Self {
+            mean: if count > 0 { (sum / count as f64) as f32 } else { 0.0 },
+            max,
+        }
+    }
+}
+
+/// Per-pixel CIEDE2000 error of a converted image against the resized source
+/// it was quantized from, row-major. Pixels that are fully transparent in
+/// either image are `None`, their color never reaches the board
+pub fn delta_e(reference: &DynamicImage, converted: &DynamicImage) -> Vec<Option<f32>> {
+    let reference = reference.to_rgba8();
+    let converted = converted.to_rgba8();
+    let metric = ColorMetric::Ciede2000;
+
+    reference
+        .pixels()
+        .zip(converted.pixels())
+        .map(|(a, b)| {
+            if a.0[3] == 0 || b.0[3] == 0 {
+                return None;
+            }
+            let a = metric.coords([a.0[0], a.0[1], a.0[2]]);
+            let b = metric.coords([b.0[0], b.0[1], b.0[2]]);
+            Some(metric.distance(&a, &b))
+        })
+        .collect()
+}
+
+/// False-color rendering of `delta_e`, left transparent where no error was measured
+pub fn heatmap(errors: &[Option<f32>], dim: (u32, u32)) -> RgbaImage {
+    RgbaImage::from_fn(dim.0, dim.1, |x, y| {
+        let Some(e) = errors.get((y * dim.0 + x) as usize).copied().flatten() else {
+            return Rgba([0, 0, 0, 0]);
+        };
+        let t = (e / HEATMAP_MAX).clamp(0.0, 1.0) * (HEATMAP_STOPS.len() - 1) as f32;
+        let i = (t as usize).min(HEATMAP_STOPS.len() - 2);
+        let f = t - i as f32;
+        let (a, b) = (HEATMAP_STOPS[i], HEATMAP_STOPS[i + 1]);
+        let c = [0, 1, 2].map(|k| (a[k] + (b[k] - a[k]) * f).round() as u8);
+        Rgba([c[0], c[1], c[2], 255])
+    })
+}
@@ -0,0 +1,106 @@
+use image::DynamicImage;
+
+// Larger references are downsampled first, the palette barely changes
+const SAMPLE_DIM: u32 = 128;
+const KMEANS_ITERATIONS: usize = 8;
+
+/// Derives an `n` color palette from a reference image, using median cut
+/// for the initial clusters and refining them with k-means
+pub fn extract_palette(img: &DynamicImage, n: usize) -> Vec<[u8; 3]> {
+    let img = img.thumbnail(SAMPLE_DIM, SAMPLE_DIM).to_rgb8();
+    let pixels: Vec<[u8; 3]> = img.pixels().map(|px| px.0).collect();
+    if pixels.is_empty() || n == 0 {
+        return vec![];
+    }
+
+    let mut centroids = median_cut(pixels.clone(), n);
+    kmeans(&pixels, &mut centroids, KMEANS_ITERATIONS);
+    centroids
+        .into_iter()
+        .map(|c| c.map(|v| v.round().clamp(0.0, 255.0) as u8))
+        .collect()
+}
+
+fn channel_range(pixels: &[[u8; 3]], ch: usize) -> u8 {
+    let (min, max) = pixels
+        .iter()
+        .fold((u8::MAX, u8::MIN), |(lo, hi), p| (lo.min(p[ch]), hi.max(p[ch])));
+    max.saturating_sub(min)
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|ch| (ch, channel_range(pixels, ch)))
+        .max_by_key(|&(_, r)| r)
+        .expect("Three channels")
+}
+
+fn mean(pixels: &[[u8; 3]]) -> [f32; 3] {
+    let mut sum = [0f32; 3];
+    for p in pixels {
+        for ch in 0..3 {
+            sum[ch] += p[ch] as f32;
+        }
+    }
+    sum.map(|v| v / pixels.len() as f32)
+}
+
+fn median_cut(pixels: Vec<[u8; 3]>, n: usize) -> Vec<[f32; 3]> {
+    let mut boxes = vec![pixels];
+    while boxes.len() < n {
+        // Split the box with the widest spread along its widest channel
+        let Some((i, ch)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (ch, range) = widest_channel(b);
+                (i, ch, range)
+            })
+            .filter(|&(_, _, range)| range > 0)
+            .max_by_key(|&(_, _, range)| range)
+            .map(|(i, ch, _)| (i, ch))
+        else {
+            break;
+        };
+
+        let mut b = boxes.swap_remove(i);
+        b.sort_unstable_by_key(|p| p[ch]);
+        let upper = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| mean(b)).collect()
+}
+
+fn kmeans(pixels: &[[u8; 3]], centroids: &mut [[f32; 3]], iterations: usize) {
+    for _ in 0..iterations {
+        let mut sums = vec![[0f32; 3]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for p in pixels {
+            let p = p.map(|v| v as f32);
+            let nearest = centroids
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let d = (0..3).map(|ch| (p[ch] - c[ch]).powi(2)).sum::<f32>();
+                    (i, d)
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+                .expect("At least one centroid");
+            for ch in 0..3 {
+                sums[nearest][ch] += p[ch];
+            }
+            counts[nearest] += 1;
+        }
+
+        // Empty clusters keep their previous position
+        for (i, c) in centroids.iter_mut().enumerate() {
+            if counts[i] > 0 {
+                *c = sums[i].map(|v| v / counts[i] as f32);
+            }
+        }
+    }
+}
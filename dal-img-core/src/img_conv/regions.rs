@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+/// A connected area of pixels sharing one palette index
+pub struct Region {
+    pub color: usize,
+    /// Row-major pixel offsets
+    pub pixels: Vec<usize>,
+}
+
+/// Connected component labeling of an index image
+pub struct Regions {
+    /// Region id of every pixel
+    pub labels: Vec<usize>,
+    pub regions: Vec<Region>,
+}
+
+/// Splits an index image into 4-connected regions of equal palette index
+pub fn label_regions(indices: &[usize], dim: (u32, u32)) -> Regions {
+    let (w, h) = (dim.0 as usize, dim.1 as usize);
+    let mut labels = vec![usize::MAX; indices.len()];
+    let mut regions = vec![];
+    let mut queue = VecDeque::new();
+
+    for start in 0..indices.len() {
+        if labels[start] != usize::MAX {
+            continue;
+        }
+
+        let id = regions.len();
+        let color = indices[start];
+        let mut pixels = vec![];
+        labels[start] = id;
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            pixels.push(i);
+            let (x, y) = (i % w, i / w);
+            let neighbors = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w),
+                (y + 1 < h).then(|| i + w),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                if labels[n] == usize::MAX && indices[n] == color {
+                    labels[n] = id;
+                    queue.push_back(n);
+                }
+            }
+        }
+        regions.push(Region { color, pixels });
+    }
+
+    Regions { labels, regions }
+}
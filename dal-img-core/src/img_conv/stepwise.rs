@@ -0,0 +1,105 @@
+//! Conversion split into small steps, so a caller sharing its thread with a UI,
+//! like the web app, can handle input between them
+
+use std::collections::HashMap;
+
+use image::DynamicImage;
+
+use super::{cut_tiles, ConvSettings, DalImageConverter, NearestCache, Prepared};
+
+// Rows mapped per step, a small part of a frame even on a wall of boards
+const ROWS_PER_STEP: u32 = 8;
+
+enum Stage {
+    Source(DynamicImage),
+    Map {
+        prepared: Prepared<'static>,
+        // First row of the next step
+        row: u32,
+        cache: HashMap<[u8; 3], [u8; 3]>,
+        diffusion: Option<Vec<[f32; 3]>>,
+    },
+    Done(DynamicImage, DynamicImage),
+    // Only while a step moves on to the next stage
+    Empty,
+}
+
+/// A conversion that runs one step per call to `step`: resizing and the
+/// adjustments, then the palette mapping a few rows at a time, then the
+/// cleanup. The result is the same as `DalImageConverter::process_tiled`
+pub struct Conversion {
+    conv: DalImageConverter,
+    settings: ConvSettings,
+    tiles: (u32, u32),
+    board: (u32, u32),
+    stage: Stage,
+}
+
+impl Conversion {
+    pub fn new(conv: &DalImageConverter, img: DynamicImage, settings: &ConvSettings) -> Self {
+        let tiles = (settings.tiles.0.max(1), settings.tiles.1.max(1));
+        Self {
+            conv: conv.wall(tiles),
+            settings: settings.clone(),
+            tiles,
+            board: conv.dim,
+            stage: Stage::Source(img),
+        }
+    }
+
+    /// Runs the next step, returns false once the conversion is done
+    pub fn step(&mut self) -> bool {
+        self.stage = match std::mem::replace(&mut self.stage, Stage::Empty) {
+            Stage::Source(img) => Stage::Map {
+                prepared: self.conv.prepare(img, &self.settings).into_owned(),
+                row: 0,
+                cache: HashMap::new(),
+                diffusion: None,
+            },
+            Stage::Map {
+                mut prepared,
+                row,
+                cache,
+                mut diffusion,
+            } => {
+                let Prepared {
+                    conv, settings, rgb, ..
+                } = &mut prepared;
+                let conv: &DalImageConverter = conv;
+                let rows = row..(row + ROWS_PER_STEP).min(rgb.height());
+                let mut cache = NearestCache {
+                    conv,
+                    map: cache,
+                    hits: 0,
+                    misses: 0,
+                };
+                conv.map_rows(rgb, rows.clone(), settings, &mut cache, &mut diffusion);
+                let cache = cache.map;
+
+                if rows.end < prepared.rgb.height() {
+                    Stage::Map {
+                        prepared,
+                        row: rows.end,
+                        cache,
+                        diffusion,
+                    }
+                } else {
+                    let (reference, converted) = prepared.finish();
+                    Stage::Done(reference, converted)
+                }
+            }
+            stage => stage,
+        };
+        !matches!(self.stage, Stage::Done(..))
+    }
+
+    /// Runs the remaining steps and returns one image per board, row by row
+    /// as ((row, col), reference, image)
+    pub fn finish(mut self) -> Vec<((u32, u32), DynamicImage, DynamicImage)> {
+        while self.step() {}
+        let Stage::Done(reference, img) = self.stage else {
+            unreachable!("Every step ran");
+        };
+        cut_tiles(&reference, &img, self.tiles, self.board)
+    }
+}
@@ -0,0 +1,85 @@
+//! Chooses the few palette colors an image is limited to
+
+// Rounds of single swaps after the greedy start, they rarely need more
+const SWAP_PASSES: usize = 4;
+
+fn total(costs: &[Vec<f32>], weights: &[f32], chosen: &[usize]) -> f32 {
+    costs
+        .iter()
+        .zip(weights)
+        .map(|(row, w)| w * chosen.iter().map(|&c| row[c]).fold(f32::INFINITY, f32::min))
+        .sum()
+}
+
+/// Picks `k` candidates so that the cost of mapping every sample to its
+/// cheapest picked candidate, summed by weight, is as low as possible.
+/// `costs` has one row per sample with a column per candidate. Candidates are
+/// added greedily, then single ones are swapped out while that still helps
+pub fn best_subset(costs: &[Vec<f32>], weights: &[f32], k: usize) -> Vec<usize> {
+    let n = costs.first().map_or(0, Vec::len);
+    if k >= n {
+        return (0..n).collect();
+    }
+
+    let mut chosen: Vec<usize> = Vec::with_capacity(k);
+    let mut best = vec![f32::INFINITY; costs.len()];
+    for _ in 0..k {
+        let gain = |c: usize| -> f32 {
+            costs
+                .iter()
+                .zip(weights)
+                .zip(&best)
+                .map(|((row, w), b)| w * row[c].min(*b))
+                .sum()
+        };
+        let Some(c) = (0..n)
+            .filter(|c| !chosen.contains(c))
+            .min_by(|&a, &b| gain(a).total_cmp(&gain(b)))
+        else {
+            break;
+        };
+        for (b, row) in best.iter_mut().zip(costs) {
+            *b = b.min(row[c]);
+        }
+        chosen.push(c);
+    }
+
+    let mut current = total(costs, weights, &chosen);
+    for _ in 0..SWAP_PASSES {
+        let mut improved = false;
+        for slot in 0..chosen.len() {
+            // Cheapest cost of every sample without the candidate in this slot
+            let others: Vec<f32> = costs
+                .iter()
+                .map(|row| {
+                    chosen
+                        .iter()
+                        .enumerate()
+                        .filter(|&(s, _)| s != slot)
+                        .map(|(_, &c)| row[c])
+                        .fold(f32::INFINITY, f32::min)
+                })
+                .collect();
+            for c in (0..n).filter(|c| !chosen.contains(c)) {
+                let trial: f32 = costs
+                    .iter()
+                    .zip(weights)
+                    .zip(&others)
+                    .map(|((row, w), o)| w * row[c].min(*o))
+                    .sum();
+                if trial < current {
+                    chosen[slot] = c;
+                    current = trial;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    chosen.sort_unstable();
+    chosen
+}
@@ -0,0 +1,6 @@
+//! Palette conversion for Dale & Dawson boards and similar displays, plus the
+//! export formats built on top of it. Has no UI dependencies, the web app is
+//! one of its users.
+
+pub mod export;
+pub mod img_conv;
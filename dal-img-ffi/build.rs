@@ -0,0 +1,9 @@
+// Regenerates include/dal_img.h from the exported functions on every build
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::generate(&crate_dir)
+        .expect("Failed to generate the C header")
+        .write_to_file(format!("{crate_dir}/include/dal_img.h"));
+}
@@ -0,0 +1,251 @@
+//! C bindings for the converter, so native tooling maps colors exactly like
+//! the web app and the CLI. `build.rs` writes the matching header to
+//! `include/dal_img.h`.
+//!
+//! ```c
+//! const uint8_t palette[] = {0, 0, 0, 255, 255, 255};
+//! DalConverter *conv = dal_converter_new(palette, 2, 87, 60);
+//! DalBuffer png = {0};
+//! if (dal_convert(conv, data, len, &png) != DAL_STATUS_OK) {
+//!     fprintf(stderr, "%s\n", dal_last_error());
+//! }
+//! dal_buffer_free(png);
+//! dal_converter_free(conv);
+//! ```
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use anyhow::{anyhow, bail, Context};
+use dal_img_core::{
+    export::OutputFormat,
+    img_conv::{self, ConvSettings, DalImageConverter},
+};
+use image::{DynamicImage, RgbImage};
+
+/// Result of every fallible call, details are in `dal_last_error`
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DalStatus {
+    Ok = 0,
+    InvalidArgument = 1,
+    Failed = 2,
+}
+
+/// Bytes owned by the library, release them with `dal_buffer_free`
+#[repr(C)]
+pub struct DalBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl DalBuffer {
+    fn new(data: Vec<u8>) -> Self {
+        let len = data.len();
+        let data = Box::into_raw(data.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// A palette and target size images are converted to, opaque to C
+pub struct DalConverter {
+    conv: DalImageConverter,
+    settings: ConvSettings,
+}
+
+impl DalConverter {
+    fn indices(&self, img: DynamicImage) -> Vec<u8> {
+        let img = self.conv.process(img, &self.settings);
+        self.conv.indices(&img.to_rgb8()).into_iter().map(|i| i as u8).collect()
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) {
+    // Interior NULs would cut the message short anyway
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+// Runs `f`, turning errors and panics into a status plus `dal_last_error`,
+// a panic must never unwind into C
+fn guard(status: DalStatus, f: impl FnOnce() -> anyhow::Result<()>) -> DalStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => DalStatus::Ok,
+        Ok(Err(e)) => {
+            set_error(format!("{e:#}"));
+            status
+        }
+        Err(_) => {
+            set_error("Internal error".into());
+            DalStatus::Failed
+        }
+    }
+}
+
+unsafe fn slice<'a>(data: *const u8, len: usize) -> anyhow::Result<&'a [u8]> {
+    if data.is_null() {
+        bail!("Null data pointer");
+    }
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+// Checks the pointers every conversion gets and stores its result in `out`
+unsafe fn convert_into(
+    conv: *const DalConverter,
+    out: *mut DalBuffer,
+    f: impl FnOnce(&DalConverter) -> anyhow::Result<Vec<u8>>,
+) -> DalStatus {
+    if conv.is_null() || out.is_null() {
+        set_error("Null converter or output pointer".into());
+        return DalStatus::InvalidArgument;
+    }
+    guard(DalStatus::Failed, || {
+        out.write(DalBuffer::new(f(&*conv)?));
+        Ok(())
+    })
+}
+
+/// Creates a converter for `colors` RGB triples in `palette` and a target size.
+/// Returns NULL for an empty palette, more than 256 colors or a zero size
+///
+/// # Safety
+/// `palette` must point to `colors * 3` readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn dal_converter_new(
+    palette: *const u8,
+    colors: usize,
+    width: u32,
+    height: u32,
+) -> *mut DalConverter {
+    let mut conv = ptr::null_mut();
+    guard(DalStatus::InvalidArgument, || {
+        // Indices are handed out as bytes
+        if colors == 0 || colors > 256 {
+            bail!("Palette must have between 1 and 256 colors");
+        }
+        if width == 0 || height == 0 {
+            bail!("Size must not be zero");
+        }
+        let palette: Vec<[u8; 3]> = slice(palette, colors * 3)?
+            .chunks_exact(3)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect();
+        conv = Box::into_raw(Box::new(DalConverter {
+            conv: DalImageConverter::with_lut(&palette, (width, height), Default::default()),
+            settings: ConvSettings::default(),
+        }));
+        Ok(())
+    });
+    conv
+}
+
+/// # Safety
+/// `conv` must come from `dal_converter_new` and not be used afterwards, NULL is ignored
+#[no_mangle]
+pub unsafe extern "C" fn dal_converter_free(conv: *mut DalConverter) {
+    if !conv.is_null() {
+        drop(Box::from_raw(conv));
+    }
+}
+
+/// Replaces the conversion settings with JSON as saved by the web app.
+/// Missing fields keep their defaults
+///
+/// # Safety
+/// `conv` must be a live converter and `json` a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn dal_converter_set_settings(conv: *mut DalConverter, json: *const c_char) -> DalStatus {
+    if conv.is_null() || json.is_null() {
+        set_error("Null converter or settings pointer".into());
+        return DalStatus::InvalidArgument;
+    }
+    guard(DalStatus::InvalidArgument, || {
+        let json = CStr::from_ptr(json).to_str().context("Settings are not UTF-8")?;
+        (*conv).settings = serde_json::from_str(json).context("Invalid settings")?;
+        Ok(())
+    })
+}
+
+/// Converts an encoded image (PNG, JPEG, GIF, WebP, ...) and stores the result as PNG in `out`.
+/// On failure `out` is left untouched
+///
+/// # Safety
+/// `conv` must be a live converter, `data` must point to `len` readable bytes
+/// and `out` to a writable `DalBuffer`
+#[no_mangle]
+pub unsafe extern "C" fn dal_convert(
+    conv: *const DalConverter,
+    data: *const u8,
+    len: usize,
+    out: *mut DalBuffer,
+) -> DalStatus {
+    convert_into(conv, out, |conv| {
+        let img = img_conv::load_image(slice(data, len)?)?;
+        OutputFormat::Png.encode(&conv.conv.process(img, &conv.settings))
+    })
+}
+
+/// Converts an encoded image and stores the palette index of every pixel,
+/// row-major, in `out`. On failure `out` is left untouched
+///
+/// # Safety
+/// Same as `dal_convert`
+#[no_mangle]
+pub unsafe extern "C" fn dal_convert_indices(
+    conv: *const DalConverter,
+    data: *const u8,
+    len: usize,
+    out: *mut DalBuffer,
+) -> DalStatus {
+    convert_into(conv, out, |conv| {
+        let img = img_conv::load_image(slice(data, len)?)?;
+        Ok(conv.indices(img))
+    })
+}
+
+/// Like `dal_convert_indices`, but takes raw RGB pixels, row-major without padding,
+/// which skips decoding entirely. On failure `out` is left untouched
+///
+/// # Safety
+/// `rgb` must point to `width * height * 3` readable bytes, otherwise as `dal_convert`
+#[no_mangle]
+pub unsafe extern "C" fn dal_convert_rgb(
+    conv: *const DalConverter,
+    rgb: *const u8,
+    width: u32,
+    height: u32,
+    out: *mut DalBuffer,
+) -> DalStatus {
+    convert_into(conv, out, |conv| {
+        let len = width as usize * height as usize * 3;
+        let img = RgbImage::from_raw(width, height, slice(rgb, len)?.to_vec())
+            .ok_or_else(|| anyhow!("Invalid image size"))?;
+        Ok(conv.indices(DynamicImage::ImageRgb8(img)))
+    })
+}
+
+/// Releases a buffer filled by one of the conversions
+///
+/// # Safety
+/// `buffer` must come from this library and not be freed twice
+#[no_mangle]
+pub unsafe extern "C" fn dal_buffer_free(buffer: DalBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)));
+    }
+}
+
+/// Message of the last failed call on this thread, NULL if there was none.
+/// Valid until the next failing call
+#[no_mangle]
+pub extern "C" fn dal_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
@@ -0,0 +1,173 @@
+//! Python bindings for the converter, built with [maturin](https://www.maturin.rs/).
+//!
+//! ```python
+//! import dale_img
+//!
+//! conv = dale_img.Converter.for_device("Dale & Dawson Board")
+//! png = conv.convert(open("chart.png", "rb").read())
+//! ```
+
+use dal_img_core::{
+    export::OutputFormat,
+    img_conv::{self, palettes, presets, quantize, ConvSettings, DalImageConverter},
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+fn py_error(e: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{e:#}"))
+}
+
+// Indices are handed out as bytes, so palettes are capped at 256 colors
+fn check_palette(palette: &[[u8; 3]]) -> PyResult<()> {
+    if palette.is_empty() || palette.len() > 256 {
+        return Err(PyValueError::new_err("Palette must have between 1 and 256 colors"));
+    }
+    Ok(())
+}
+
+// Case-insensitive prefix match, like the CLI's `--device`
+fn find_by_name<'a, T>(items: &'a [T], name: &str, item_name: impl Fn(&T) -> &str) -> Option<&'a T> {
+    let name = name.to_lowercase();
+    items.iter().find(|i| item_name(i).to_lowercase().starts_with(&name))
+}
+
+/// A palette and target size images are converted to
+#[pyclass(module = "dale_img")]
+struct Converter {
+    conv: DalImageConverter,
+    settings: ConvSettings,
+}
+
+#[pymethods]
+impl Converter {
+    /// `palette` is a list of `(r, g, b)` tuples
+    #[new]
+    fn new(palette: Vec<[u8; 3]>, width: u32, height: u32) -> PyResult<Self> {
+        check_palette(&palette)?;
+        if width == 0 || height == 0 {
+            return Err(PyValueError::new_err("Size must not be zero"));
+        }
+        Ok(Self {
+            conv: DalImageConverter::with_lut(&palette, (width, height), Default::default()),
+            settings: ConvSettings::default(),
+        })
+    }
+
+    /// Uses the size and palette of a board preset, see `devices()`
+    #[staticmethod]
+    fn for_device(name: &str) -> PyResult<Self> {
+        let device = find_by_name(&presets::DEVICES, name, |d| d.name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown device {name}")))?;
+        Self::new(device.palette.to_vec(), device.dim.0, device.dim.1)
+    }
+
+    /// Replaces the conversion settings with JSON as saved by the web app.
+    /// Missing fields keep their defaults
+    fn set_settings(&mut self, json: &str) -> PyResult<()> {
+        self.settings = serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(())
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.conv.dim().0
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.conv.dim().1
+    }
+
+    #[getter]
+    fn palette(&self) -> Vec<[u8; 3]> {
+        self.conv.palette().to_vec()
+    }
+
+    #[setter]
+    fn set_palette(&mut self, palette: Vec<[u8; 3]>) -> PyResult<()> {
+        check_palette(&palette)?;
+        self.conv.set_palette(&palette);
+        Ok(())
+    }
+
+    /// Which palette colors take part in matching, one flag per color
+    #[getter]
+    fn enabled(&self) -> Vec<bool> {
+        self.conv.enabled().to_vec()
+    }
+
+    #[setter]
+    fn set_enabled(&mut self, enabled: Vec<bool>) -> PyResult<()> {
+        if enabled.len() != self.conv.palette().len() || !enabled.contains(&true) {
+            return Err(PyValueError::new_err("Need one flag per color with at least one enabled"));
+        }
+        self.conv.set_enabled(&enabled);
+        Ok(())
+    }
+
+    /// Converts an encoded image (PNG, JPEG, GIF, WebP, ...) and returns the result as PNG
+    fn convert<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let png = py.allow_threads(|| {
+            let img = img_conv::load_image(data)?;
+            OutputFormat::Png.encode(&self.conv.process(img, &self.settings))
+        });
+        Ok(PyBytes::new_bound(py, &png.map_err(py_error)?))
+    }
+
+    /// Converts an encoded image and returns the palette index of every pixel, row-major
+    fn convert_to_indices<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let indices = py.allow_threads(|| {
+            let img = img_conv::load_image(data)?;
+            let img = self.conv.process(img, &self.settings);
+            anyhow::Ok(self.conv.indices(&img.to_rgb8()).into_iter().map(|i| i as u8).collect::<Vec<_>>())
+        });
+        Ok(PyBytes::new_bound(py, &indices.map_err(py_error)?))
+    }
+}
+
+/// The built-in palettes by name
+// Named apart from the `palettes` module, the one `#[pyfunction]` generates would clash with it
+#[pyfunction]
+#[pyo3(name = "palettes")]
+fn builtin_palettes() -> Vec<(&'static str, Vec<[u8; 3]>)> {
+    palettes::PRESETS.iter().map(|p| (p.name, p.colors.to_vec())).collect()
+}
+
+/// The board presets as `(name, (width, height))`
+#[pyfunction]
+fn devices() -> Vec<(&'static str, (u32, u32))> {
+    presets::DEVICES.iter().map(|d| (d.name, d.dim)).collect()
+}
+
+/// Looks up a built-in palette by (a prefix of) its name
+#[pyfunction]
+fn palette(name: &str) -> PyResult<Vec<[u8; 3]>> {
+    find_by_name(&palettes::PRESETS, name, |p| p.name)
+        .map(|p| p.colors.to_vec())
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown palette {name}")))
+}
+
+/// Parses a .gpl, .json or .hex palette, the format is picked by `file_name`
+#[pyfunction]
+fn parse_palette(file_name: &str, text: &str) -> PyResult<Vec<[u8; 3]>> {
+    palettes::parse_palette(file_name, text).map_err(py_error)
+}
+
+/// Picks the `colors` dominant colors of an encoded image
+#[pyfunction]
+#[pyo3(signature = (data, colors = 18))]
+fn extract_palette(data: &[u8], colors: usize) -> PyResult<Vec<[u8; 3]>> {
+    let img = img_conv::load_image(data).map_err(py_error)?;
+    Ok(quantize::extract_palette(&img, colors.clamp(1, 256)))
+}
+
+#[pymodule]
+fn dale_img(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Converter>()?;
+    m.add_function(wrap_pyfunction!(builtin_palettes, m)?)?;
+    m.add_function(wrap_pyfunction!(devices, m)?)?;
+    m.add_function(wrap_pyfunction!(palette, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_palette, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_palette, m)?)?;
+    Ok(())
+}
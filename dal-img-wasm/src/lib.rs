@@ -0,0 +1,114 @@
+//! JavaScript bindings for the converter, for web apps that want the palette
+//! mapping without the Dioxus UI.
+//!
+//! ```js
+//! const conv = new DalConverter(new Uint8Array([0, 0, 0, 255, 255, 255]), 87, 60);
+//! const png = conv.convert(new Uint8Array(await file.arrayBuffer()));
+//! ```
+
+use dal_img_core::{
+    export::OutputFormat,
+    img_conv::{self, ConvSettings, DalImageConverter},
+};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const SETTINGS_TYPE: &str = r#"
+/** Conversion settings as accepted by `DalConverter.setSettings` (as JSON), every field is optional */
+export interface DalConverterSettings {
+    auto_rotate?: boolean;
+    dither?: "None" | "ErrorDiffusion" | "Ordered";
+    kernel?: "FloydSteinberg" | "Atkinson" | "JarvisJudiceNinke" | "Stucki" | "Sierra";
+    serpentine?: boolean;
+    bayer_size?: "X2" | "X4" | "X8";
+    linear_light?: boolean;
+    fit?: "Stretch" | "Letterbox" | "CoverCrop" | "Center";
+    fill_color?: [number, number, number];
+    filter?: "Nearest" | "Triangle" | "CatmullRom" | "Lanczos3";
+    pixel_art?: "Auto" | "On" | "Off";
+    tiles?: [number, number];
+    transparent_color?: [number, number, number];
+    alpha_mode?: "Threshold" | "Composite" | "Keep";
+    alpha_threshold?: number;
+    alpha_background?: [number, number, number];
+    levels?: { black?: number; white?: number; gamma?: number };
+    curve?: { points?: [number, number][] };
+    denoise?: { mode?: "None" | "Median" | "Bilateral"; radius?: number; strength?: number };
+    auto_enhance?: { white_balance?: boolean; exposure?: boolean };
+    equalize?: { mode?: "None" | "Global" | "Clahe"; tiles?: number; clip_limit?: number };
+    tone?: { exposure?: number; brightness?: number; contrast?: number };
+    saturation?: { saturation?: number; hue?: number };
+    sharpen?: { amount?: number; radius?: number; threshold?: number };
+    noise?: { kind?: "None" | "Triangular" | "BlueNoise"; amount?: number; seed?: number };
+    despeckle?: boolean;
+    min_region?: number;
+    max_colors?: number;
+}
+"#;
+
+fn js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&format!("{e:#}"))
+}
+
+/// A palette and target size images are converted to
+#[wasm_bindgen]
+pub struct DalConverter {
+    conv: DalImageConverter,
+    settings: ConvSettings,
+}
+
+#[wasm_bindgen]
+impl DalConverter {
+    /// `palette` holds the colors as consecutive RGB bytes
+    #[wasm_bindgen(constructor)]
+    pub fn new(palette: &[u8], width: u32, height: u32) -> Result<DalConverter, JsError> {
+        if palette.is_empty() || palette.len() % 3 != 0 {
+            return Err(JsError::new("Palette must be a non-empty list of RGB triples"));
+        }
+        // Indices are handed out as bytes
+        if palette.len() > 256 * 3 {
+            return Err(JsError::new("Palette must have at most 256 colors"));
+        }
+        if width == 0 || height == 0 {
+            return Err(JsError::new("Size must not be zero"));
+        }
+        let palette: Vec<[u8; 3]> = palette.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        Ok(Self {
+            conv: DalImageConverter::with_lut(&palette, (width, height), Default::default()),
+            settings: ConvSettings::default(),
+        })
+    }
+
+    /// Replaces the conversion settings with a JSON encoded `DalConverterSettings`
+    /// object, as saved by the web app. Missing fields keep their defaults
+    #[wasm_bindgen(js_name = setSettings)]
+    pub fn set_settings(&mut self, json: &str) -> Result<(), JsError> {
+        self.settings = serde_json::from_str(json).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.conv.dim().0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.conv.dim().1
+    }
+
+    /// Converts an encoded image (PNG, JPEG, GIF, WebP, ...) and returns the result as PNG
+    pub fn convert(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        let img = img_conv::load_image(data).map_err(js_error)?;
+        let img = self.conv.process(img, &self.settings);
+        OutputFormat::Png.encode(&img).map_err(js_error)
+    }
+
+    /// Converts an encoded image and returns the palette index of every pixel, row-major
+    #[wasm_bindgen(js_name = convertToIndices)]
+    pub fn convert_to_indices(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        let img = img_conv::load_image(data).map_err(js_error)?;
+        let img = self.conv.process(img, &self.settings);
+        Ok(self.conv.indices(&img.to_rgb8()).into_iter().map(|i| i as u8).collect())
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/dale_img.proto").expect("Failed to compile proto/dale_img.proto");
+}
@@ -0,0 +1,100 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Context;
+use clap::Args;
+use dal_img_core::{
+    export::artnet::{self, FixtureLayout},
+    img_conv::{self, ConvSettings, DalImageConverter},
+};
+
+use crate::pipeline::{self, ConvertArgs};
+
+/// Options of the `artnet` subcommand
+#[derive(Args, Debug)]
+pub struct ArtnetArgs {
+    /// Image streamed to the wall, converted again whenever the file changes
+    pub input: PathBuf,
+    /// Address of the Art-Net node, broadcasts to the local network by default
+    #[arg(long, default_value_t = Ipv4Addr::BROADCAST)]
+    pub target: Ipv4Addr,
+    /// JSON fixture layout, defaults to one chain over the whole image starting at universe 0
+    #[arg(long)]
+    pub layout: Option<PathBuf>,
+    /// Frames per second, nodes fall back to black when the stream stops
+    #[arg(long, default_value_t = 30)]
+    pub fps: u32,
+    /// Sends the frame once and exits
+    #[arg(long)]
+    pub once: bool,
+    #[command(flatten)]
+    pub args: ConvertArgs,
+}
+
+fn load_layout(path: &Path) -> anyhow::Result<FixtureLayout> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Invalid fixture layout in {}", path.display()))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Converts the input and splits it into the DMX data of every universe
+fn frame(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    layout: Option<&FixtureLayout>,
+    input: &Path,
+) -> anyhow::Result<Vec<(u16, Vec<u8>)>> {
+    let data = std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let img = conv.process(img_conv::load_image(&data)?, settings).to_rgb8();
+    let layout = match layout {
+        Some(layout) => layout.clone(),
+        None => FixtureLayout::whole_image(img.dimensions()),
+    };
+    Ok(layout.map(&img)?.into_iter().collect())
+}
+
+/// Streams the converted input to an Art-Net node until interrupted
+pub fn artnet(args: &ArtnetArgs) -> anyhow::Result<()> {
+    let (conv, settings) = pipeline::configure(&args.args)?;
+    let layout = args.layout.as_deref().map(load_layout).transpose()?;
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).context("Failed to open a UDP socket")?;
+    socket.set_broadcast(args.target.is_broadcast())?;
+    let target = SocketAddr::from((args.target, artnet::PORT));
+    let interval = Duration::from_secs(1) / args.fps.max(1);
+
+    let mut stamp = modified(&args.input);
+    let mut universes = frame(&conv, &settings, layout.as_ref(), &args.input)?;
+    eprintln!("Streaming {} universe(s) to {target}", universes.len());
+    // 0 would tell the node not to reorder, so the sequence runs 1..=255
+    let mut sequence = 1u8;
+    loop {
+        let start = Instant::now();
+        for (universe, data) in &universes {
+            socket
+                .send_to(&artnet::dmx_packet(*universe, sequence, data), target)
+                .with_context(|| format!("Failed to send to {target}"))?;
+        }
+        if args.once {
+            return Ok(());
+        }
+        sequence = sequence % 255 + 1;
+
+        let current = modified(&args.input);
+        if current != stamp {
+            stamp = current;
+            // A half written file keeps the last frame on the wall
+            match frame(&conv, &settings, layout.as_ref(), &args.input) {
+                Ok(next) => universes = next,
+                Err(e) => eprintln!("{}: {e:#}", args.input.display()),
+            }
+        }
+        thread::sleep(interval.saturating_sub(start.elapsed()));
+    }
+}
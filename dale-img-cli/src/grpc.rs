@@ -0,0 +1,118 @@
+use std::pin::Pin;
+
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+
+use crate::remote::{self, RemoteOptions};
+
+mod proto {
+    tonic::include_proto!("dale_img");
+}
+
+use proto::{
+    converter_server::{Converter, ConverterServer},
+    ConvertOptions, ConvertReply, ConvertRequest,
+};
+
+// Replies buffered per batch stream before the client has to catch up
+const BATCH_BUFFER: usize = 8;
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+fn non_zero(v: u32) -> Option<u32> {
+    (v != 0).then_some(v)
+}
+
+impl From<ConvertOptions> for RemoteOptions {
+    fn from(options: ConvertOptions) -> Self {
+        Self {
+            palette: non_empty(options.palette),
+            device: non_empty(options.device),
+            dither: non_empty(options.dither),
+            width: non_zero(options.width),
+            height: non_zero(options.height),
+            format: non_empty(options.format),
+        }
+    }
+}
+
+// Converts off the async workers through `remote::convert`, which also caps
+// the requested size. Rejected requests fail with `invalid_argument`, a
+// panicked conversion with `internal`
+async fn run(options: Option<ConvertOptions>, image: Vec<u8>) -> Result<ConvertReply, Status> {
+    let options = options.unwrap_or_default().into();
+    let result = tokio::task::spawn_blocking(move || remote::convert(options, &image)).await;
+    match result {
+        Ok(Ok((output, data))) => Ok(ConvertReply {
+            content_type: output.mime().to_string(),
+            data,
+            ..Default::default()
+        }),
+        Ok(Err(e)) => Err(Status::invalid_argument(format!("{e:#}"))),
+        Err(e) => Err(Status::internal(e.to_string())),
+    }
+}
+
+// Batch replies carry their failure, so one bad image doesn't end the stream
+async fn convert(request: ConvertRequest) -> ConvertReply {
+    let ConvertRequest { name, image, options } = request;
+    match run(options, image).await {
+        Ok(reply) => ConvertReply { name, ..reply },
+        Err(status) => ConvertReply {
+            name,
+            error: status.message().to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+#[derive(Default)]
+struct ConverterService;
+
+#[tonic::async_trait]
+impl Converter for ConverterService {
+    async fn convert(&self, request: Request<ConvertRequest>) -> Result<Response<ConvertReply>, Status> {
+        let ConvertRequest { name, image, options } = request.into_inner();
+        let reply = run(options, image).await?;
+        Ok(Response::new(ConvertReply { name, ..reply }))
+    }
+
+    type ConvertBatchStream = Pin<Box<dyn Stream<Item = Result<ConvertReply, Status>> + Send>>;
+
+    async fn convert_batch(
+        &self,
+        request: Request<Streaming<ConvertRequest>>,
+    ) -> Result<Response<Self::ConvertBatchStream>, Status> {
+        let mut requests = request.into_inner();
+        let (tx, rx) = tokio::sync::mpsc::channel(BATCH_BUFFER);
+        tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let reply = match request {
+                    Ok(request) => Ok(convert(request).await),
+                    Err(status) => Err(status),
+                };
+                // Stop once the client hung up
+                if tx.send(reply).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Serves the gRPC converter until the process is stopped
+pub fn serve(addr: &str) -> anyhow::Result<()> {
+    let addr = addr.parse()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        eprintln!("Listening on {addr}");
+        Server::builder()
+            .add_service(ConverterServer::new(ConverterService))
+            .serve(addr)
+            .await?;
+        Ok(())
+    })
+}
@@ -0,0 +1,260 @@
+mod artnet;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod pipeline;
+#[cfg(any(feature = "server", feature = "grpc"))]
+mod remote;
+#[cfg(feature = "server")]
+mod server;
+mod watch;
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use artnet::ArtnetArgs;
+use clap::{Parser, Subcommand, ValueEnum};
+use dal_img_core::img_conv::{self, palettes, quantize};
+use pipeline::{ConvertArgs, Output};
+use watch::WatchArgs;
+
+/// Converts images to the palette and resolution of Dale & Dawson boards
+/// and similar displays, using the same pipeline as the web app
+#[derive(Parser, Debug)]
+#[command(name = "dale-img", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Converts a single image
+    Convert {
+        /// Input file, `-` reads from stdin
+        input: PathBuf,
+        /// Output file, `-` writes to stdout. Defaults to the input name with
+        /// the format's extension, or stdout when reading from stdin
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        args: ConvertArgs,
+    },
+    /// Converts every file matching the given paths or glob patterns
+    Batch {
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Directory the results are written to
+        #[arg(long, short)]
+        output: PathBuf,
+        #[command(flatten)]
+        args: ConvertArgs,
+    },
+    /// Converts every image that appears in a directory until interrupted
+    Watch(WatchArgs),
+    /// Streams a converted image to an LED wall over Art-Net
+    Artnet(ArtnetArgs),
+    /// Lists, extracts and converts palettes
+    Palette {
+        #[command(subcommand)]
+        command: PaletteCommand,
+    },
+    /// Serves `POST /convert` over HTTP, taking a multipart image upload and
+    /// `palette`, `device`, `dither`, `width`, `height` and `format` query parameters
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Serves the gRPC converter described in proto/dale_img.proto
+    #[cfg(feature = "grpc")]
+    ServeGrpc {
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PaletteCommand {
+    /// Prints the built-in palette and device presets
+    List,
+    /// Picks the dominant colors of a reference image
+    Extract {
+        image: PathBuf,
+        #[arg(long, short, default_value_t = 18)]
+        colors: usize,
+        #[arg(long, short, value_enum, default_value_t = PaletteFormat::Gpl)]
+        format: PaletteFormat,
+    },
+    /// Prints a preset or palette file in another format
+    Show {
+        /// Preset name or palette file
+        palette: String,
+        #[arg(long, short, value_enum, default_value_t = PaletteFormat::Hex)]
+        format: PaletteFormat,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PaletteFormat {
+    Gpl,
+    Json,
+    Hex,
+}
+
+fn print_palette(palette: &[[u8; 3]], format: PaletteFormat) {
+    match format {
+        PaletteFormat::Gpl => print!("{}", palettes::to_gpl(palette, "Dale")),
+        PaletteFormat::Json => println!("{}", palettes::to_json(palette)),
+        PaletteFormat::Hex => {
+            for &c in palette {
+                println!("{}", palettes::to_hex(c));
+            }
+        }
+    }
+}
+
+// Stands for stdin or stdout in place of a file name
+const STDIO: &str = "-";
+
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new(STDIO)
+}
+
+// Reads a whole input, decoders need to seek so stdin is buffered completely
+fn read_input(input: &Path) -> anyhow::Result<Vec<u8>> {
+    if is_stdio(input) {
+        let mut data = vec![];
+        std::io::stdin().lock().read_to_end(&mut data).context("Failed to read stdin")?;
+        return Ok(data);
+    }
+    std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))
+}
+
+fn write_stdout(data: &[u8]) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(data).and_then(|_| stdout.flush()).context("Failed to write stdout")
+}
+
+fn file_stem(path: &Path) -> String {
+    if is_stdio(path) {
+        return "stdin".into();
+    }
+    path.file_stem().map_or("image".into(), |s| s.to_string_lossy().into_owned())
+}
+
+// Output format from the flag, else from the output name, else PNG
+fn output_format(args: &ConvertArgs, output: Option<&Path>) -> Output {
+    args.format
+        .or_else(|| output.and_then(Output::from_path))
+        .unwrap_or(Output::Png)
+}
+
+fn convert(input: &Path, output: Option<PathBuf>, args: &ConvertArgs) -> anyhow::Result<()> {
+    let (conv, settings) = pipeline::configure(args)?;
+    let format = output_format(args, output.as_deref().filter(|p| !is_stdio(p)));
+    let data = read_input(input)?;
+    let results = pipeline::convert_bytes(&conv, &settings, &data, &file_stem(input), format)?;
+
+    let to_stdout = match &output {
+        Some(path) => is_stdio(path),
+        None => is_stdio(input),
+    };
+    if to_stdout {
+        let [(_, bytes)] = results.as_slice() else {
+            bail!("Split images produce several files and can't be written to stdout");
+        };
+        return write_stdout(bytes);
+    }
+
+    let dir = input.parent().filter(|_| !is_stdio(input)).unwrap_or(Path::new("."));
+    for (name, bytes) in results {
+        // An explicit output name only fits a single result, tiles keep their generated names
+        let path = match &output {
+            Some(path) if settings.tiles == (1, 1) => path.clone(),
+            Some(path) => path.parent().unwrap_or(Path::new(".")).join(name),
+            None => dir.join(name),
+        };
+        std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn batch(inputs: &[String], output: &Path, args: &ConvertArgs) -> anyhow::Result<()> {
+    let (conv, settings) = pipeline::configure(args)?;
+    let format = output_format(args, None);
+    std::fs::create_dir_all(output).with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let mut files = vec![];
+    for pattern in inputs {
+        let matches = glob::glob(pattern).with_context(|| format!("Invalid pattern {pattern}"))?;
+        files.extend(matches.filter_map(Result::ok).filter(|p| p.is_file()));
+    }
+    if files.is_empty() {
+        bail!("No input files found");
+    }
+
+    let mut failed = 0;
+    for input in &files {
+        let result = std::fs::read(input)
+            .with_context(|| format!("Failed to read {}", input.display()))
+            .and_then(|data| pipeline::convert_bytes(&conv, &settings, &data, &file_stem(input), format));
+        match result {
+            Ok(results) => {
+                for (name, bytes) in results {
+                    let path = output.join(name);
+                    std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+                    eprintln!("{}", path.display());
+                }
+            }
+            // Keep going, one broken file shouldn't stop a batch
+            Err(e) => {
+                eprintln!("{}: {e:#}", input.display());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} of {} files failed", files.len());
+    }
+    Ok(())
+}
+
+fn palette(command: PaletteCommand) -> anyhow::Result<()> {
+    match command {
+        PaletteCommand::List => {
+            println!("Palettes:");
+            for preset in &palettes::PRESETS {
+                println!("  {} ({} colors)", preset.name, preset.colors.len());
+            }
+            println!("Devices:");
+            for device in &img_conv::presets::DEVICES {
+                println!("  {} ({}x{})", device.name, device.dim.0, device.dim.1);
+            }
+        }
+        PaletteCommand::Extract { image, colors, format } => {
+            let data = std::fs::read(&image).with_context(|| format!("Failed to read {}", image.display()))?;
+            let img = img_conv::load_image(&data)?;
+            print_palette(&quantize::extract_palette(&img, colors.clamp(1, 256)), format);
+        }
+        PaletteCommand::Show { palette, format } => print_palette(&pipeline::load_palette(&palette)?, format),
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Convert { input, output, args } => convert(&input, output, &args),
+        Command::Batch { inputs, output, args } => batch(&inputs, &output, &args),
+        Command::Watch(args) => watch::watch(&args),
+        Command::Artnet(args) => artnet::artnet(&args),
+        Command::Palette { command } => palette(command),
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => server::serve(&addr),
+        #[cfg(feature = "grpc")]
+        Command::ServeGrpc { addr } => grpc::serve(&addr),
+    }
+}
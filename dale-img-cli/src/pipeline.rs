@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use clap::{Args, ValueEnum};
+use dal_img_core::{
+    export::{codegen, grid, OutputFormat},
+    img_conv::{self, palettes, presets, ConvSettings, DalImageConverter, Dither},
+};
+use image::DynamicImage;
+
+/// What a converted image is written as
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Png,
+    Bmp,
+    Gif,
+    Webp,
+    Jpg,
+    /// Palette indices as CSV, -1 for transparent pixels
+    Csv,
+    /// Palette indices as JSON rows
+    Json,
+    /// Raw palette indices, one per byte
+    Bin,
+    /// Raw palette indices, two per byte
+    Bin4,
+}
+
+impl Output {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Output::Png => "png",
+            Output::Bmp => "bmp",
+            Output::Gif => "gif",
+            Output::Webp => "webp",
+            Output::Jpg => "jpg",
+            Output::Csv => "csv",
+            Output::Json => "json",
+            Output::Bin | Output::Bin4 => "bin",
+        }
+    }
+
+    #[cfg(any(feature = "server", feature = "grpc"))]
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Output::Png => "image/png",
+            Output::Bmp => "image/bmp",
+            Output::Gif => "image/gif",
+            Output::Webp => "image/webp",
+            Output::Jpg => "image/jpeg",
+            Output::Csv => "text/csv",
+            Output::Json => "application/json",
+            Output::Bin | Output::Bin4 => "application/octet-stream",
+        }
+    }
+
+    /// Picks the output from a file extension, for `-o` without `--format`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::from_str(ext, true).ok().or(match ext.to_ascii_lowercase().as_str() {
+            "jpeg" => Some(Output::Jpg),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DitherArg {
+    None,
+    ErrorDiffusion,
+    Ordered,
+}
+
+/// Options shared by every subcommand that converts images
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    /// Settings JSON as saved by the web app, flags below override it
+    #[arg(long)]
+    pub settings: Option<String>,
+    /// Palette preset name (prefix, case-insensitive) or a .gpl/.json/.hex palette file
+    #[arg(long, short)]
+    pub palette: Option<String>,
+    /// Device preset name (prefix, case-insensitive), sets size and palette
+    #[arg(long)]
+    pub device: Option<String>,
+    /// Output size as WIDTHxHEIGHT
+    #[arg(long, value_parser = parse_size)]
+    pub size: Option<(u32, u32)>,
+    #[arg(long, value_enum)]
+    pub dither: Option<DitherArg>,
+    /// Output format, guessed from the output file name when missing
+    #[arg(long, short, value_enum)]
+    pub format: Option<Output>,
+}
+
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s.split_once(['x', 'X']).ok_or("expected WIDTHxHEIGHT")?;
+    let size = (w.parse().map_err(|_| "invalid width")?, h.parse().map_err(|_| "invalid height")?);
+    if size.0 == 0 || size.1 == 0 {
+        return Err("size must not be zero".into());
+    }
+    Ok(size)
+}
+
+fn find_preset<'a, T>(items: &'a [T], name: &str, item_name: fn(&T) -> &str) -> Option<&'a T> {
+    let name = name.to_ascii_lowercase();
+    items.iter().find(|item| item_name(item).to_ascii_lowercase().starts_with(&name))
+}
+
+/// Builds the converter and settings described by the flags
+pub fn configure(args: &ConvertArgs) -> anyhow::Result<(DalImageConverter, ConvSettings)> {
+    let mut settings = match &args.settings {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+            serde_json::from_str(&text).with_context(|| format!("Invalid settings in {path}"))?
+        }
+        None => ConvSettings::default(),
+    };
+    if let Some(dither) = args.dither {
+        settings.dither = match dither {
+            DitherArg::None => Dither::None,
+            DitherArg::ErrorDiffusion => Dither::ErrorDiffusion,
+            DitherArg::Ordered => Dither::Ordered,
+        };
+    }
+
+    let mut conv = DalImageConverter::default();
+    if let Some(name) = &args.device {
+        let Some(device) = find_preset(&presets::DEVICES, name, |d| d.name) else {
+            bail!("Unknown device {name}");
+        };
+        conv.set_device(device);
+    }
+    if let Some(palette) = &args.palette {
+        conv.set_palette(&load_palette(palette)?);
+    }
+    if let Some(size) = args.size {
+        conv.set_dim(size);
+    }
+    Ok((conv, settings))
+}
+
+/// A palette preset by name
+pub fn preset_palette(name: &str) -> Option<Vec<[u8; 3]>> {
+    find_preset(&palettes::PRESETS, name, |p| p.name).map(|p| p.colors.to_vec())
+}
+
+/// A palette preset by name, or else a palette file
+pub fn load_palette(name: &str) -> anyhow::Result<Vec<[u8; 3]>> {
+    if let Some(palette) = preset_palette(name) {
+        return Ok(palette);
+    }
+    let text = std::fs::read_to_string(name).with_context(|| format!("No palette preset or file named {name}"))?;
+    palettes::parse_palette(name, &text)
+}
+
+/// Converts one decoded image. Split settings produce one image per tile,
+/// named by `stem` with the tile position appended like the web app does
+pub fn convert(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    img: DynamicImage,
+    stem: &str,
+) -> Vec<(String, DynamicImage)> {
+    if settings.tiles == (1, 1) {
+        return vec![(stem.to_string(), conv.process(img, settings))];
+    }
+    conv.process_tiled(img, settings)
+        .into_iter()
+        .map(|((r, c), _, img)| (format!("{stem}_r{}_c{}", r + 1, c + 1), img))
+        .collect()
+}
+
+/// Decodes, converts and encodes one file's bytes
+pub fn convert_bytes(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    data: &[u8],
+    stem: &str,
+    output: Output,
+) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let img = img_conv::load_image(data)?;
+    convert(conv, settings, img, stem)
+        .into_iter()
+        .map(|(stem, img)| Ok((format!("{stem}.{}", output.extension()), encode(conv, &img, output)?)))
+        .collect()
+}
+
+/// Writes a converted image in the requested output format
+pub fn encode(conv: &DalImageConverter, img: &DynamicImage, output: Output) -> anyhow::Result<Vec<u8>> {
+    let format = match output {
+        Output::Png => OutputFormat::Png,
+        Output::Bmp => OutputFormat::Bmp,
+        Output::Gif => OutputFormat::Gif,
+        Output::Webp => OutputFormat::WebP,
+        Output::Jpg => OutputFormat::Jpeg,
+        Output::Csv | Output::Json | Output::Bin | Output::Bin4 => {
+            let indices = conv.indices(&img.to_rgb8());
+            let transparent: Option<Vec<bool>> = img
+                .as_rgba8()
+                .map(|img| img.pixels().map(|px| px.0[3] < 255).collect());
+            return match output {
+                Output::Csv => Ok(grid::to_csv(&indices, img.width(), transparent.as_deref()).into_bytes()),
+                Output::Json => Ok(grid::to_json(&indices, img.width(), transparent.as_deref()).into_bytes()),
+                Output::Bin => codegen::to_binary(&indices, codegen::Packing::Bits8),
+                _ => codegen::to_binary(&indices, codegen::Packing::Bits4),
+            };
+        }
+    };
+    format.encode(img)
+}
@@ -0,0 +1,66 @@
+use anyhow::{anyhow, bail};
+use clap::ValueEnum;
+use dal_img_core::img_conv::palettes;
+
+use crate::pipeline::{self, ConvertArgs, DitherArg, Output};
+
+/// Conversion options of the network services, all optional
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "server", derive(serde::Deserialize))]
+pub struct RemoteOptions {
+    /// Palette preset name or comma separated hex colors. Palette files are
+    /// not read, clients must not reach the server's file system
+    pub palette: Option<String>,
+    pub device: Option<String>,
+    pub dither: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Any CLI output format, `json` returns the palette indices
+    pub format: Option<String>,
+}
+
+/// Largest width or height a client may ask for. Without a cap a single request
+/// could make the server allocate gigabytes and abort
+pub const MAX_SIDE: u32 = 4096;
+
+fn parse_enum<T: ValueEnum>(value: Option<&str>) -> anyhow::Result<Option<T>> {
+    value.map(|v| T::from_str(v, true).map_err(|e| anyhow!(e))).transpose()
+}
+
+/// Converts one uploaded image, returning the output format and its bytes.
+/// Split settings can't be requested, so there is always a single result.
+/// Every network service converts through here, so they share the size cap
+pub fn convert(options: RemoteOptions, data: &[u8]) -> anyhow::Result<(Output, Vec<u8>)> {
+    let format: Option<Output> = parse_enum(options.format.as_deref())?;
+    let dither: Option<DitherArg> = parse_enum(options.dither.as_deref())?;
+    let size = match (options.width, options.height) {
+        (Some(w), Some(h)) if w > MAX_SIDE || h > MAX_SIDE => {
+            bail!("width and height must be at most {MAX_SIDE}")
+        }
+        (Some(w), Some(h)) if w > 0 && h > 0 => Some((w, h)),
+        (None, None) => None,
+        _ => bail!("width and height must both be given and non-zero"),
+    };
+    let palette = options
+        .palette
+        .as_deref()
+        .map(|p| pipeline::preset_palette(p).map_or_else(|| palettes::parse_palette("query.hex", p), Ok))
+        .transpose()?;
+
+    let args = ConvertArgs {
+        settings: None,
+        palette: None,
+        device: options.device,
+        size,
+        dither,
+        format,
+    };
+    let (mut conv, settings) = pipeline::configure(&args)?;
+    if let Some(palette) = palette {
+        conv.set_palette(&palette);
+    }
+    let output = format.unwrap_or(Output::Png);
+    let mut results = pipeline::convert_bytes(&conv, &settings, data, "upload", output)?;
+    let (_, bytes) = results.swap_remove(0);
+    Ok((output, bytes))
+}
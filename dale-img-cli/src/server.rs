@@ -0,0 +1,53 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+
+use crate::remote::{self, RemoteOptions};
+
+// Phone photos easily exceed axum's 2 MB default
+const MAX_UPLOAD: usize = 32 * 1024 * 1024;
+
+fn bad_request(message: impl ToString) -> Response {
+    (StatusCode::BAD_REQUEST, message.to_string()).into_response()
+}
+
+// Converts the first file of a multipart upload
+async fn convert_handler(Query(options): Query<RemoteOptions>, mut multipart: Multipart) -> Response {
+    let data = match multipart.next_field().await {
+        Ok(Some(field)) => field.bytes().await,
+        Ok(None) => return bad_request("No image in the upload"),
+        Err(e) => return bad_request(e),
+    };
+    let data = match data {
+        Ok(data) => data.to_vec(),
+        Err(e) => return bad_request(e),
+    };
+
+    // Conversion is CPU bound, keep it off the async workers
+    match tokio::task::spawn_blocking(move || remote::convert(options, &data)).await {
+        Ok(Ok((output, bytes))) => ([(header::CONTENT_TYPE, output.mime())], bytes).into_response(),
+        Ok(Err(e)) => bad_request(format!("{e:#}")),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/convert", post(convert_handler))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD))
+}
+
+/// Serves the conversion endpoint until the process is stopped
+pub fn serve(addr: &str) -> anyhow::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!("Listening on http://{}", listener.local_addr()?);
+        axum::serve(listener, router()).await?;
+        Ok(())
+    })
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use clap::Args;
+
+use crate::{
+    file_stem, output_format,
+    pipeline::{self, ConvertArgs},
+};
+
+/// Options of the `watch` subcommand
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Directory new images are dropped into
+    pub dir: PathBuf,
+    /// Directory the results are written to, defaults to `converted` inside the watched one
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Seconds between two scans of the directory
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+    /// Also converts the images already in the directory on startup
+    #[arg(long)]
+    pub existing: bool,
+    /// Program and arguments run for every written file, ended by `;`. Every
+    /// `{}` argument is replaced by its path, e.g. `--exec scp {} pi:frames/ ';'`.
+    /// No shell is involved, so file names can't inject commands
+    #[arg(long, num_args = 1.., value_terminator = ";", allow_hyphen_values = true)]
+    pub exec: Vec<String>,
+    #[command(flatten)]
+    pub args: ConvertArgs,
+}
+
+// Size and modification time, a file is only picked up once they stop changing
+type Stamp = (u64, Option<SystemTime>);
+
+fn scan(dir: &Path) -> anyhow::Result<HashMap<PathBuf, Stamp>> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .filter(|e| image::ImageFormat::from_path(e.path()).is_ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((e.path(), (meta.len(), meta.modified().ok())))
+        })
+        .collect())
+}
+
+fn run_exec(command: &[String], path: &Path) -> anyhow::Result<()> {
+    let (program, args) = command.split_first().context("--exec needs a program")?;
+    let args = args.iter().map(|arg| match arg.as_str() {
+        "{}" => path.as_os_str().to_owned(),
+        arg => arg.into(),
+    });
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {program}"))?;
+    anyhow::ensure!(status.success(), "{program} exited with {status}");
+    Ok(())
+}
+
+/// Polls `dir` and converts every image that appears in it until interrupted.
+/// Polling rather than file system events keeps network shares working
+pub fn watch(watch: &WatchArgs) -> anyhow::Result<()> {
+    let (conv, settings) = pipeline::configure(&watch.args)?;
+    let format = output_format(&watch.args, None);
+    let output = watch.output.clone().unwrap_or_else(|| watch.dir.join("converted"));
+    // Results written next to the inputs would be converted again
+    anyhow::ensure!(output != watch.dir, "The output directory must differ from the watched one");
+    std::fs::create_dir_all(&output).with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let mut seen = HashMap::new();
+    if !watch.existing {
+        seen = scan(&watch.dir)?;
+    }
+    // Files that changed since the last scan, converted once their stamp holds still
+    let mut settling: HashMap<PathBuf, Stamp> = HashMap::new();
+    eprintln!("Watching {} for new images", watch.dir.display());
+
+    loop {
+        for (path, stamp) in scan(&watch.dir)? {
+            if seen.get(&path) == Some(&stamp) {
+                continue;
+            }
+            if settling.insert(path.clone(), stamp) != Some(stamp) {
+                continue;
+            }
+            settling.remove(&path);
+            seen.insert(path.clone(), stamp);
+
+            let result = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+                .and_then(|data| pipeline::convert_bytes(&conv, &settings, &data, &file_stem(&path), format));
+            let results = match result {
+                Ok(results) => results,
+                // Keep watching, the next drop may be fine
+                Err(e) => {
+                    eprintln!("{}: {e:#}", path.display());
+                    continue;
+                }
+            };
+            for (name, bytes) in results {
+                let target = output.join(name);
+                if let Err(e) = std::fs::write(&target, bytes) {
+                    eprintln!("Failed to write {}: {e}", target.display());
+                    continue;
+                }
+                eprintln!("{}", target.display());
+                if !watch.exec.is_empty() {
+                    if let Err(e) = run_exec(&watch.exec, &target) {
+                        eprintln!("{e:#}");
+                    }
+                }
+            }
+        }
+        thread::sleep(Duration::from_secs(watch.interval.max(1)));
+    }
+}
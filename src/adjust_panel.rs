@@ -0,0 +1,395 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{
+        Adjustment, AutoEnhance, ConvSettings, DalImageConverter, Denoise, DenoiseMode, Equalize,
+        EqualizeMode, Noise, NoiseKind, Saturation, Sharpen, Tone,
+    },
+    reconvert_image, ImageResult,
+};
+
+// Labeled range input. Values are applied while dragging, so the live preview
+// follows, the enclosing form records the change once it is released
+#[component]
+fn slider(
+    label: String,
+    value: f32,
+    min: f32,
+    max: f32,
+    step: f32,
+    oninput: EventHandler<f32>,
+) -> Element {
+    rsx! {
+        div {
+            class: "mb-2",
+            label { class: "form-label small mb-0", "{label}" }
+            input {
+                r#type: "range",
+                class: "form-range",
+                min: "{min}",
+                max: "{max}",
+                step: "{step}",
+                value: "{value}",
+                oninput: move |evt| {
+                    if let Ok(v) = evt.value().parse::<f32>() {
+                        oninput.call(v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn percent(v: f32) -> String {
+    format!("{:+.0}%", v * 100.0)
+}
+
+// Edits the tone being drafted for the selected image, or the one of the settings
+fn edit_tone(
+    mut settings: Signal<ConvSettings>,
+    mut draft: Signal<Option<Tone>>,
+    f: impl FnOnce(&mut Tone),
+) {
+    if draft.read().is_none() {
+        f(&mut settings.write().tone);
+        return;
+    }
+    if let Some(tone) = draft.write().as_mut() {
+        f(tone);
+    }
+}
+
+// Gives the selected image its own tone, or `None` to follow the settings
+// again, and converts it anew
+fn set_image_tone(
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    selected: Signal<Option<u64>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    mut history: Signal<History>,
+    tone: Option<Tone>,
+) {
+    let index = selected
+        .read()
+        .and_then(|id| images.read().iter().position(|img| img.id == id));
+    let Some(index) = index else {
+        return;
+    };
+    let img = images.read()[index].clone();
+    if img.tone == tone {
+        return;
+    }
+    if let Some(next) = reconvert_image(&conv.read(), &settings.read(), &img, img.transform, tone) {
+        history.write().record_gallery(&images.read());
+        images.write()[index] = next;
+    }
+}
+
+/// Filters run on the resized image before it's mapped to the palette. The tone
+/// can also be set for the selected gallery image alone
+#[component]
+pub fn adjust_panel(
+    mut settings: Signal<ConvSettings>,
+    mut history: Signal<History>,
+    images: Signal<Vec<Rc<ImageResult>>>,
+    selected: Signal<Option<u64>>,
+    conv: Signal<DalImageConverter>,
+    /// Tone of the selected image while its sliders move, applied once released
+    mut draft: Signal<Option<Tone>>,
+) -> Element {
+    use_effect(move || {
+        let id = *selected.read();
+        let images = images.read();
+        let tone = id.and_then(|id| images.iter().find(|img| img.id == id).and_then(|img| img.tone));
+        draft.set(tone);
+    });
+    let has_selection = selected.read().is_some_and(|id| images.read().iter().any(|img| img.id == id));
+    let own_tone = draft.read().is_some();
+
+    let auto = settings.read().auto_enhance;
+    let equalize = settings.read().equalize;
+    let tone = draft.read().unwrap_or(settings.read().tone);
+    let saturation = settings.read().saturation;
+    let denoise = settings.read().denoise;
+    let sharpen = settings.read().sharpen;
+    let noise = settings.read().noise;
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h5 { class: "card-title", {tr("Adjustments")} }
+                form {
+                    // Inputs change the settings first, the bubbled event records it
+                    onchange: move |_| {
+                        history.write().settings_changed(&settings.read());
+                        if let Some(tone) = *draft.read() {
+                            set_image_tone(images, selected, conv, settings, history, Some(tone));
+                        }
+                    },
+                    div {
+                        class: "d-flex align-items-center mb-2",
+                        h6 { class: "me-auto mb-0", {tr("Auto Enhance")} }
+                        button {
+                            class: if auto.white_balance && auto.exposure { "btn btn-sm btn-primary" } else { "btn btn-sm btn-outline-primary" },
+                            r#type: "button",
+                            title: tr("Gray-world white balance and exposure from the histogram"),
+                            onclick: move |_| {
+                                let on = settings.read().auto_enhance != AutoEnhance { white_balance: true, exposure: true };
+                                settings.write().auto_enhance = AutoEnhance { white_balance: on, exposure: on };
+                                history.write().settings_changed(&settings.read());
+                            },
+                            {tr("Auto")}
+                        }
+                    }
+                    div {
+                        class: "form-check form-check-inline mb-2",
+                        input {
+                            class: "form-check-input",
+                            r#type: "checkbox",
+                            checked: auto.white_balance,
+                            onchange: move |evt| settings.write().auto_enhance.white_balance = evt.checked(),
+                        }
+                        label { class: "form-check-label small", {tr("White Balance")} }
+                    }
+                    div {
+                        class: "form-check form-check-inline mb-2",
+                        input {
+                            class: "form-check-input",
+                            r#type: "checkbox",
+                            checked: auto.exposure,
+                            onchange: move |evt| settings.write().auto_enhance.exposure = evt.checked(),
+                        }
+                        label { class: "form-check-label small", {tr("Exposure")} }
+                    }
+                    h6 { {tr("Equalization")} }
+                    select {
+                        class: "form-select form-select-sm mb-2",
+                        onchange: move |evt| {
+                            if let Some(mode) = evt.value().parse::<usize>().ok().and_then(|i| EqualizeMode::ALL.get(i)) {
+                                settings.write().equalize.mode = *mode;
+                            }
+                        },
+                        for (i, mode) in EqualizeMode::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: equalize.mode == *mode, {tr(mode.label())} }
+                        }
+                    }
+                    if equalize.mode == EqualizeMode::Clahe {
+                        slider {
+                            label: tr_with("Tiles: {}", &[&equalize.tiles]),
+                            value: equalize.tiles as f32,
+                            min: 1.0,
+                            max: 8.0,
+                            step: 1.0,
+                            oninput: move |v: f32| settings.write().equalize.tiles = v as u32,
+                        }
+                        slider {
+                            label: tr_with("Clip Limit: {}", &[&format!("{:.1}", equalize.clip_limit)]),
+                            value: equalize.clip_limit,
+                            min: 1.0,
+                            max: 5.0,
+                            step: 0.1,
+                            oninput: move |v| settings.write().equalize.clip_limit = v,
+                        }
+                    }
+                    h6 { {tr("Tone")} }
+                    if has_selection {
+                        div {
+                            class: "form-check form-switch mb-2",
+                            input {
+                                class: "form-check-input",
+                                role: "switch",
+                                r#type: "checkbox",
+                                checked: own_tone,
+                                onchange: move |evt| {
+                                    let tone = evt.checked().then(|| settings.read().tone);
+                                    draft.set(tone);
+                                    set_image_tone(images, selected, conv, settings, history, tone);
+                                },
+                            }
+                            label { class: "form-check-label small", {tr("Selected Image Only")} }
+                        }
+                    }
+                    slider {
+                        label: tr_with("Exposure: {}", &[&format!("{:+.1} EV", tone.exposure)]),
+                        value: tone.exposure,
+                        min: -2.0,
+                        max: 2.0,
+                        step: 0.1,
+                        oninput: move |v| edit_tone(settings, draft, |tone| tone.exposure = v),
+                    }
+                    slider {
+                        label: tr_with("Brightness: {}", &[&percent(tone.brightness)]),
+                        value: tone.brightness,
+                        min: -0.5,
+                        max: 0.5,
+                        step: 0.01,
+                        oninput: move |v| edit_tone(settings, draft, |tone| tone.brightness = v),
+                    }
+                    slider {
+                        label: tr_with("Contrast: {}", &[&percent(tone.contrast)]),
+                        value: tone.contrast,
+                        min: -1.0,
+                        max: 1.0,
+                        step: 0.05,
+                        oninput: move |v| edit_tone(settings, draft, |tone| tone.contrast = v),
+                    }
+                    h6 { {tr("Color")} }
+                    slider {
+                        label: tr_with("Saturation: {}", &[&percent(saturation.saturation)]),
+                        value: saturation.saturation,
+                        min: -1.0,
+                        max: 1.0,
+                        step: 0.05,
+                        oninput: move |v| settings.write().saturation.saturation = v,
+                    }
+                    slider {
+                        label: tr_with("Hue: {}", &[&format!("{:+.0}°", saturation.hue)]),
+                        value: saturation.hue,
+                        min: -180.0,
+                        max: 180.0,
+                        step: 1.0,
+                        oninput: move |v| settings.write().saturation.hue = v,
+                    }
+                    h6 { {tr("Denoise")} }
+                    select {
+                        class: "form-select form-select-sm mb-2",
+                        onchange: move |evt| {
+                            if let Some(mode) = evt.value().parse::<usize>().ok().and_then(|i| DenoiseMode::ALL.get(i)) {
+                                settings.write().denoise.mode = *mode;
+                            }
+                        },
+                        for (i, mode) in DenoiseMode::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: denoise.mode == *mode, {tr(mode.label())} }
+                        }
+                    }
+                    if denoise.mode != DenoiseMode::None {
+                        slider {
+                            label: tr_with("Radius: {}", &[&denoise.radius]),
+                            value: denoise.radius as f32,
+                            min: 1.0,
+                            max: 4.0,
+                            step: 1.0,
+                            oninput: move |v: f32| settings.write().denoise.radius = v as u32,
+                        }
+                    }
+                    if denoise.mode == DenoiseMode::Bilateral {
+                        slider {
+                            label: tr_with("Strength: {}", &[&denoise.strength]),
+                            value: denoise.strength,
+                            min: 5.0,
+                            max: 100.0,
+                            step: 1.0,
+                            oninput: move |v| settings.write().denoise.strength = v,
+                        }
+                    }
+                    h6 { {tr("Sharpen")} }
+                    slider {
+                        label: tr_with("Amount: {}", &[&format!("{:.1}", sharpen.amount)]),
+                        value: sharpen.amount,
+                        min: 0.0,
+                        max: 3.0,
+                        step: 0.1,
+                        oninput: move |v| settings.write().sharpen.amount = v,
+                    }
+                    slider {
+                        label: tr_with("Radius: {}", &[&format!("{:.1}", sharpen.radius)]),
+                        value: sharpen.radius,
+                        min: 0.3,
+                        max: 5.0,
+                        step: 0.1,
+                        oninput: move |v| settings.write().sharpen.radius = v,
+                    }
+                    slider {
+                        label: tr_with("Threshold: {}", &[&sharpen.threshold]),
+                        value: sharpen.threshold as f32,
+                        min: 0.0,
+                        max: 64.0,
+                        step: 1.0,
+                        oninput: move |v: f32| settings.write().sharpen.threshold = v as u8,
+                    }
+                    h6 { {tr("Noise")} }
+                    select {
+                        class: "form-select form-select-sm mb-2",
+                        onchange: move |evt| {
+                            if let Some(kind) = evt.value().parse::<usize>().ok().and_then(|i| NoiseKind::ALL.get(i)) {
+                                settings.write().noise.kind = *kind;
+                            }
+                        },
+                        for (i, kind) in NoiseKind::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: noise.kind == *kind, {tr(kind.label())} }
+                        }
+                    }
+                    if noise.kind != NoiseKind::None {
+                        slider {
+                            label: tr_with("Amount: {}", &[&noise.amount]),
+                            value: noise.amount,
+                            min: 1.0,
+                            max: 64.0,
+                            step: 1.0,
+                            oninput: move |v| settings.write().noise.amount = v,
+                        }
+                        div {
+                            class: "input-group input-group-sm mb-2",
+                            span { class: "input-group-text", {tr("Seed")} }
+                            input {
+                                class: "form-control",
+                                r#type: "number",
+                                min: "0",
+                                value: "{noise.seed}",
+                                onchange: move |evt| {
+                                    if let Ok(v) = evt.value().parse::<u32>() {
+                                        settings.write().noise.seed = v;
+                                    }
+                                }
+                            }
+                            button {
+                                class: "btn btn-outline-secondary",
+                                r#type: "button",
+                                onclick: move |_| {
+                                    let seed = settings.read().noise.seed;
+                                    settings.write().noise.seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345) >> 1;
+                                    history.write().settings_changed(&settings.read());
+                                },
+                                {tr("New Seed")}
+                            }
+                        }
+                    }
+                    button {
+                        class: "btn btn-sm btn-outline-secondary",
+                        r#type: "button",
+                        disabled: auto.is_identity()
+                            && equalize.is_identity()
+                            && tone.is_identity()
+                            && saturation.is_identity()
+                            && denoise.is_identity()
+                            && sharpen.is_identity()
+                            && noise.is_identity(),
+                        onclick: move |_| {
+                            if own_tone {
+                                draft.set(Some(Tone::default()));
+                                set_image_tone(images, selected, conv, settings, history, Some(Tone::default()));
+                            }
+                            settings.with_mut(|settings| {
+                                settings.auto_enhance = AutoEnhance::default();
+                                settings.equalize = Equalize::default();
+                                settings.tone = Tone::default();
+                                settings.saturation = Saturation::default();
+                                settings.denoise = Denoise::default();
+                                settings.sharpen = Sharpen::default();
+                                settings.noise = Noise::default();
+                            });
+                            history.write().settings_changed(&settings.read());
+                        },
+                        {tr("Reset")}
+                    }
+                }
+            }
+        }
+    }
+}
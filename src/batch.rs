@@ -0,0 +1,132 @@
+use std::{collections::HashSet, rc::Rc};
+
+use dioxus::prelude::*;
+
+use crate::{
+    download_all,
+    export::OutputFormat,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{presets, ConvSettings, DalImageConverter},
+    merge_reconverted, reconvert_all, toast, ImageResult,
+};
+
+/// Adds or removes one gallery entry from the batch selection
+pub fn toggle(mut checked: Signal<HashSet<u64>>, id: u64) {
+    let mut checked = checked.write();
+    if !checked.remove(&id) {
+        checked.insert(id);
+    }
+}
+
+/// Adds every entry between `anchor` and `id` to the batch selection, like
+/// shift-clicking in a file manager. Without an anchor only `id` is added
+pub fn select_range(images: &[Rc<ImageResult>], mut checked: Signal<HashSet<u64>>, anchor: Option<u64>, id: u64) {
+    let position = |id: u64| images.iter().position(|img| img.id == id);
+    let Some(to) = position(id) else {
+        return;
+    };
+    let from = anchor.and_then(position).unwrap_or(to);
+    let mut checked = checked.write();
+    for img in &images[from.min(to)..=from.max(to)] {
+        checked.insert(img.id);
+    }
+}
+
+// Gallery entries in the batch selection, in gallery order
+fn checked_images(images: &[Rc<ImageResult>], checked: &HashSet<u64>) -> Vec<Rc<ImageResult>> {
+    images.iter().filter(|img| checked.contains(&img.id)).cloned().collect()
+}
+
+/// Action bar for the batch selection, shown while any card is checked
+#[component]
+pub fn batch_bar(
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    mut checked: Signal<HashSet<u64>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    format: Signal<OutputFormat>,
+    mut history: Signal<History>,
+) -> Element {
+    // Index into `presets::DEVICES`, `None` re-converts with the current settings
+    let mut device = use_signal(|| None::<usize>);
+    let count = images.read().iter().filter(|img| checked.read().contains(&img.id)).count();
+    if count == 0 {
+        return None;
+    }
+
+    rsx! {
+        div {
+            class: "d-flex flex-wrap align-items-center gap-2 border rounded p-2 mb-3",
+            span { class: "me-2", {tr_with("{} selected", &[&count])} }
+            button {
+                class: "btn btn-sm btn-outline-secondary",
+                onclick: move |_| {
+                    let ids = images.read().iter().map(|img| img.id).collect();
+                    checked.set(ids);
+                },
+                {tr("Select All")}
+            }
+            button {
+                class: "btn btn-sm btn-outline-secondary",
+                onclick: move |_| checked.write().clear(),
+                {tr("Clear Selection")}
+            }
+            button {
+                class: "btn btn-sm btn-outline-danger",
+                onclick: move |_| {
+                    history.write().record_gallery(&images.read());
+                    let checked = checked.take();
+                    images.write().retain(|img| !checked.contains(&img.id));
+                },
+                {tr("Delete")}
+            }
+            button {
+                class: "btn btn-sm btn-outline-primary",
+                onclick: move |_| {
+                    let selection = checked_images(&images.read(), &checked.read());
+                    if let Err(e) = download_all(&selection, *format.read()) {
+                        toast::error(tr_with("Failed to create ZIP: {}", &[&e]));
+                    }
+                },
+                {tr("Download")}
+            }
+            div {
+                class: "input-group input-group-sm w-auto",
+                select {
+                    class: "form-select",
+                    onchange: move |evt| device.set(evt.value().parse::<usize>().ok()),
+                    option {
+                        value: "",
+                        selected: device.read().is_none(),
+                        {tr("Current Settings")}
+                    }
+                    for (i, preset) in presets::DEVICES.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: *device.read() == Some(i),
+                            "{preset.name}"
+                        }
+                    }
+                }
+                button {
+                    class: "btn btn-outline-secondary",
+                    onclick: move |_| async move {
+                        let mut conv = conv.read().clone();
+                        if let Some(preset) = device.read().and_then(|i| presets::DEVICES.get(i)) {
+                            conv.set_device(preset);
+                        }
+                        let settings = settings.read().clone();
+                        let selection = checked_images(&images.read(), &checked.read());
+                        // Tiles of a source are converted together, only the checked ones are replaced
+                        let results = reconvert_all(&conv, &settings, &selection).await;
+                        let next = merge_reconverted(&images.read(), &selection, results);
+                        history.write().record_gallery(&images.read());
+                        images.set(next);
+                    },
+                    {tr("Re-convert")}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    convert_image, download,
+    export::OutputFormat,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{ConvSettings, DalImageConverter},
+    settings::settings_form,
+    AppState, ImageResult, Route,
+};
+
+/// One side of the comparison, converting the image with its own copy of the settings
+#[component]
+fn compare_profile(
+    label: &'static str,
+    img: Option<Rc<ImageResult>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+) -> Element {
+    let AppState {
+        conv: mut global_conv,
+        settings: mut global_settings,
+        mut history,
+        ..
+    } = use_context();
+    // Profiles have their own undo trail and export format, neither is used outside this page
+    let format = use_signal(OutputFormat::default);
+    let local_history = use_signal(|| History::new(settings.read().clone(), conv.read().clone()));
+
+    let result = img.and_then(|img| {
+        let results = convert_image(&conv.read(), &settings.read(), img.source.clone(), img.name.clone(), img.transform);
+        results.iter().find(|r| r.tile == img.tile).or(results.first()).cloned()
+    });
+    let preview = result
+        .as_ref()
+        .and_then(|r| OutputFormat::Png.encode(&r.img).ok())
+        .map(|png| download::data_url("image/png", &png));
+
+    rsx! {
+        div {
+            class: "col-md-6",
+            div {
+                class: "card mb-3",
+                div {
+                    class: "card-body",
+                    div {
+                        class: "d-flex align-items-center mb-2",
+                        h5 { class: "card-title me-auto mb-0", {tr_with("Profile {}", &[&label])} }
+                        button {
+                            class: "btn btn-sm btn-outline-primary",
+                            title: tr("Make these the settings used for new uploads"),
+                            onclick: move |_| {
+                                global_conv.set(conv.read().clone());
+                                global_settings.set(settings.read().clone());
+                                history.write().settings_changed(&global_settings.read());
+                            },
+                            {tr("Use as Default")}
+                        }
+                    }
+                    if let Some(src) = preview {
+                        img {
+                            class: "img-fluid border",
+                            style: "image-rendering: pixelated; width: 100%;",
+                            src: "{src}",
+                        }
+                    }
+                    if let Some(quality) = result.as_ref().and_then(|r| r.quality) {
+                        p {
+                            class: "small text-body-secondary mt-1 mb-0",
+                            {tr_with("ΔE mean {} · max {}", &[&format!("{:.2}", quality.mean), &format!("{:.2}", quality.max)])}
+                        }
+                    }
+                }
+            }
+            settings_form { conv, settings, format, history: local_history }
+        }
+    }
+}
+
+/// Converts one gallery image under two independent settings profiles side by side
+#[component]
+pub fn Compare() -> Element {
+    let AppState {
+        images,
+        conv,
+        settings,
+        mut selected,
+        ..
+    } = use_context();
+    // Both profiles start out as the current settings
+    let conv_a = use_signal(|| conv.read().clone());
+    let settings_a = use_signal(|| settings.read().clone());
+    let conv_b = use_signal(|| conv.read().clone());
+    let settings_b = use_signal(|| settings.read().clone());
+
+    let img = selected
+        .read()
+        .and_then(|id| images.read().iter().find(|img| img.id == id).cloned());
+
+    rsx! {
+        div {
+            div {
+                class: "d-flex align-items-center mb-3",
+                h1 { class: "me-auto", {tr("Compare Settings")} }
+                Link { class: "btn btn-outline-primary", to: Route::Home {}, {tr("Back to Gallery")} }
+            }
+            select {
+                class: "form-select mb-3",
+                onchange: move |evt| {
+                    let id = evt.value().parse::<u64>().ok();
+                    selected.set(id);
+                },
+                option {
+                    value: "",
+                    selected: img.is_none(),
+                    {tr("Choose an image")}
+                }
+                for entry in images.read().iter() {
+                    option {
+                        value: "{entry.id}",
+                        selected: img.as_ref().is_some_and(|img| img.id == entry.id),
+                        "{entry.stem()}"
+                    }
+                }
+            }
+            if img.is_none() {
+                p { class: "text-muted", {tr("Select an image in the gallery to compare settings.")} }
+            }
+            div {
+                class: "row",
+                compare_profile { label: "A", img: img.clone(), conv: conv_a, settings: settings_a }
+                compare_profile { label: "B", img, conv: conv_b, settings: settings_b }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use dioxus::prelude::*;
+
+use crate::{
+    history::History,
+    i18n::tr,
+    img_conv::{Adjustment, ConvSettings, Curve},
+};
+
+// Side of the square chart in screen pixels, one pixel per value
+const SIZE: f64 = 256.0;
+// Distance in pixels within which a press grabs an existing point
+const GRAB_RADIUS: f64 = 8.0;
+
+// Chart coordinates of a curve point, output grows upwards
+fn to_chart([x, y]: [u8; 2]) -> (f64, f64) {
+    (x as f64, 255.0 - y as f64)
+}
+
+fn from_chart(x: f64, y: f64) -> [u8; 2] {
+    [x, 255.0 - y].map(|v| v.clamp(0.0, 255.0).round() as u8)
+}
+
+// Index of the point closest to a chart position, if it's within reach
+fn grab(points: &[[u8; 2]], x: f64, y: f64) -> Option<usize> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            let (px, py) = to_chart(p);
+            (i, (px - x).hypot(py - y))
+        })
+        .filter(|&(_, d)| d <= GRAB_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Luminance curve editor. Pressing the chart adds a point or grabs the
+/// closest one, double clicking a point removes it
+#[component]
+pub fn curve_panel(mut settings: Signal<ConvSettings>, mut history: Signal<History>) -> Element {
+    let mut dragging = use_signal(|| None::<usize>);
+    let curve = settings.read().curve.clone();
+    let path = curve
+        .lut()
+        .iter()
+        .enumerate()
+        .map(|(x, &y)| format!("{x},{}", 255 - y as usize))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut drag_to = move |x: f64, y: f64| {
+        if let Some(i) = *dragging.read() {
+            if let Some(p) = settings.write().curve.points.get_mut(i) {
+                *p = from_chart(x, y);
+            }
+        }
+    };
+    let mut release = move || {
+        if dragging.take().is_some() {
+            history.write().settings_changed(&settings.read());
+        }
+    };
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h6 { class: "card-title", {tr("Curves")} }
+                svg {
+                    width: "{SIZE}",
+                    height: "{SIZE}",
+                    view_box: "0 0 {SIZE} {SIZE}",
+                    style: "display: block; max-width: 100%; user-select: none;",
+                    rect { width: "{SIZE}", height: "{SIZE}", fill: "#f8f9fa" }
+                    for q in [64, 128, 192] {
+                        line { x1: "{q}", y1: "0", x2: "{q}", y2: "{SIZE}", stroke: "#dee2e6" }
+                        line { x1: "0", y1: "{q}", x2: "{SIZE}", y2: "{q}", stroke: "#dee2e6" }
+                    }
+                    line { x1: "0", y1: "255", x2: "255", y2: "0", stroke: "#adb5bd", stroke_dasharray: "4 4" }
+                    polyline { points: "{path}", fill: "none", stroke: "#212529", stroke_width: "1.5" }
+                    for p in curve.points.iter() {
+                        circle {
+                            cx: "{to_chart(*p).0}",
+                            cy: "{to_chart(*p).1}",
+                            r: "4",
+                            fill: "#fff",
+                            stroke: "#0d6efd",
+                            stroke_width: "2",
+                        }
+                    }
+                    // Catches all pointer input, so coordinates are always relative to the chart
+                    rect {
+                        width: "{SIZE}",
+                        height: "{SIZE}",
+                        fill: "transparent",
+                        style: "cursor: crosshair;",
+                        onmousedown: move |evt| {
+                            let (x, y) = (evt.element_coordinates().x, evt.element_coordinates().y);
+                            let existing = grab(&settings.read().curve.points, x, y);
+                            let i = existing.unwrap_or_else(|| {
+                                let mut settings = settings.write();
+                                settings.curve.points.push(from_chart(x, y));
+                                settings.curve.points.len() - 1
+                            });
+                            dragging.set(Some(i));
+                            drag_to(x, y);
+                        },
+                        onmousemove: move |evt| drag_to(evt.element_coordinates().x, evt.element_coordinates().y),
+                        onmouseup: move |_| release(),
+                        onmouseleave: move |_| release(),
+                        ondoubleclick: move |evt| {
+                            let (x, y) = (evt.element_coordinates().x, evt.element_coordinates().y);
+                            if let Some(i) = grab(&settings.read().curve.points, x, y) {
+                                settings.write().curve.points.remove(i);
+                                history.write().settings_changed(&settings.read());
+                            }
+                        },
+                    }
+                }
+                div {
+                    class: "d-flex align-items-center small mt-2",
+                    span { class: "text-muted", {tr("Click to add a point, double click to remove it")} }
+                    button {
+                        class: "btn btn-sm btn-outline-secondary ms-auto",
+                        r#type: "button",
+                        disabled: curve.is_identity(),
+                        onclick: move |_| {
+                            settings.write().curve = Curve::default();
+                            history.write().settings_changed(&settings.read());
+                        },
+                        {tr("Reset")}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,662 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    export::{led_matrix, wire, OutputFormat},
+    i18n::{tr, tr_with},
+    storage, toast, ImageResult,
+};
+
+// Asks for a port on first use and keeps it open in `window.daleSerial`, so
+// later frames go out without another prompt. Reports progress per chunk
+const SERIAL_BRIDGE: &str = r#"
+const [frame, baudRate] = await dioxus.recv();
+const CHUNK = 4096;
+try {
+    if (!("serial" in navigator)) {
+        throw "WebSerial is not supported by this browser";
+    }
+    if (window.daleSerial && window.daleSerialBaud !== baudRate) {
+        await window.daleSerial.close();
+        window.daleSerial = undefined;
+    }
+    if (!window.daleSerial) {
+        const port = await navigator.serial.requestPort();
+        await port.open({ baudRate });
+        window.daleSerial = port;
+        window.daleSerialBaud = baudRate;
+    }
+    const bytes = Uint8Array.from(atob(frame), (c) => c.charCodeAt(0));
+    const writer = window.daleSerial.writable.getWriter();
+    try {
+        for (let i = 0; i < bytes.length; i += CHUNK) {
+            await writer.write(bytes.subarray(i, i + CHUNK));
+            dioxus.send({ progress: Math.min(i + CHUNK, bytes.length) / bytes.length });
+        }
+    } finally {
+        writer.releaseLock();
+    }
+    dioxus.send({ done: true });
+} catch (e) {
+    // A broken port is dropped so the next attempt asks again
+    if (window.daleSerial) {
+        window.daleSerial.close().catch(() => {});
+        window.daleSerial = undefined;
+    }
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+// Same for WebUSB, the claimed device lives in `window.daleUsb` as long as
+// the filters, interface and endpoint stay the same
+const USB_BRIDGE: &str = r#"
+const [frame, filters, iface, endpoint] = await dioxus.recv();
+const CHUNK = 4096;
+const key = JSON.stringify([filters, iface]);
+try {
+    if (!("usb" in navigator)) {
+        throw "WebUSB is not supported by this browser";
+    }
+    if (window.daleUsb && window.daleUsbKey !== key) {
+        await window.daleUsb.close();
+        window.daleUsb = undefined;
+    }
+    if (!window.daleUsb) {
+        const device = await navigator.usb.requestDevice({ filters });
+        await device.open();
+        if (device.configuration === null) {
+            await device.selectConfiguration(1);
+        }
+        await device.claimInterface(iface);
+        window.daleUsb = device;
+        window.daleUsbKey = key;
+    }
+    const bytes = Uint8Array.from(atob(frame), (c) => c.charCodeAt(0));
+    for (let i = 0; i < bytes.length; i += CHUNK) {
+        const result = await window.daleUsb.transferOut(endpoint, bytes.subarray(i, i + CHUNK));
+        if (result.status !== "ok") {
+            throw `USB transfer failed: ${result.status}`;
+        }
+        dioxus.send({ progress: Math.min(i + CHUNK, bytes.length) / bytes.length });
+    }
+    dioxus.send({ done: true });
+} catch (e) {
+    if (window.daleUsb) {
+        window.daleUsb.close().catch(() => {});
+        window.daleUsb = undefined;
+    }
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+// Web Bluetooth, connected once and kept in `window.daleBle`. Browsers
+// don't expose the negotiated MTU, so the chunk size comes from the settings
+const BLE_BRIDGE: &str = r#"
+const [frame, service, characteristic, chunk, retries] = await dioxus.recv();
+const key = JSON.stringify([service, characteristic]);
+try {
+    if (!("bluetooth" in navigator)) {
+        throw "Web Bluetooth is not supported by this browser";
+    }
+    if (window.daleBle && (window.daleBleKey !== key || !window.daleBle.service.device.gatt.connected)) {
+        window.daleBle.service.device.gatt.disconnect();
+        window.daleBle = undefined;
+    }
+    if (!window.daleBle) {
+        const device = await navigator.bluetooth.requestDevice({ filters: [{ services: [service] }] });
+        const server = await device.gatt.connect();
+        const gattService = await server.getPrimaryService(service);
+        window.daleBle = { service: gattService, characteristic: await gattService.getCharacteristic(characteristic) };
+        window.daleBleKey = key;
+    }
+    const bytes = Uint8Array.from(atob(frame), (c) => c.charCodeAt(0));
+    for (let i = 0; i < bytes.length; i += chunk) {
+        const part = bytes.subarray(i, i + chunk);
+        // GATT writes fail now and then on busy links, back off and retry
+        for (let attempt = 0; ; attempt++) {
+            try {
+                await window.daleBle.characteristic.writeValueWithResponse(part);
+                break;
+            } catch (e) {
+                if (attempt >= retries) {
+                    throw e;
+                }
+                await new Promise((resolve) => setTimeout(resolve, 50 * 2 ** attempt));
+            }
+        }
+        dioxus.send({ progress: Math.min(i + chunk, bytes.length) / bytes.length });
+    }
+    dioxus.send({ done: true });
+} catch (e) {
+    if (window.daleBle) {
+        window.daleBle.service.device.gatt.disconnect();
+        window.daleBle = undefined;
+    }
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+// A single POST, fetch can't report upload progress. Non-2xx answers still
+// reply with their status, which the caller turns into an error
+const HTTP_BRIDGE: &str = r#"
+const [url, body, headers] = await dioxus.recv();
+try {
+    const bytes = Uint8Array.from(atob(body), (c) => c.charCodeAt(0));
+    const response = await fetch(url, { method: "POST", headers, body: bytes });
+    dioxus.send({ done: true, ok: response.ok, status: `${response.status} ${response.statusText}`.trim() });
+} catch (e) {
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+/// Runs a transfer script that replies with `progress` fractions until it
+/// sends `done` or an `error`, returning that last reply
+async fn run_transfer(
+    script: &str,
+    args: serde_json::Value,
+    mut progress: impl FnMut(f32),
+) -> anyhow::Result<serde_json::Value> {
+    let mut bridge = eval(script);
+    bridge
+        .send(args)
+        .map_err(|e| anyhow!("Failed to reach the browser bridge: {e:?}"))?;
+    loop {
+        let reply = bridge
+            .recv()
+            .await
+            .map_err(|e| anyhow!("No reply from the browser bridge: {e:?}"))?;
+        if let Some(e) = reply.get("error").and_then(|e| e.as_str()) {
+            anyhow::bail!("{e}");
+        }
+        match reply.get("progress").and_then(|p| p.as_f64()) {
+            Some(p) => progress(p as f32),
+            None => return Ok(reply),
+        }
+    }
+}
+
+/// A way of getting frames onto a board
+pub trait DeviceTransport {
+    /// Writes one payload, calling `progress` with the fraction sent so far.
+    /// Returns the status the device answered with, for transports that have one
+    async fn send(&self, payload: &[u8], progress: impl FnMut(f32)) -> anyhow::Result<Option<String>>;
+}
+
+pub struct SerialTransport {
+    pub baud_rate: u32,
+}
+
+impl DeviceTransport for SerialTransport {
+    async fn send(&self, frame: &[u8], progress: impl FnMut(f32)) -> anyhow::Result<Option<String>> {
+        let args = serde_json::json!([BASE64_STANDARD.encode(frame), self.baud_rate]);
+        run_transfer(SERIAL_BRIDGE, args, progress).await.map(|_| None)
+    }
+}
+
+pub struct UsbTransport {
+    /// Limits the device chooser, any device is offered when unset
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub interface: u8,
+    /// Bulk OUT endpoint the frame is written to
+    pub endpoint: u8,
+}
+
+impl DeviceTransport for UsbTransport {
+    async fn send(&self, frame: &[u8], progress: impl FnMut(f32)) -> anyhow::Result<Option<String>> {
+        // A product id alone is not a valid WebUSB filter
+        let filters = match (self.vendor_id, self.product_id) {
+            (Some(vendor), Some(product)) => serde_json::json!([{ "vendorId": vendor, "productId": product }]),
+            (Some(vendor), None) => serde_json::json!([{ "vendorId": vendor }]),
+            (None, _) => serde_json::json!([]),
+        };
+        let args = serde_json::json!([BASE64_STANDARD.encode(frame), filters, self.interface, self.endpoint]);
+        run_transfer(USB_BRIDGE, args, progress).await.map(|_| None)
+    }
+}
+
+/// Attempts per chunk before a BLE transfer gives up
+const BLE_RETRIES: u32 = 3;
+
+pub struct BleTransport {
+    pub service: String,
+    /// Characteristic the frame is written to
+    pub characteristic: String,
+    /// Bytes per GATT write, at most the link's MTU minus 3
+    pub chunk_size: usize,
+}
+
+impl DeviceTransport for BleTransport {
+    async fn send(&self, frame: &[u8], progress: impl FnMut(f32)) -> anyhow::Result<Option<String>> {
+        let args = serde_json::json!([
+            BASE64_STANDARD.encode(frame),
+            self.service.to_lowercase(),
+            self.characteristic.to_lowercase(),
+            self.chunk_size.max(1),
+            BLE_RETRIES,
+        ]);
+        run_transfer(BLE_BRIDGE, args, progress).await.map(|_| None)
+    }
+}
+
+pub struct HttpTransport {
+    pub url: String,
+    pub content_type: &'static str,
+    /// Extra header for authentication, e.g. `Authorization: Bearer ...`
+    pub auth_header: Option<(String, String)>,
+}
+
+impl DeviceTransport for HttpTransport {
+    async fn send(&self, body: &[u8], mut progress: impl FnMut(f32)) -> anyhow::Result<Option<String>> {
+        anyhow::ensure!(!self.url.is_empty(), "No device URL set");
+        let mut headers = serde_json::json!({ "Content-Type": self.content_type });
+        if let Some((name, value)) = &self.auth_header {
+            headers[name] = value.clone().into();
+        }
+        let args = serde_json::json!([self.url, BASE64_STANDARD.encode(body), headers]);
+        let reply = run_transfer(HTTP_BRIDGE, args, &mut progress).await?;
+        let status = reply.get("status").and_then(|s| s.as_str()).unwrap_or_default().to_string();
+        if !reply.get("ok").and_then(|ok| ok.as_bool()).unwrap_or(false) {
+            anyhow::bail!("The device answered {status}");
+        }
+        progress(1.0);
+        Ok(Some(status))
+    }
+}
+
+/// What an HTTP push carries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpPayload {
+    /// The `export::wire` frame, as the other transports send it
+    #[default]
+    Frame,
+    /// Bare palette indices, one byte per pixel
+    Indices,
+    Png,
+    /// WLED's JSON API, POSTed to `/json/state`
+    Wled,
+    /// Divoom Pixoo HTTP commands, POSTed to `/post`
+    Divoom,
+}
+
+impl HttpPayload {
+    pub const ALL: [HttpPayload; 5] = [
+        HttpPayload::Frame,
+        HttpPayload::Indices,
+        HttpPayload::Png,
+        HttpPayload::Wled,
+        HttpPayload::Divoom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HttpPayload::Frame => "Frame",
+            HttpPayload::Indices => "Palette Indices",
+            HttpPayload::Png => "PNG",
+            HttpPayload::Wled => "WLED JSON API",
+            HttpPayload::Divoom => "Divoom Pixoo",
+        }
+    }
+
+    /// Example URL for the input's placeholder
+    pub fn example_url(&self) -> &'static str {
+        match self {
+            HttpPayload::Wled => "http://wled.local/json/state",
+            HttpPayload::Divoom => "http://192.168.1.50/post",
+            _ => "http://192.168.4.1/frame",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransportKind {
+    #[default]
+    Serial,
+    Usb,
+    Ble,
+    Http,
+}
+
+impl TransportKind {
+    pub const ALL: [TransportKind; 4] = [
+        TransportKind::Serial,
+        TransportKind::Usb,
+        TransportKind::Ble,
+        TransportKind::Http,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransportKind::Serial => "Serial (WebSerial)",
+            TransportKind::Usb => "USB (WebUSB)",
+            TransportKind::Ble => "Bluetooth LE",
+            TransportKind::Http => "HTTP POST",
+        }
+    }
+}
+
+/// How frames are sent, remembered across visits
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    pub transport: TransportKind,
+    pub baud_rate: u32,
+    pub usb_vendor_id: Option<u16>,
+    pub usb_product_id: Option<u16>,
+    pub usb_interface: u8,
+    pub usb_endpoint: u8,
+    pub ble_service: String,
+    pub ble_characteristic: String,
+    pub ble_chunk_size: usize,
+    pub http_url: String,
+    pub http_payload: HttpPayload,
+    pub http_auth_name: String,
+    /// Sent only when not empty. Kept for this visit only, localStorage
+    /// would hold the secret in plain text
+    #[serde(skip)]
+    pub http_auth_value: String,
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        Self {
+            transport: TransportKind::default(),
+            baud_rate: 115_200,
+            usb_vendor_id: None,
+            usb_product_id: None,
+            usb_interface: 0,
+            usb_endpoint: 1,
+            // Nordic UART Service, which most ESP32 and nRF firmwares offer
+            ble_service: "6e400001-b5a3-f393-e0a9-e50e24dcca9e".into(),
+            ble_characteristic: "6e400002-b5a3-f393-e0a9-e50e24dcca9e".into(),
+            // Fits the smallest MTU of 23 bytes, larger links can go up to 512
+            ble_chunk_size: 20,
+            http_url: String::new(),
+            http_payload: HttpPayload::default(),
+            http_auth_name: "Authorization".into(),
+            http_auth_value: String::new(),
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// Sends the image over the chosen transport, see `DeviceTransport::send`
+    pub async fn send(&self, img: &ImageResult, progress: impl FnMut(f32)) -> anyhow::Result<Option<String>> {
+        let frame = wire::encode_frame(&img.indices, (img.img.width(), img.img.height()))?;
+        match self.transport {
+            TransportKind::Serial => SerialTransport { baud_rate: self.baud_rate }.send(&frame, progress).await,
+            TransportKind::Usb => {
+                let usb = UsbTransport {
+                    vendor_id: self.usb_vendor_id,
+                    product_id: self.usb_product_id,
+                    interface: self.usb_interface,
+                    endpoint: self.usb_endpoint,
+                };
+                usb.send(&frame, progress).await
+            }
+            TransportKind::Ble => {
+                let ble = BleTransport {
+                    service: self.ble_service.clone(),
+                    characteristic: self.ble_characteristic.clone(),
+                    chunk_size: self.ble_chunk_size,
+                };
+                ble.send(&frame, progress).await
+            }
+            TransportKind::Http => {
+                let (bodies, content_type) = match self.http_payload {
+                    HttpPayload::Frame => (vec![frame], "application/octet-stream"),
+                    HttpPayload::Indices => {
+                        let indices = img.indices.iter().map(|&i| i as u8).collect();
+                        (vec![indices], "application/octet-stream")
+                    }
+                    HttpPayload::Png => (vec![img.encode(OutputFormat::Png)?], "image/png"),
+                    HttpPayload::Wled => {
+                        let state = led_matrix::wled_state(&img.img.to_rgb8());
+                        (vec![state.into_bytes()], "application/json")
+                    }
+                    HttpPayload::Divoom => {
+                        let commands = led_matrix::divoom_commands(&img.img.to_rgb8())?;
+                        (commands.into_iter().map(String::into_bytes).collect(), "application/json")
+                    }
+                };
+                let http = HttpTransport {
+                    url: self.http_url.clone(),
+                    content_type,
+                    auth_header: (!self.http_auth_name.is_empty() && !self.http_auth_value.is_empty())
+                        .then(|| (self.http_auth_name.clone(), self.http_auth_value.clone())),
+                };
+                // Multi-request payloads report progress per request
+                let count = bodies.len() as f32;
+                let mut progress = progress;
+                let mut status = None;
+                for (i, body) in bodies.iter().enumerate() {
+                    status = http.send(body, |p| progress((i as f32 + p) / count)).await?;
+                }
+                Ok(status)
+            }
+        }
+    }
+}
+
+fn to_hex_id(id: Option<u16>) -> String {
+    id.map_or(String::new(), |id| format!("{id:04x}"))
+}
+
+// USB ids are usually written in hex, an optional 0x is accepted
+fn parse_hex_id(s: &str) -> Option<u16> {
+    let s = s.trim();
+    u16::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+/// Transport choice, its options and the transfer progress for one image
+#[component]
+pub fn send_panel(images: Signal<Vec<Rc<ImageResult>>>, index: usize) -> Element {
+    let mut device = storage::use_persistent("device", DeviceSettings::default);
+    let mut progress = use_signal(|| None::<f32>);
+    // Answer of the last transfer, as reported by the device or the error
+    let mut status = use_signal(|| None::<Result<String, String>>);
+    let transport = device.read().transport;
+
+    rsx! {
+        div {
+            class: "border rounded p-2 mx-2 mb-2",
+            select {
+                class: "form-select form-select-sm mb-2",
+                onchange: move |evt| {
+                    if let Some(kind) = evt.value().parse::<usize>().ok().and_then(|i| TransportKind::ALL.get(i)) {
+                        device.write().transport = *kind;
+                    }
+                },
+                for (i, kind) in TransportKind::ALL.iter().enumerate() {
+                    option { value: "{i}", selected: *kind == transport, {tr(kind.label())} }
+                }
+            }
+            match transport {
+                TransportKind::Serial => rsx! {
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", {tr("Baud Rate")} }
+                        input {
+                            r#type: "number",
+                            class: "form-control",
+                            min: "300",
+                            value: "{device.read().baud_rate}",
+                            onchange: move |evt| {
+                                if let Ok(baud) = evt.value().parse::<u32>() {
+                                    device.write().baud_rate = baud.max(300);
+                                }
+                            }
+                        }
+                    }
+                },
+                TransportKind::Usb => rsx! {
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", "VID" }
+                        input {
+                            class: "form-control",
+                            placeholder: tr("any"),
+                            value: "{to_hex_id(device.read().usb_vendor_id)}",
+                            onchange: move |evt| device.write().usb_vendor_id = parse_hex_id(&evt.value()),
+                        }
+                        span { class: "input-group-text", "PID" }
+                        input {
+                            class: "form-control",
+                            placeholder: tr("any"),
+                            value: "{to_hex_id(device.read().usb_product_id)}",
+                            onchange: move |evt| device.write().usb_product_id = parse_hex_id(&evt.value()),
+                        }
+                    }
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", {tr("Interface")} }
+                        input {
+                            r#type: "number",
+                            class: "form-control",
+                            min: "0",
+                            value: "{device.read().usb_interface}",
+                            onchange: move |evt| {
+                                if let Ok(n) = evt.value().parse::<u8>() {
+                                    device.write().usb_interface = n;
+                                }
+                            }
+                        }
+                        span { class: "input-group-text", {tr("Endpoint")} }
+                        input {
+                            r#type: "number",
+                            class: "form-control",
+                            min: "1",
+                            max: "15",
+                            value: "{device.read().usb_endpoint}",
+                            onchange: move |evt| {
+                                if let Ok(n) = evt.value().parse::<u8>() {
+                                    device.write().usb_endpoint = n.clamp(1, 15);
+                                }
+                            }
+                        }
+                    }
+                },
+                TransportKind::Ble => rsx! {
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", {tr("Service")} }
+                        input {
+                            class: "form-control font-monospace",
+                            value: "{device.read().ble_service}",
+                            onchange: move |evt| device.write().ble_service = evt.value().trim().to_string(),
+                        }
+                    }
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", {tr("Characteristic")} }
+                        input {
+                            class: "form-control font-monospace",
+                            value: "{device.read().ble_characteristic}",
+                            onchange: move |evt| device.write().ble_characteristic = evt.value().trim().to_string(),
+                        }
+                    }
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        span { class: "input-group-text", {tr("Chunk Size")} }
+                        input {
+                            r#type: "number",
+                            class: "form-control",
+                            min: "20",
+                            max: "512",
+                            value: "{device.read().ble_chunk_size}",
+                            onchange: move |evt| {
+                                if let Ok(n) = evt.value().parse::<usize>() {
+                                    device.write().ble_chunk_size = n.clamp(20, 512);
+                                }
+                            }
+                        }
+                    }
+                },
+                TransportKind::Http => rsx! {
+                    input {
+                        class: "form-control form-control-sm mb-2",
+                        placeholder: device.read().http_payload.example_url(),
+                        title: tr("Device URL"),
+                        value: "{device.read().http_url}",
+                        onchange: move |evt| device.write().http_url = evt.value().trim().to_string(),
+                    }
+                    select {
+                        class: "form-select form-select-sm mb-2",
+                        onchange: move |evt| {
+                            if let Some(payload) = evt.value().parse::<usize>().ok().and_then(|i| HttpPayload::ALL.get(i)) {
+                                device.write().http_payload = *payload;
+                            }
+                        },
+                        for (i, payload) in HttpPayload::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: *payload == device.read().http_payload, {tr(payload.label())} }
+                        }
+                    }
+                    div {
+                        class: "input-group input-group-sm mb-2",
+                        input {
+                            class: "form-control",
+                            style: "max-width: 10rem",
+                            title: tr("Authentication Header"),
+                            value: "{device.read().http_auth_name}",
+                            onchange: move |evt| device.write().http_auth_name = evt.value().trim().to_string(),
+                        }
+                        input {
+                            class: "form-control",
+                            r#type: "password",
+                            placeholder: "Bearer …",
+                            value: "{device.read().http_auth_value}",
+                            onchange: move |evt| device.write().http_auth_value = evt.value(),
+                        }
+                    }
+                },
+            }
+            if let Some(p) = *progress.read() {
+                div {
+                    class: "progress mb-2",
+                    div {
+                        class: "progress-bar",
+                        role: "progressbar",
+                        style: "width: {p * 100.0}%",
+                        "{p * 100.0:.0}%"
+                    }
+                }
+            }
+            button {
+                class: "btn btn-success btn-sm",
+                r#type: "button",
+                disabled: progress.read().is_some(),
+                onclick: move |_| async move {
+                    let img = images.read()[index].clone();
+                    let device = device.read().clone();
+                    progress.set(Some(0.0));
+                    status.set(None);
+                    match device.send(&img, |p| progress.set(Some(p))).await {
+                        Ok(answer) => {
+                            toast::notify(toast::ToastLevel::Info, tr("Sent to board"));
+                            status.set(answer.map(Ok));
+                        }
+                        Err(e) => {
+                            toast::error(tr_with("Failed to send to board: {}", &[&e]));
+                            status.set(Some(Err(e.to_string())));
+                        }
+                    }
+                    progress.set(None);
+                },
+                {tr("Send")}
+            }
+            match &*status.read() {
+                Some(Ok(answer)) => rsx! {
+                    span { class: "small text-success ms-2", {tr_with("Device answered {}", &[answer])} }
+                },
+                Some(Err(e)) => rsx! {
+                    span { class: "small text-danger ms-2", "{e}" }
+                },
+                None => None,
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+#[cfg(not(feature = "desktop"))]
+use wasm_bindgen::JsCast;
+#[cfg(not(feature = "desktop"))]
+use web_sys::HtmlAnchorElement;
+
+use crate::{i18n::tr_with, toast};
+
+/// Encodes `data` as a base64 data URL, usable as `href` of a download link
+pub fn data_url(mime: &str, data: &[u8]) -> String {
+    let enc = BASE64_STANDARD.encode(data);
+    format!("data:{mime};base64,{enc}")
+}
+
+/// Triggers a browser download of `data` through a temporary object URL, or
+/// asks where to save it on desktop
+pub fn save(file_name: &str, mime: &str, data: &[u8]) {
+    if let Err(e) = try_save(file_name, mime, data) {
+        toast::error(tr_with("Failed to save {}: {}", &[&file_name, &e]));
+    }
+}
+
+/// Wraps `data` in a Blob and returns an object URL for it, which must be
+/// revoked by the caller once it is no longer needed
+#[cfg(not(feature = "desktop"))]
+pub fn object_url(mime: &str, data: &[u8]) -> Result<String, String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let opts = web_sys::BlobPropertyBag::new();
+    opts.set_type(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &opts)
+        .map_err(|e| format!("{e:?}"))?;
+    web_sys::Url::create_object_url_with_blob(&blob).map_err(|e| format!("{e:?}"))
+}
+
+// The desktop webview has no Blob store shared with Rust, data URLs stand in
+#[cfg(feature = "desktop")]
+pub fn object_url(mime: &str, data: &[u8]) -> Result<String, String> {
+    Ok(data_url(mime, data))
+}
+
+pub fn revoke_object_url(url: &str) {
+    #[cfg(not(feature = "desktop"))]
+    let _ = web_sys::Url::revoke_object_url(url);
+    #[cfg(feature = "desktop")]
+    let _ = url;
+}
+
+/// An object URL owning its Blob, revoked when dropped
+pub struct ObjectUrl(String);
+
+impl ObjectUrl {
+    pub fn new(mime: &str, data: &[u8]) -> Result<Self, String> {
+        object_url(mime, data).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for ObjectUrl {
+    fn drop(&mut self) {
+        revoke_object_url(&self.0);
+    }
+}
+
+#[cfg(not(feature = "desktop"))]
+fn try_save(file_name: &str, mime: &str, data: &[u8]) -> Result<(), String> {
+    let window = web_sys::window().ok_or("No window")?;
+    let document = window.document().ok_or("No document")?;
+
+    let url = object_url(mime, data)?;
+
+    let a: HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(|e| format!("{e:?}"))?
+        .dyn_into()
+        .map_err(|e| format!("{e:?}"))?;
+    a.set_href(&url);
+    a.set_download(file_name);
+    a.click();
+    revoke_object_url(&url);
+    Ok(())
+}
+
+#[cfg(feature = "desktop")]
+fn try_save(file_name: &str, _mime: &str, data: &[u8]) -> Result<(), String> {
+    let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() else {
+        return Ok(());
+    };
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Asks for a folder and writes every file into it, returning how many were written
+#[cfg(feature = "desktop")]
+pub fn save_to_folder(files: &[(String, Vec<u8>)]) -> anyhow::Result<usize> {
+    let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+        return Ok(0);
+    };
+    for (name, data) in files {
+        std::fs::write(dir.join(name), data)?;
+    }
+    Ok(files.len())
+}
@@ -0,0 +1,88 @@
+use dioxus::prelude::*;
+
+use crate::{
+    export::eink::{BitOrder, EinkFormat, EinkOptions},
+    i18n::tr,
+    storage,
+};
+
+/// Storage key of the e-paper options, read by the export menu on every export
+pub const EINK_KEY: &str = "eink";
+
+pub fn eink_options() -> EinkOptions {
+    storage::load(EINK_KEY).unwrap_or_default()
+}
+
+/// Packing options for the e-paper framebuffer export
+#[component]
+pub fn eink_settings() -> Element {
+    let mut options = storage::use_persistent(EINK_KEY, EinkOptions::default);
+    let current = options.read().clone();
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h5 { class: "card-title", {tr("E-Paper Framebuffer")} }
+                div {
+                    class: "input-group input-group-sm mb-2",
+                    span { class: "input-group-text", {tr("Format")} }
+                    select {
+                        class: "form-select",
+                        onchange: move |evt| {
+                            if let Some(format) = evt.value().parse::<usize>().ok().and_then(|i| EinkFormat::ALL.get(i)) {
+                                options.write().format = *format;
+                            }
+                        },
+                        for (i, format) in EinkFormat::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: *format == current.format, {tr(format.label())} }
+                        }
+                    }
+                }
+                div {
+                    class: "input-group input-group-sm mb-2",
+                    span { class: "input-group-text", {tr("Bit Order")} }
+                    select {
+                        class: "form-select",
+                        onchange: move |evt| {
+                            if let Some(order) = evt.value().parse::<usize>().ok().and_then(|i| BitOrder::ALL.get(i)) {
+                                options.write().bit_order = *order;
+                            }
+                        },
+                        for (i, order) in BitOrder::ALL.iter().enumerate() {
+                            option { value: "{i}", selected: *order == current.bit_order, {tr(order.label())} }
+                        }
+                    }
+                }
+                div {
+                    class: "input-group input-group-sm mb-2",
+                    span { class: "input-group-text", {tr("Pad Rows to Bytes")} }
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "0",
+                        title: tr("0 packs rows back to back"),
+                        value: "{current.row_align}",
+                        onchange: move |evt| {
+                            if let Ok(n) = evt.value().parse::<u32>() {
+                                options.write().row_align = n;
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "form-check",
+                    input {
+                        class: "form-check-input",
+                        r#type: "checkbox",
+                        checked: current.invert,
+                        disabled: current.format == EinkFormat::Acep7,
+                        onchange: move |evt| options.write().invert = evt.checked(),
+                    }
+                    label { class: "form-check-label", {tr("Invert (0 is White)")} }
+                }
+            }
+        }
+    }
+}
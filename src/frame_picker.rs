@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use image::DynamicImage;
+
+use crate::{
+    convert_image, download,
+    export::OutputFormat,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{ConvSettings, DalImageConverter, Transform},
+    ImageResult,
+};
+
+/// An uploaded animation waiting for the user to pick its frames
+pub struct Animation {
+    pub name: String,
+    /// Decoded frames with their delay in milliseconds
+    pub frames: Vec<(Rc<DynamicImage>, u32)>,
+    /// Hash of the uploaded file, so uploading it again is detected
+    pub source_hash: Option<u64>,
+}
+
+// Keeps the extension so stems of different frames stay unique
+fn frame_name(name: &str, frame: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}_f{}.{ext}", frame + 1),
+        _ => format!("{name}_f{}", frame + 1),
+    }
+}
+
+fn convert_frame(
+    animation: &Animation,
+    frame: usize,
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+) -> Vec<Rc<ImageResult>> {
+    let (source, delay) = &animation.frames[frame];
+    convert_image(conv, settings, source.clone(), frame_name(&animation.name, frame), Transform::default())
+        .into_iter()
+        .map(|img| {
+            let mut img = (*img).clone();
+            if *delay > 0 {
+                img.delay_ms = *delay;
+            }
+            img.source_hash = animation.source_hash;
+            Rc::new(img)
+        })
+        .collect()
+}
+
+#[component]
+pub fn frame_picker(
+    mut pending: Signal<Option<Animation>>,
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    mut history: Signal<History>,
+) -> Element {
+    let mut current = use_signal(|| 0usize);
+
+    let pending_ref = pending.read();
+    let Some(animation) = pending_ref.as_ref() else {
+        return None;
+    };
+    let count = animation.frames.len();
+    let frame = (*current.read()).min(count - 1);
+    let (preview, delay) = &animation.frames[frame];
+    let preview = download::data_url("image/png", &OutputFormat::Png.encode(preview).unwrap_or_default());
+    let name = animation.name.clone();
+    let delay = *delay;
+    drop(pending_ref);
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h5 { class: "card-title", "{name}" }
+                img {
+                    class: "img-fluid mb-2",
+                    style: "image-rendering: pixelated; max-height: 240px;",
+                    src: "{preview}"
+                }
+                label {
+                    class: "form-label d-block",
+                    {tr_with("Frame {} of {} ({} ms)", &[&(frame + 1), &count, &delay])}
+                }
+                input {
+                    r#type: "range",
+                    class: "form-range mb-2",
+                    min: "0",
+                    max: "{count - 1}",
+                    value: "{frame}",
+                    oninput: move |evt| {
+                        if let Ok(i) = evt.value().parse::<usize>() {
+                            current.set(i);
+                        }
+                    }
+                }
+                button {
+                    class: "btn btn-primary",
+                    r#type: "button",
+                    onclick: move |_| {
+                        if let Some(animation) = pending.take() {
+                            history.write().record_gallery(&images.read());
+                            let results = convert_frame(&animation, frame, &conv.read(), &settings.read());
+                            images.write().extend(results);
+                        }
+                        current.set(0);
+                    },
+                    {tr("Convert Frame")}
+                }
+                button {
+                    class: "btn btn-outline-primary ms-2",
+                    r#type: "button",
+                    onclick: move |_| {
+                        if let Some(animation) = pending.take() {
+                            history.write().record_gallery(&images.read());
+                            for frame in 0..animation.frames.len() {
+                                let results = convert_frame(&animation, frame, &conv.read(), &settings.read());
+                                images.write().extend(results);
+                            }
+                        }
+                        current.set(0);
+                    },
+                    {tr("Convert All Frames")}
+                }
+                button {
+                    class: "btn btn-outline-secondary ms-2",
+                    r#type: "button",
+                    onclick: move |_| {
+                        pending.set(None);
+                        current.set(0);
+                    },
+                    {tr("Cancel")}
+                }
+            }
+        }
+    }
+}
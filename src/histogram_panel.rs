@@ -0,0 +1,188 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{adjust, Adjustment, ConvSettings, DalImageConverter, Levels},
+    ImageResult,
+};
+
+// Chart size in screen pixels, one column per channel value
+const CHART_WIDTH: f64 = 256.0;
+const CHART_HEIGHT: f64 = 100.0;
+// Strip below the chart holding the black and white point handles
+const HANDLE_HEIGHT: f64 = 12.0;
+
+const CHANNEL_COLORS: [&str; 4] = ["#dc3545", "#198754", "#0d6efd", "#6c757d"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Handle {
+    Black,
+    White,
+}
+
+// SVG path tracing one channel, scaled to its own peak
+fn channel_path(counts: &[u32; 256]) -> String {
+    let peak = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let mut path = format!("M0 {CHART_HEIGHT}");
+    for (v, &n) in counts.iter().enumerate() {
+        path.push_str(&format!(" L{} {:.1}", v, CHART_HEIGHT - n as f64 * CHART_HEIGHT / peak));
+    }
+    path.push_str(&format!(" L255 {CHART_HEIGHT} Z"));
+    path
+}
+
+/// RGB and luminance histograms of the selected image after resizing, with
+/// black and white point handles that set the levels adjustment
+#[component]
+pub fn histogram_panel(
+    images: Signal<Vec<Rc<ImageResult>>>,
+    selected: Signal<Option<u64>>,
+    conv: Signal<DalImageConverter>,
+    mut settings: Signal<ConvSettings>,
+    mut history: Signal<History>,
+) -> Element {
+    let mut dragging = use_signal(|| None::<Handle>);
+    // Adjustments don't change the resized source, so dragging a handle doesn't resize again
+    let resize_settings = use_memo(move || settings.read().without_adjustments());
+    let histograms = use_memo(move || {
+        let id = (*selected.read())?;
+        let img = images.read().iter().find(|img| img.id == id).cloned()?;
+        let settings = resize_settings.read();
+        let resized = conv.read().resize_and_rotate(img.transform.apply((*img.source).clone()), &settings);
+        Some(adjust::channel_histograms(&resized))
+    });
+
+    let levels = settings.read().levels;
+    let mut drag_to = move |x: f64| {
+        let v = x.clamp(0.0, 255.0).round() as u8;
+        let mut settings = settings.write();
+        let levels = &mut settings.levels;
+        match *dragging.read() {
+            Some(Handle::Black) => levels.black = v.min(levels.white - 1),
+            Some(Handle::White) => levels.white = v.max(levels.black + 1),
+            None => {}
+        }
+    };
+    let mut release = move || {
+        if dragging.take().is_some() {
+            history.write().settings_changed(&settings.read());
+        }
+    };
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h6 { class: "card-title", {tr("Levels")} }
+                match histograms.read().as_ref() {
+                    Some(hist) => rsx! {
+                        svg {
+                            width: "{CHART_WIDTH}",
+                            height: "{CHART_HEIGHT + HANDLE_HEIGHT}",
+                            view_box: "0 0 {CHART_WIDTH} {CHART_HEIGHT + HANDLE_HEIGHT}",
+                            style: "display: block; max-width: 100%; user-select: none;",
+                            rect { width: "{CHART_WIDTH}", height: "{CHART_HEIGHT}", fill: "#f8f9fa" }
+                            for (counts, color) in hist.iter().zip(CHANNEL_COLORS) {
+                                path {
+                                    d: "{channel_path(counts)}",
+                                    fill: "{color}",
+                                    fill_opacity: "0.25",
+                                    stroke: "{color}",
+                                    stroke_width: "0.5",
+                                }
+                            }
+                            // Shade the values the levels clip
+                            rect { width: "{levels.black}", height: "{CHART_HEIGHT}", fill: "#000", fill_opacity: "0.3" }
+                            rect {
+                                x: "{levels.white}",
+                                width: "{255 - levels.white}",
+                                height: "{CHART_HEIGHT}",
+                                fill: "#000",
+                                fill_opacity: "0.3",
+                            }
+                            polygon {
+                                points: "{levels.black},{CHART_HEIGHT} {levels.black as f64 - 5.0},{CHART_HEIGHT + HANDLE_HEIGHT} {levels.black as f64 + 5.0},{CHART_HEIGHT + HANDLE_HEIGHT}",
+                                fill: "#000",
+                                stroke: "#6c757d",
+                            }
+                            polygon {
+                                points: "{levels.white},{CHART_HEIGHT} {levels.white as f64 - 5.0},{CHART_HEIGHT + HANDLE_HEIGHT} {levels.white as f64 + 5.0},{CHART_HEIGHT + HANDLE_HEIGHT}",
+                                fill: "#fff",
+                                stroke: "#6c757d",
+                            }
+                            // Catches all pointer input, so coordinates are always relative to the chart.
+                            // Pressing grabs whichever handle is closer
+                            rect {
+                                width: "{CHART_WIDTH}",
+                                height: "{CHART_HEIGHT + HANDLE_HEIGHT}",
+                                fill: "transparent",
+                                style: "cursor: ew-resize;",
+                                onmousedown: move |evt| {
+                                    let x = evt.element_coordinates().x;
+                                    let levels = settings.read().levels;
+                                    let handle = if (x - levels.black as f64).abs() <= (x - levels.white as f64).abs() {
+                                        Handle::Black
+                                    } else {
+                                        Handle::White
+                                    };
+                                    dragging.set(Some(handle));
+                                    drag_to(x);
+                                },
+                                onmousemove: move |evt| {
+                                    if dragging.read().is_some() {
+                                        drag_to(evt.element_coordinates().x);
+                                    }
+                                },
+                                onmouseup: move |_| release(),
+                                onmouseleave: move |_| release(),
+                            }
+                        }
+                        label {
+                            class: "form-label small mt-2 mb-0",
+                            {tr_with("Midtones: {}", &[&format!("{:.2}", levels.gamma)])}
+                        }
+                        input {
+                            r#type: "range",
+                            class: "form-range",
+                            min: "0.3",
+                            max: "3",
+                            step: "0.05",
+                            value: "{levels.gamma}",
+                            oninput: move |evt| {
+                                if let Ok(v) = evt.value().parse::<f32>() {
+                                    settings.write().levels.gamma = v;
+                                }
+                            },
+                            onchange: move |_| history.write().settings_changed(&settings.read()),
+                        }
+                        div {
+                            class: "d-flex align-items-center gap-2 small",
+                            span { {tr_with("Black: {}", &[&levels.black])} }
+                            span { {tr_with("White: {}", &[&levels.white])} }
+                            button {
+                                class: "btn btn-sm btn-outline-secondary ms-auto",
+                                r#type: "button",
+                                disabled: levels.is_identity(),
+                                onclick: move |_| {
+                                    settings.write().levels = Levels::default();
+                                    history.write().settings_changed(&settings.read());
+                                },
+                                {tr("Reset")}
+                            }
+                        }
+                    },
+                    None => rsx! {
+                        p {
+                            class: "text-muted small mb-0",
+                            {tr("Select an image in the gallery to see its histogram.")}
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
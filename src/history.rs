@@ -0,0 +1,154 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    i18n::tr,
+    img_conv::{ConvSettings, DalImageConverter},
+    ImageResult,
+};
+
+// Oldest changes are dropped beyond this, each keeps a gallery, settings or converter copy
+const MAX_CHANGES: usize = 100;
+
+/// A reversible change, holding the state from before it was made.
+/// Gallery entries are `Rc`s, so keeping a gallery copy is cheap.
+pub enum Change {
+    Gallery(Vec<Rc<ImageResult>>),
+    Settings(ConvSettings),
+    /// Palette, enabled colors, penalties, size and metric
+    Converter(DalImageConverter),
+}
+
+/// Undo and redo stacks for the gallery, the conversion settings and the converter
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    // Settings and converter as of the last recorded change, to detect edits after the fact
+    settings: ConvSettings,
+    converter: DalImageConverter,
+}
+
+impl History {
+    pub fn new(settings: ConvSettings, converter: DalImageConverter) -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            settings,
+            converter,
+        }
+    }
+
+    pub fn record(&mut self, change: Change) {
+        if self.undo.len() == MAX_CHANGES {
+            self.undo.remove(0);
+        }
+        self.undo.push(change);
+        self.redo.clear();
+    }
+
+    /// Records the gallery as it is before a change to it
+    pub fn record_gallery(&mut self, images: &[Rc<ImageResult>]) {
+        self.record(Change::Gallery(images.to_vec()));
+    }
+
+    /// Records a settings edit, if `current` differs from the last known settings
+    pub fn settings_changed(&mut self, current: &ConvSettings) {
+        if *current != self.settings {
+            let prev = std::mem::replace(&mut self.settings, current.clone());
+            self.record(Change::Settings(prev));
+        }
+    }
+
+    /// Records a converter edit, if `current` differs from the last known converter
+    pub fn converter_changed(&mut self, current: &DalImageConverter) {
+        if *current != self.converter {
+            let prev = std::mem::replace(&mut self.converter, current.clone());
+            self.record(Change::Converter(prev));
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+// Restores the state held by `change` and returns the state it replaced
+fn apply(
+    change: Change,
+    history: &mut History,
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    mut conv: Signal<DalImageConverter>,
+    mut settings: Signal<ConvSettings>,
+) -> Change {
+    match change {
+        Change::Gallery(prev) => Change::Gallery(std::mem::replace(&mut *images.write(), prev)),
+        Change::Settings(prev) => {
+            history.settings = prev.clone();
+            Change::Settings(std::mem::replace(&mut *settings.write(), prev))
+        }
+        Change::Converter(prev) => {
+            history.converter = prev.clone();
+            Change::Converter(std::mem::replace(&mut *conv.write(), prev))
+        }
+    }
+}
+
+pub fn undo(
+    mut history: Signal<History>,
+    images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+) {
+    let mut history = history.write();
+    if let Some(change) = history.undo.pop() {
+        let inverse = apply(change, &mut history, images, conv, settings);
+        history.redo.push(inverse);
+    }
+}
+
+pub fn redo(
+    mut history: Signal<History>,
+    images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+) {
+    let mut history = history.write();
+    if let Some(change) = history.redo.pop() {
+        let inverse = apply(change, &mut history, images, conv, settings);
+        history.undo.push(inverse);
+    }
+}
+
+/// Undo and redo buttons, the keyboard shortcuts live in `shortcuts`
+#[component]
+pub fn history_buttons(
+    history: Signal<History>,
+    images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+) -> Element {
+    rsx! {
+        div {
+            class: "btn-group btn-group-sm",
+            button {
+                class: "btn btn-outline-secondary",
+                title: tr("Undo (Ctrl+Z)"),
+                disabled: !history.read().can_undo(),
+                onclick: move |_| undo(history, images, conv, settings),
+                {tr("Undo")}
+            }
+            button {
+                class: "btn btn-outline-secondary",
+                title: tr("Redo (Ctrl+Shift+Z)"),
+                disabled: !history.read().can_redo(),
+                onclick: move |_| redo(history, images, conv, settings),
+                {tr("Redo")}
+            }
+        }
+    }
+}
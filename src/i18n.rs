@@ -0,0 +1,338 @@
+use std::fmt::Display;
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Name of the language in itself, as shown in the switcher
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+}
+
+/// Language of the UI, read by every translated string so components
+/// re-render when it is switched
+pub static LANGUAGE: GlobalSignal<Language> = Signal::global(Language::default);
+
+// English text, which doubles as the key, and its German translation.
+// `{}` marks arguments of `tr_with`.
+const STRINGS: &[(&str, &str)] = &[
+    // Gallery
+    ("Dale & Dawson Image Converter", "Dale & Dawson Bildkonverter"),
+    ("Settings", "Einstellungen"),
+    ("Back to Gallery", "Zurück zur Galerie"),
+    ("Clear", "Leeren"),
+    ("Re-convert All", "Alle neu konvertieren"),
+    ("Download All", "Alle herunterladen"),
+    ("Spritesheet Columns", "Spritesheet-Spalten"),
+    ("Download Spritesheet", "Spritesheet herunterladen"),
+    ("Download Animation", "Animation herunterladen"),
+    ("Undo", "Rückgängig"),
+    ("Redo", "Wiederholen"),
+    ("Undo (Ctrl+Z)", "Rückgängig (Strg+Z)"),
+    ("Redo (Ctrl+Shift+Z)", "Wiederholen (Strg+Umschalt+Z)"),
+    ("Language", "Sprache"),
+    // Image card
+    ("Remove", "Entfernen"),
+    ("Name used for all downloads of this image", "Name für alle Downloads dieses Bildes"),
+    ("Rotate 90°", "Um 90° drehen"),
+    ("Rotate 180°", "Um 180° drehen"),
+    ("Rotate 270°", "Um 270° drehen"),
+    ("Flip horizontally", "Horizontal spiegeln"),
+    ("Flip vertically", "Vertikal spiegeln"),
+    ("Edit Pixels", "Pixel bearbeiten"),
+    ("Frame Delay (ms)", "Bilddauer (ms)"),
+    ("Download", "Herunterladen"),
+    ("8-bit PNG with the palette embedded", "8-Bit-PNG mit eingebetteter Palette"),
+    ("Indexed PNG", "Indiziertes PNG"),
+    ("Send to Board", "An Board senden"),
+    ("Streams the frame to a board over serial, USB or Bluetooth", "Überträgt das Bild per Seriell, USB oder Bluetooth an ein Board"),
+    ("Serial (WebSerial)", "Seriell (WebSerial)"),
+    ("USB (WebUSB)", "USB (WebUSB)"),
+    ("Baud Rate", "Baudrate"),
+    ("any", "beliebig"),
+    ("Interface", "Schnittstelle"),
+    ("Endpoint", "Endpunkt"),
+    ("Bluetooth LE", "Bluetooth LE"),
+    ("Service", "Dienst"),
+    ("Characteristic", "Charakteristik"),
+    ("Chunk Size", "Blockgröße"),
+    ("HTTP POST", "HTTP-POST"),
+    ("Device URL", "Geräte-URL"),
+    ("Frame", "Frame"),
+    ("PNG", "PNG"),
+    ("Authentication Header", "Authentifizierungs-Header"),
+    ("Device answered {}", "Gerät antwortete {}"),
+    ("WLED JSON API", "WLED-JSON-API"),
+    ("Divoom Pixoo", "Divoom Pixoo"),
+    // MQTT
+    ("Publish Finished Conversions", "Fertige Konvertierungen veröffentlichen"),
+    ("Broker WebSocket URL", "WebSocket-URL des Brokers"),
+    ("Topic", "Topic"),
+    ("Username", "Benutzername"),
+    ("Password", "Passwort"),
+    ("Palette Indices", "Palettenindizes"),
+    ("PNG (base64)", "PNG (base64)"),
+    ("Retain Last Frame", "Letztes Bild behalten (retain)"),
+    ("Failed to publish to MQTT: {}", "Veröffentlichen per MQTT fehlgeschlagen: {}"),
+    ("Send", "Senden"),
+    ("Sent to board", "An Board gesendet"),
+    ("Failed to send to board: {}", "Senden an Board fehlgeschlagen: {}"),
+    ("Export", "Exportieren"),
+    ("Indices (CSV)", "Indizes (CSV)"),
+    ("Indices (JSON)", "Indizes (JSON)"),
+    ("C Array", "C-Array"),
+    ("Rust Array", "Rust-Array"),
+    ("Pattern Sheet (PDF)", "Vorlagenblatt (PDF)"),
+    ("Cross-Stitch Chart (DMC)", "Kreuzstichmuster (DMC)"),
+    ("Color Masks (ZIP)", "Farbmasken (ZIP)"),
+    ("Paint by Number (SVG)", "Malen nach Zahlen (SVG)"),
+    ("SVG", "SVG"),
+    ("SVG with Grid", "SVG mit Raster"),
+    ("Bead Pattern ({})", "Bügelperlenmuster ({})"),
+    ("Raw Binary (8-bit)", "Rohdaten (8 Bit)"),
+    ("Raw Binary (4-bit)", "Rohdaten (4 Bit)"),
+    ("E-Paper Framebuffer", "E-Paper-Framebuffer"),
+    ("Packed as set up under Settings", "Gepackt wie unter Einstellungen festgelegt"),
+    ("Failed to export framebuffer: {}", "Framebuffer konnte nicht exportiert werden: {}"),
+    ("Format", "Format"),
+    ("1bpp (Black/White)", "1 bpp (Schwarz/Weiß)"),
+    ("2bpp (4 Gray)", "2 bpp (4 Graustufen)"),
+    ("4bpp (7-Color ACeP)", "4 bpp (7 Farben ACeP)"),
+    ("Bit Order", "Bitreihenfolge"),
+    ("MSB First", "MSB zuerst"),
+    ("LSB First", "LSB zuerst"),
+    ("Pad Rows to Bytes", "Zeilen auffüllen auf Bytes"),
+    ("0 packs rows back to back", "0 packt Zeilen ohne Lücke"),
+    ("Invert (0 is White)", "Invertieren (0 ist Weiß)"),
+    ("Zoom out", "Verkleinern"),
+    ("Zoom in", "Vergrößern"),
+    ("Grid", "Raster"),
+    // Uploads
+    ("Frame {} of {} ({} ms)", "Bild {} von {} ({} ms)"),
+    ("Convert Frame", "Bild konvertieren"),
+    ("Convert All Frames", "Alle Bilder konvertieren"),
+    ("Drop files here", "Dateien hier ablegen"),
+    ("Open Files…", "Dateien öffnen…"),
+    ("Images", "Bilder"),
+    ("Save All to Folder…", "Alle in Ordner speichern…"),
+    ("Saved {} images", "{} Bilder gespeichert"),
+    ("Failed to save images: {}", "Bilder konnten nicht gespeichert werden: {}"),
+    ("Cancel", "Abbrechen"),
+    ("Timestamp {} s of {} s", "Zeitpunkt {} s von {} s"),
+    ("{} of {} files processed, {} failed, {} canceled", "{} von {} Dateien verarbeitet, {} fehlgeschlagen, {} abgebrochen"),
+    ("Duplicate", "Duplikat"),
+    ("ΔE mean {} · max {}", "ΔE Mittel {} · Max {}"),
+    ("Error Heatmap", "Fehler-Heatmap"),
+    ("Show Full Size", "Volle Größe zeigen"),
+    ("Show Thumbnail", "Vorschaubild zeigen"),
+    ("{} selected", "{} ausgewählt"),
+    ("Select All", "Alle auswählen"),
+    ("Clear Selection", "Auswahl aufheben"),
+    ("Delete", "Löschen"),
+    ("Current Settings", "Aktuelle Einstellungen"),
+    ("Re-convert", "Neu konvertieren"),
+    ("Select for batch actions", "Für Stapelaktionen auswählen"),
+    ("Compare", "Vergleichen"),
+    ("Compare Settings", "Einstellungen vergleichen"),
+    ("Profile {}", "Profil {}"),
+    ("Use as Default", "Als Standard verwenden"),
+    ("Make these the settings used for new uploads", "Diese Einstellungen für neue Uploads verwenden"),
+    ("Choose an image", "Bild auswählen"),
+    ("Select an image in the gallery to compare settings.", "Wähle ein Bild in der Galerie, um Einstellungen zu vergleichen."),
+    ("Levels", "Tonwerte"),
+    ("Black: {}", "Schwarz: {}"),
+    ("White: {}", "Weiß: {}"),
+    ("Midtones: {}", "Mitteltöne: {}"),
+    ("Curves", "Gradationskurve"),
+    ("Click to add a point, double click to remove it", "Klicken fügt einen Punkt hinzu, Doppelklick entfernt ihn"),
+    ("Reset", "Zurücksetzen"),
+    ("Select an image in the gallery to see its histogram.", "Wähle ein Bild in der Galerie, um sein Histogramm zu sehen."),
+    ("Blue is exact, red is ΔE {} or more", "Blau ist exakt, Rot ist ΔE {} oder mehr"),
+    ("CIEDE2000 difference between the resized source and the converted image", "CIEDE2000-Abstand zwischen skaliertem Original und konvertiertem Bild"),
+    ("Allow Duplicate Uploads", "Doppelte Uploads erlauben"),
+    ("Skipped {}, it is already in the gallery", "{} übersprungen, es ist schon in der Galerie"),
+    ("Clear Finished", "Erledigte entfernen"),
+    ("Queued", "Wartend"),
+    ("Decoding", "Dekodieren"),
+    ("Converting", "Konvertieren"),
+    ("Done", "Fertig"),
+    ("Failed", "Fehlgeschlagen"),
+    ("Canceled", "Abgebrochen"),
+    ("Restore previous session with {} images?", "Vorherige Sitzung mit {} Bildern wiederherstellen?"),
+    ("Restore", "Wiederherstellen"),
+    ("Discard", "Verwerfen"),
+    (
+        "The gallery is too large for the browser storage and will not survive a reload. Remove some images to keep it.",
+        "Die Galerie ist zu groß für den Browserspeicher und geht beim Neuladen verloren. Entferne einige Bilder, um sie zu behalten.",
+    ),
+    ("Live Preview", "Live-Vorschau"),
+    ("Select an image in the gallery to preview setting changes.", "Wähle ein Bild in der Galerie, um Änderungen der Einstellungen zu sehen."),
+    // Errors
+    ("Dismiss", "Schließen"),
+    ("Failed to load {}: {}", "{} konnte nicht geladen werden: {}"),
+    ("Failed to create ZIP: {}", "ZIP konnte nicht erstellt werden: {}"),
+    ("Failed to create animation: {}", "Animation konnte nicht erstellt werden: {}"),
+    ("Failed to create spritesheet: {}", "Spritesheet konnte nicht erstellt werden: {}"),
+    ("Failed to encode image: {}", "Bild konnte nicht kodiert werden: {}"),
+    ("Failed to encode indexed PNG: {}", "Indiziertes PNG konnte nicht kodiert werden: {}"),
+    ("Failed to encode preview: {}", "Vorschau konnte nicht kodiert werden: {}"),
+    ("Failed to export binary: {}", "Rohdaten konnten nicht exportiert werden: {}"),
+    ("Failed to export chart: {}", "Muster konnte nicht exportiert werden: {}"),
+    ("Failed to export code: {}", "Code konnte nicht exportiert werden: {}"),
+    ("Failed to export masks: {}", "Masken konnten nicht exportiert werden: {}"),
+    ("Failed to grab frame: {}", "Bild konnte nicht aus dem Video gelesen werden: {}"),
+    ("Failed to load image: {}", "Bild konnte nicht geladen werden: {}"),
+    ("Failed to load palette: {}", "Palette konnte nicht geladen werden: {}"),
+    ("Failed to paste image: {}", "Bild konnte nicht eingefügt werden: {}"),
+    ("Failed to read file: {}", "Datei konnte nicht gelesen werden: {}"),
+    ("Failed to restore session: {}", "Sitzung konnte nicht wiederhergestellt werden: {}"),
+    ("Failed to save {}: {}", "{} konnte nicht gespeichert werden: {}"),
+    // Palette
+    ("Palette", "Palette"),
+    ("Custom", "Benutzerdefiniert"),
+    ("Use this color", "Diese Farbe verwenden"),
+    ("Add Color", "Farbe hinzufügen"),
+    ("Export .gpl", "Als .gpl exportieren"),
+    ("Export .json", "Als .json exportieren"),
+    ("Import Palette", "Palette importieren"),
+    ("Extract From Reference Image", "Aus Referenzbild extrahieren"),
+    ("Penalty added to the color distance (ΔE), higher values make this color rarer", "Aufschlag auf den Farbabstand (ΔE), höhere Werte machen diese Farbe seltener"),
+    // Settings
+    ("Device", "Gerät"),
+    ("Dimensions", "Abmessungen"),
+    ("Board Wall (Columns × Rows)", "Board-Wand (Spalten × Zeilen)"),
+    ("Auto Rotate", "Automatisch drehen"),
+    ("Fit", "Einpassen"),
+    ("Fill Color", "Füllfarbe"),
+    ("Resize Filter", "Skalierungsfilter"),
+    ("Pixel Art Scaling", "Pixel-Art-Skalierung"),
+    ("Transparency", "Transparenz"),
+    ("Background", "Hintergrund"),
+    ("Alpha Threshold: {}", "Alpha-Schwelle: {}"),
+    ("Transparent Pixels", "Transparente Pixel"),
+    ("Linear Light", "Lineares Licht"),
+    ("Color Distance", "Farbabstand"),
+    ("Dithering", "Dithering"),
+    ("Kernel", "Kernel"),
+    ("Serpentine Scanning", "Serpentinen-Abtastung"),
+    ("Bayer Matrix", "Bayer-Matrix"),
+    ("Despeckle", "Einzelpixel entfernen"),
+    ("Replaces single pixels without a neighbor of the same color", "Ersetzt einzelne Pixel ohne gleichfarbigen Nachbarn"),
+    ("Minimum Region Size", "Minimale Flächengröße"),
+    ("Smaller areas of one color are merged into their largest neighbor", "Kleinere einfarbige Flächen werden mit ihrem größten Nachbarn verschmolzen"),
+    ("Maximum Colors", "Maximale Farbanzahl"),
+    ("Uses only the palette colors that fit the image best, 0 uses all of them", "Verwendet nur die Palettenfarben, die am besten zum Bild passen, 0 verwendet alle"),
+    ("Output Format", "Ausgabeformat"),
+    // Adjustments
+    ("Adjustments", "Anpassungen"),
+    ("Auto Enhance", "Automatisch verbessern"),
+    ("Gray-world white balance and exposure from the histogram", "Gray-World-Weißabgleich und Belichtung aus dem Histogramm"),
+    ("White Balance", "Weißabgleich"),
+    ("Exposure", "Belichtung"),
+    ("Equalization", "Ausgleich"),
+    ("Histogram Equalization", "Histogrammausgleich"),
+    ("CLAHE", "CLAHE"),
+    ("Tiles: {}", "Kacheln: {}"),
+    ("Clip Limit: {}", "Begrenzung: {}"),
+    ("Tone", "Tonwert"),
+    ("Selected Image Only", "Nur ausgewähltes Bild"),
+    ("Exposure: {}", "Belichtung: {}"),
+    ("Brightness: {}", "Helligkeit: {}"),
+    ("Contrast: {}", "Kontrast: {}"),
+    ("Color", "Farbe"),
+    ("Saturation: {}", "Sättigung: {}"),
+    ("Hue: {}", "Farbton: {}"),
+    ("Denoise", "Entrauschen"),
+    ("Median", "Median"),
+    ("Bilateral", "Bilateral"),
+    ("Strength: {}", "Stärke: {}"),
+    ("Sharpen", "Schärfen"),
+    ("Noise", "Rauschen"),
+    ("Triangular", "Dreiecksverteilt"),
+    ("Blue Noise", "Blaues Rauschen"),
+    ("Seed", "Startwert"),
+    ("New Seed", "Neuer Startwert"),
+    ("Amount: {}", "Stärke: {}"),
+    ("Radius: {}", "Radius: {}"),
+    ("Threshold: {}", "Schwellwert: {}"),
+    // Option labels
+    ("None", "Keins"),
+    ("Error Diffusion", "Fehlerdiffusion"),
+    ("Ordered (Bayer)", "Geordnet (Bayer)"),
+    ("Stretch", "Strecken"),
+    ("Letterbox", "Letterbox"),
+    ("Crop to Fill", "Zuschneiden"),
+    ("Center", "Zentrieren"),
+    ("Nearest", "Nächster Nachbar"),
+    ("Triangle", "Dreieck"),
+    ("Auto", "Automatisch"),
+    ("On", "An"),
+    ("Off", "Aus"),
+    ("Threshold", "Schwellwert"),
+    ("Composite", "Überblenden"),
+    ("Keep Transparency", "Transparenz behalten"),
+    ("Weighted RGB", "Gewichtetes RGB"),
+    ("WebP (lossless)", "WebP (verlustfrei)"),
+];
+
+/// Translates a UI string into the current language, falling back to the
+/// English key for untranslated strings
+pub fn tr(key: &'static str) -> &'static str {
+    match *LANGUAGE.read() {
+        Language::English => key,
+        Language::German => STRINGS
+            .iter()
+            .find(|(en, _)| *en == key)
+            .map_or(key, |&(_, de)| de),
+    }
+}
+
+/// Translates a string with `{}` placeholders, filled with `args` in order
+pub fn tr_with(key: &'static str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut parts = tr(key).split("{}");
+    out.push_str(parts.next().unwrap_or_default());
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// Language switcher, the choice is kept in localStorage
+#[component]
+pub fn language_select() -> Element {
+    rsx! {
+        select {
+            class: "form-select form-select-sm w-auto",
+            title: tr("Language"),
+            onchange: move |evt| {
+                if let Some(lang) = evt.value().parse::<usize>().ok().and_then(|i| Language::ALL.get(i)) {
+                    *LANGUAGE.write() = *lang;
+                }
+            },
+            for (i, lang) in Language::ALL.iter().enumerate() {
+                option {
+                    value: "{i}",
+                    selected: *LANGUAGE.read() == *lang,
+                    "{lang.label()}"
+                }
+            }
+        }
+    }
+}
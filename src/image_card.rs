@@ -0,0 +1,490 @@
+use std::{collections::HashSet, rc::Rc};
+
+use dioxus::prelude::*;
+use image::DynamicImage;
+
+use crate::{
+    batch, device::send_panel, download, eink_panel::eink_options,
+    export::{beads, codegen, cross_stitch, eink, grid, masks, paint_by_number, pdf, svg, OutputFormat},
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{palettes, quality, ConvSettings, DalImageConverter, Transform},
+    pixel_preview::pixel_preview,
+    reconvert_image, toast, ImageResult,
+};
+
+#[component]
+pub fn image_card(
+    images: Signal<Vec<Rc<ImageResult>>>,
+    index: usize,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    format: Signal<OutputFormat>,
+    mut history: Signal<History>,
+    /// Id of the card currently being dragged to a new position
+    mut dragged: Signal<Option<u64>>,
+    mut selected: Signal<Option<u64>>,
+    checked: Signal<HashSet<u64>>,
+) -> Element {
+    let img = images.read()[index].clone();
+    let mut editing = use_signal(|| false);
+    let mut brush = use_signal(|| 0usize);
+    let mut show_heatmap = use_signal(|| false);
+    let mut expanded = use_signal(|| false);
+    let mut show_send = use_signal(|| false);
+    // Large images show a thumbnail until the full size is needed
+    let full_size = !img.is_downscaled() || *expanded.read() || *editing.read() || *show_heatmap.read();
+    let heatmap = if *show_heatmap.read() && !img.delta_e.is_empty() {
+        let dim = (img.img.width(), img.img.height());
+        let heatmap = DynamicImage::ImageRgba8(quality::heatmap(&img.delta_e, dim));
+        OutputFormat::Png.encode(&heatmap).ok().map(|png| download::data_url("image/png", &png))
+    } else {
+        None
+    };
+
+    // Re-runs the conversion of this card with an updated orientation
+    let mut reorient = move |f: fn(Transform) -> Transform| {
+        let img = images.read()[index].clone();
+        let next = reconvert_image(&conv.read(), &settings.read(), &img, f(img.transform), img.tone);
+        if let Some(next) = next {
+            history.write().record_gallery(&images.read());
+            images.write()[index] = next;
+        }
+    };
+
+    rsx! {
+        div {
+            class: "col",
+            // Painting drags the mouse too, so cards only move outside edit mode
+            draggable: !*editing.read(),
+            prevent_default: "ondragover",
+            ondragstart: move |_| dragged.set(Some(images.read()[index].id)),
+            ondragend: move |_| dragged.set(None),
+            ondragover: move |_| {},
+            ondrop: move |_| {
+                let Some(id) = dragged.take() else {
+                    return;
+                };
+                let from = images.read().iter().position(|img| img.id == id);
+                if let Some(from) = from.filter(|&from| from != index) {
+                    history.write().record_gallery(&images.read());
+                    let mut images = images.write();
+                    let img = images.remove(from);
+                    images.insert(index, img);
+                }
+            },
+            div {
+                class: if dragged.read().is_some_and(|id| id == img.id) {
+                    "card opacity-50"
+                } else if selected.read().is_some_and(|id| id == img.id) {
+                    "card border-primary"
+                } else {
+                    "card"
+                },
+                // Shift extends the batch selection from the selected card, Ctrl toggles this one
+                onclick: move |evt| {
+                    let id = images.read()[index].id;
+                    let modifiers = evt.modifiers();
+                    if modifiers.shift() {
+                        batch::select_range(&images.read(), checked, *selected.read(), id);
+                        return;
+                    }
+                    if modifiers.ctrl() || modifiers.meta() {
+                        batch::toggle(checked, id);
+                    }
+                    selected.set(Some(id));
+                },
+                input {
+                    class: "form-check-input position-absolute top-0 start-0 m-2",
+                    r#type: "checkbox",
+                    title: tr("Select for batch actions"),
+                    checked: checked.read().contains(&img.id),
+                    onclick: move |evt| evt.stop_propagation(),
+                    onchange: move |_| batch::toggle(checked, images.read()[index].id),
+                }
+                button {
+                    class: "btn-close position-absolute top-0 end-0 m-2 bg-light",
+                    title: tr("Remove"),
+                    onclick: move |_| {
+                        let id = images.read()[index].id;
+                        history.write().record_gallery(&images.read());
+                        images.write().retain(|img| img.id != id);
+                    }
+                }
+                if full_size {
+                    pixel_preview {
+                        src: img.preview().to_string(),
+                        alt: img.name.clone(),
+                        width: img.img.width(),
+                        height: img.img.height(),
+                        editable: *editing.read(),
+                        overlay: heatmap,
+                        onstroke: move |_| history.write().record_gallery(&images.read()),
+                        onpaint: move |(x, y)| {
+                            let mut next = (*images.read()[index]).clone();
+                            if next.paint(x, y, *brush.read()) {
+                                next.refresh_preview();
+                                images.write()[index] = Rc::new(next);
+                            }
+                        },
+                    }
+                } else {
+                    img {
+                        class: "card-img-top border-bottom",
+                        src: "{img.thumbnail_src()}",
+                        alt: "{img.name}",
+                        loading: "lazy",
+                    }
+                }
+                if img.is_downscaled() && !*editing.read() && !*show_heatmap.read() {
+                    button {
+                        class: "btn btn-sm btn-link mx-2 align-self-start",
+                        onclick: move |_| {
+                            let next = !*expanded.read();
+                            expanded.set(next);
+                        },
+                        if *expanded.read() { {tr("Show Thumbnail")} } else { {tr("Show Full Size")} }
+                    }
+                }
+                div {
+                    class: "input-group input-group-sm mx-2 my-2 w-auto",
+                    input {
+                        r#type: "text",
+                        class: "form-control",
+                        title: tr("Name used for all downloads of this image"),
+                        value: "{img.base_stem()}",
+                        onchange: move |evt| {
+                            let mut next = (*images.read()[index]).clone();
+                            next.rename(&evt.value());
+                            if next.name != images.read()[index].name {
+                                history.write().record_gallery(&images.read());
+                                images.write()[index] = Rc::new(next);
+                            }
+                        }
+                    }
+                    if let Some((r, c)) = img.tile {
+                        span { class: "input-group-text", "r{r + 1} c{c + 1}" }
+                    }
+                }
+                div {
+                    class: "btn-group btn-group-sm mx-2 mb-2",
+                    button {
+                        class: "btn btn-outline-secondary",
+                        title: tr("Rotate 90°"),
+                        onclick: move |_| reorient(|t| t.rotated(1)),
+                        "90°"
+                    }
+                    button {
+                        class: "btn btn-outline-secondary",
+                        title: tr("Rotate 180°"),
+                        onclick: move |_| reorient(|t| t.rotated(2)),
+                        "180°"
+                    }
+                    button {
+                        class: "btn btn-outline-secondary",
+                        title: tr("Rotate 270°"),
+                        onclick: move |_| reorient(|t| t.rotated(3)),
+                        "270°"
+                    }
+                    button {
+                        class: "btn btn-outline-secondary",
+                        title: tr("Flip horizontally"),
+                        onclick: move |_| reorient(|t| Transform { flip_h: !t.flip_h, ..t }),
+                        "⇋"
+                    }
+                    button {
+                        class: "btn btn-outline-secondary",
+                        title: tr("Flip vertically"),
+                        onclick: move |_| reorient(|t| Transform { flip_v: !t.flip_v, ..t }),
+                        "⇵"
+                    }
+                }
+                div {
+                    class: "d-flex flex-wrap align-items-center gap-1 mx-2 mb-2",
+                    button {
+                        class: if *editing.read() { "btn btn-sm btn-secondary" } else { "btn btn-sm btn-outline-secondary" },
+                        onclick: move |_| {
+                            let next = !*editing.read();
+                            editing.set(next);
+                        },
+                        {tr("Edit Pixels")}
+                    }
+                    if *editing.read() {
+                        for (i, &color) in img.palette.iter().enumerate() {
+                            button {
+                                class: if *brush.read() == i { "btn btn-sm border border-3 border-primary" } else { "btn btn-sm border" },
+                                style: "width: 1.6em; height: 1.6em; padding: 0; background-color: {palettes::to_hex(color)}",
+                                title: "{i}: {palettes::to_hex(color)}",
+                                onclick: move |_| brush.set(i),
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "input-group input-group-sm mx-2 mb-2 w-auto",
+                    span { class: "input-group-text", {tr("Frame Delay (ms)")} }
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "10",
+                        step: "100",
+                        value: "{img.delay_ms}",
+                        onchange: move |evt| {
+                            if let Ok(ms) = evt.value().parse::<u32>() {
+                                let mut next = (*images.read()[index]).clone();
+                                next.delay_ms = ms.max(10);
+                                history.write().record_gallery(&images.read());
+                                images.write()[index] = Rc::new(next);
+                            }
+                        }
+                    }
+                }
+                if let Some(quality) = img.quality {
+                    div {
+                        class: "d-flex align-items-center gap-2 small text-body-secondary mx-2 mb-1",
+                        span {
+                            title: tr("CIEDE2000 difference between the resized source and the converted image"),
+                            {tr_with("ΔE mean {} · max {}", &[&format!("{:.2}", quality.mean), &format!("{:.2}", quality.max)])}
+                        }
+                        if !img.delta_e.is_empty() {
+                            div {
+                                class: "form-check mb-0",
+                                input {
+                                    class: "form-check-input",
+                                    r#type: "checkbox",
+                                    checked: *show_heatmap.read(),
+                                    onchange: move |evt| show_heatmap.set(evt.checked()),
+                                }
+                                label {
+                                    class: "form-check-label",
+                                    title: tr_with("Blue is exact, red is ΔE {} or more", &[&quality::HEATMAP_MAX]),
+                                    {tr("Error Heatmap")}
+                                }
+                            }
+                        }
+                    }
+                }
+                ul {
+                    class: "list-unstyled small mx-2",
+                    for (color, count, pct) in img.usage() {
+                        li {
+                            span {
+                                class: "d-inline-block border me-2",
+                                style: "width: 1em; height: 1em; vertical-align: middle; background-color: {palettes::to_hex(color)}",
+                            }
+                            "{palettes::to_hex(color)}: {count} ({pct:.1}%)"
+                        }
+                    }
+                }
+                div {
+                    class: "d-flex gap-2 mx-2 mb-2",
+                    button {
+                        class: "btn btn-primary",
+                        onclick: move |_| {
+                            let img = images.read()[index].clone();
+                            let format = *format.read();
+                            match img.encode(format) {
+                                Ok(data) => download::save(&img.file_name(format), format.mime(), &data),
+                                Err(e) => toast::error(tr_with("Failed to encode image: {}", &[&e])),
+                            }
+                        },
+                        {tr("Download")}
+                    }
+                    button {
+                        class: "btn btn-outline-primary",
+                        title: tr("8-bit PNG with the palette embedded"),
+                        onclick: move |_| {
+                            let img = images.read()[index].clone();
+                            match img.indexed_png() {
+                                Ok(data) => download::save(&format!("{}_indexed.png", img.stem()), "image/png", &data),
+                                Err(e) => toast::error(tr_with("Failed to encode indexed PNG: {}", &[&e])),
+                            }
+                        },
+                        {tr("Indexed PNG")}
+                    }
+                    button {
+                        class: if *show_send.read() { "btn btn-success" } else { "btn btn-outline-success" },
+                        title: tr("Streams the frame to a board over serial, USB or Bluetooth"),
+                        onclick: move |_| show_send.toggle(),
+                        {tr("Send to Board")}
+                    }
+                    div {
+                        class: "dropdown",
+                        button {
+                            class: "btn btn-outline-secondary dropdown-toggle",
+                            "data-bs-toggle": "dropdown",
+                            {tr("Export")}
+                        }
+                        ul {
+                            class: "dropdown-menu",
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let csv = grid::to_csv(&img.indices, img.img.width(), img.transparency().as_deref());
+                                        download::save(&format!("{}.csv", img.stem()), "text/csv", csv.as_bytes());
+                                    },
+                                    {tr("Indices (CSV)")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let json = grid::to_json(&img.indices, img.img.width(), img.transparency().as_deref());
+                                        download::save(&format!("{}.json", img.stem()), "application/json", json.as_bytes());
+                                    },
+                                    {tr("Indices (JSON)")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        match codegen::to_c_array(&img.stem(), &img.indices, dim) {
+                                            Ok(code) => download::save(&format!("{}.h", img.stem()), "text/x-c", code.as_bytes()),
+                                            Err(e) => toast::error(tr_with("Failed to export code: {}", &[&e])),
+                                        }
+                                    },
+                                    {tr("C Array")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        match codegen::to_rust_array(&img.stem(), &img.indices, dim) {
+                                            Ok(code) => download::save(&format!("{}.rs", img.stem()), "text/rust", code.as_bytes()),
+                                            Err(e) => toast::error(tr_with("Failed to export code: {}", &[&e])),
+                                        }
+                                    },
+                                    {tr("Rust Array")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        let data = pdf::pattern_sheet(&img.stem(), &img.indices, dim, &img.palette, &img.histogram);
+                                        download::save(&format!("{}.pdf", img.stem()), "application/pdf", &data);
+                                    },
+                                    {tr("Pattern Sheet (PDF)")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        match cross_stitch::chart(&img.stem(), &img.indices, dim, &img.palette) {
+                                            Ok(chart) => download::save(&format!("{}_cross_stitch.txt", img.stem()), "text/plain", chart.as_bytes()),
+                                            Err(e) => toast::error(tr_with("Failed to export chart: {}", &[&e])),
+                                        }
+                                    },
+                                    {tr("Cross-Stitch Chart (DMC)")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        match masks::masks_zip(&img.stem(), &img.indices, dim, &img.palette) {
+                                            Ok(zip) => download::save(&format!("{}_masks.zip", img.stem()), "application/zip", &zip),
+                                            Err(e) => toast::error(tr_with("Failed to export masks: {}", &[&e])),
+                                        }
+                                    },
+                                    {tr("Color Masks (ZIP)")}
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        let dim = (img.img.width(), img.img.height());
+                                        let doc = paint_by_number::outline_svg(&img.indices, dim, &img.palette);
+                                        download::save(&format!("{}_paint_by_number.svg", img.stem()), "image/svg+xml", doc.as_bytes());
+                                    },
+                                    {tr("Paint by Number (SVG)")}
+                                }
+                            }
+                            for (label, with_grid) in [("SVG", false), ("SVG with Grid", true)] {
+                                li {
+                                    button {
+                                        class: "dropdown-item",
+                                        onclick: move |_| {
+                                            let img = images.read()[index].clone();
+                                            let dim = (img.img.width(), img.img.height());
+                                            let doc = svg::to_svg(&img.indices, dim, &img.palette, img.transparency().as_deref(), with_grid);
+                                            download::save(&format!("{}.svg", img.stem()), "image/svg+xml", doc.as_bytes());
+                                        },
+                                        "{tr(label)}"
+                                    }
+                                }
+                            }
+                            for brand in beads::BeadBrand::ALL {
+                                li {
+                                    button {
+                                        class: "dropdown-item",
+                                        onclick: move |_| {
+                                            let img = images.read()[index].clone();
+                                            let dim = (img.img.width(), img.img.height());
+                                            match beads::pattern(&img.stem(), brand, &img.indices, dim, &img.palette) {
+                                                Ok(pattern) => download::save(&format!("{}_{}.txt", img.stem(), brand.label().to_lowercase()), "text/plain", pattern.as_bytes()),
+                                                Err(e) => toast::error(tr_with("Failed to export chart: {}", &[&e])),
+                                            }
+                                        },
+                                        {tr_with("Bead Pattern ({})", &[&brand.label()])}
+                                    }
+                                }
+                            }
+                            li {
+                                button {
+                                    class: "dropdown-item",
+                                    title: tr("Packed as set up under Settings"),
+                                    onclick: move |_| {
+                                        let img = images.read()[index].clone();
+                                        match eink::pack(&img.indices, img.img.width(), &eink_options()) {
+                                            Ok(data) => download::save(&format!("{}_epd.bin", img.stem()), "application/octet-stream", &data),
+                                            Err(e) => toast::error(tr_with("Failed to export framebuffer: {}", &[&e])),
+                                        }
+                                    },
+                                    {tr("E-Paper Framebuffer")}
+                                }
+                            }
+                            for (label, packing) in [("Raw Binary (8-bit)", codegen::Packing::Bits8), ("Raw Binary (4-bit)", codegen::Packing::Bits4)] {
+                                li {
+                                    button {
+                                        class: "dropdown-item",
+                                        onclick: move |_| {
+                                            let img = images.read()[index].clone();
+                                            match codegen::to_binary(&img.indices, packing) {
+                                                Ok(data) => download::save(&format!("{}.bin", img.stem()), "application/octet-stream", &data),
+                                                Err(e) => toast::error(tr_with("Failed to export binary: {}", &[&e])),
+                                            }
+                                        },
+                                        "{tr(label)}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if *show_send.read() {
+                    send_panel { images, index }
+                }
+            }
+        }
+    }
+}
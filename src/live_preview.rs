@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    convert_image_async,
+    download::ObjectUrl,
+    export::OutputFormat,
+    i18n::{tr, tr_with},
+    img_conv::{ConvSettings, DalImageConverter, Tone},
+    timer, toast, with_tone, ImageResult,
+};
+
+// Quiet time after the last settings change before the preview is rebuilt
+const DEBOUNCE_MS: u32 = 250;
+
+/// Re-converts the selected image with the current settings while they are
+/// being adjusted, without touching the gallery entry itself
+#[component]
+pub fn live_preview(
+    images: Signal<Vec<Rc<ImageResult>>>,
+    selected: Signal<Option<u64>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    /// Tone drafted for the selected image alone, previewed before it's applied
+    tone_draft: Signal<Option<Tone>>,
+) -> Element {
+    let mut enabled = use_signal(|| true);
+    let mut preview = use_signal(|| None::<ObjectUrl>);
+    let mut pending = use_signal(|| None::<Task>);
+
+    use_effect(move || {
+        // Subscribe to everything the preview depends on
+        let id = *selected.read();
+        let settings = settings.read().clone();
+        let draft = *tone_draft.read();
+        let _ = conv.read();
+        let img = id.and_then(|id| images.read().iter().find(|img| img.id == id).cloned());
+
+        if let Some(task) = pending.take() {
+            task.cancel();
+        }
+        let (Some(img), true) = (img, *enabled.read()) else {
+            preview.set(None);
+            return;
+        };
+
+        // The conversion runs in its own task after the debounce, so input
+        // handlers return right away and superseded runs are dropped
+        let task = spawn(async move {
+            timer::sleep(DEBOUNCE_MS).await;
+            let settings = with_tone(&settings, draft.or(img.tone));
+            let conv = conv.peek().clone();
+            let results = convert_image_async(conv, settings, img.source.clone(), img.name.clone(), img.transform).await;
+            let result = results.iter().find(|r| r.tile == img.tile).or(results.first());
+            if let Some(result) = result {
+                let url = OutputFormat::Png
+                    .encode(&result.img)
+                    .and_then(|png| ObjectUrl::new("image/png", &png).map_err(|e| anyhow::anyhow!(e)));
+                match url {
+                    Ok(url) => preview.set(Some(url)),
+                    Err(e) => toast::error(tr_with("Failed to encode preview: {}", &[&e])),
+                }
+            }
+            pending.set(None);
+        });
+        pending.set(Some(task));
+    });
+
+    rsx! {
+        div {
+            class: "card sticky-top mb-3",
+            div {
+                class: "card-body",
+                div {
+                    class: "form-check form-switch mb-2",
+                    input {
+                        class: "form-check-input",
+                        r#type: "checkbox",
+                        checked: *enabled.read(),
+                        onchange: move |evt| enabled.set(evt.checked()),
+                    }
+                    label { class: "form-check-label", {tr("Live Preview")} }
+                }
+                match preview.read().as_ref() {
+                    Some(src) => rsx! {
+                        img {
+                            class: "img-fluid",
+                            style: "image-rendering: pixelated; width: 100%;",
+                            src: "{src.as_str()}",
+                        }
+                    },
+                    None => rsx! {
+                        p {
+                            class: "text-muted small mb-0",
+                            {tr("Select an image in the gallery to preview setting changes.")}
+                        }
+                    },
+                }
+                if pending.read().is_some() {
+                    div { class: "spinner-border spinner-border-sm mt-2" }
+                }
+            }
+        }
+    }
+}
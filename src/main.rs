@@ -1,123 +1,821 @@
 #![allow(non_snake_case)]
 
-pub mod img_conv;
+mod adjust_panel;
+mod batch;
+mod compare;
+mod curve_panel;
+mod device;
+mod download;
+mod eink_panel;
+mod frame_picker;
+mod histogram_panel;
+mod history;
+mod i18n;
+mod image_card;
+mod live_preview;
+mod media_input;
+mod mqtt;
+mod palette_editor;
+mod pixel_preview;
+mod queue;
+mod session;
+mod settings;
+mod shortcuts;
+mod storage;
+mod timer;
+mod toast;
+mod upload;
 
-use std::rc::Rc;
+pub use dal_img_core::{export, img_conv};
 
-use base64::{prelude::BASE64_STANDARD, Engine};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use batch::batch_bar;
+use compare::Compare;
 use dioxus::prelude::*;
-use dioxus_logger::tracing::{info, Level};
-use image::DynamicImage;
-use img_conv::DalImageConverter;
+use download::ObjectUrl;
+use dioxus_logger::tracing::{error, info, Level};
+use export::OutputFormat;
+use frame_picker::{frame_picker, Animation};
+use history::{history_buttons, History};
+use i18n::{language_select, tr, tr_with, LANGUAGE};
+use image::{imageops::FilterType, DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+use img_conv::{quality::Quality, stepwise::Conversion, ConvSettings, DalImageConverter, Tone, Transform};
+use image_card::image_card;
+use media_input::{video_picker, Video};
+use mqtt::{mqtt_publisher, MqttSettings};
+use queue::queue_panel;
+use session::session_keeper;
+use settings::Settings;
+use shortcuts::shortcuts;
+use toast::toasts;
+use upload::{UploadSource, Uploader};
+
+const DEFAULT_DELAY_MS: u32 = 2000;
+
+#[cfg(feature = "desktop")]
+const BOOTSTRAP_HEAD: &str = r#"<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/bootstrap/dist/css/bootstrap.css">
+<script src="https://cdn.jsdelivr.net/npm/bootstrap/dist/js/bootstrap.js"></script>"#;
+const UPLOAD_ACCEPT: &str = ".png,.jpg,.jpeg,.webp,.gif,.heic,.heif,.avif,.jxl,.mp4,.webm,.csv";
 
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
 pub struct ImageResult {
+    /// Identifies a gallery entry across removals and re-conversions
+    pub id: u64,
     pub img: DynamicImage,
     pub name: String,
-    pub base64: String,
+    /// Downscaled PNG of `img` for the gallery, shared by clones of the entry.
+    /// `None` if it couldn't be encoded, see `thumbnail_src`
+    pub thumbnail: Rc<Option<ObjectUrl>>,
+    // Full size PNG, only encoded once a card is zoomed into or edited
+    preview: Rc<OnceCell<Option<ObjectUrl>>>,
+    // Decoded upload and the manual orientation applied to it
+    pub source: Rc<DynamicImage>,
+    pub transform: Transform,
+    /// Brightness, contrast and exposure for this image only, replacing the
+    /// ones of the settings whenever it is converted again
+    pub tone: Option<Tone>,
+    /// Settings the image was converted with
+    pub settings: ConvSettings,
+    // (row, col) when the source was split across several boards
+    pub tile: Option<(u32, u32)>,
+    // Palette the image was converted with, the palette index of every
+    // pixel and the usage count of each entry
+    pub palette: Vec<[u8; 3]>,
+    pub indices: Vec<usize>,
+    pub histogram: Vec<usize>,
+    /// Display time when the gallery is exported as an animation
+    pub delay_ms: u32,
+    /// Hash of the uploaded file, used to spot repeated uploads
+    pub source_hash: Option<u64>,
+    /// Error against the resized source, measured when the image was converted.
+    /// The per-pixel errors are kept for the heatmap, but not across sessions
+    pub quality: Option<Quality>,
+    pub delta_e: Vec<Option<f32>>,
+}
+
+// Longest side of gallery thumbnails, smaller images are shown as they are
+const THUMBNAIL_SIZE: u32 = 160;
+
+// Transparent 1x1 GIF, shown when a preview couldn't be encoded
+const PLACEHOLDER_URL: &str = "data:image/gif;base64,R0lGODlhAQABAAAAACH5BAEKAAEALAAAAAABAAEAAAICTAEAOw==";
+
+// The gallery preview is always a lossless PNG, downloads use the chosen format.
+// Failures are only logged, the gallery falls back instead of going down
+fn preview_url(img: &DynamicImage) -> Option<ObjectUrl> {
+    let url = OutputFormat::Png
+        .encode(img)
+        .and_then(|png| ObjectUrl::new("image/png", &png).map_err(|e| anyhow::anyhow!(e)));
+    url.map_err(|e| error!("Failed to encode preview: {}", e)).ok()
+}
+
+fn thumbnail_url(img: &DynamicImage) -> Rc<Option<ObjectUrl>> {
+    if img.width().max(img.height()) <= THUMBNAIL_SIZE {
+        return Rc::new(preview_url(img));
+    }
+    Rc::new(preview_url(&img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)))
 }
 
 impl ImageResult {
-    pub fn new(img: DynamicImage, name: String) -> Self {
-        let mut buf = std::io::Cursor::new(Vec::new());
-        img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
-        let enc = BASE64_STANDARD.encode(buf.get_ref());
-        let base64 = format!("data:image/png;base64,{enc}");
-        Self { img, name, base64 }
+    pub fn new(
+        img: DynamicImage,
+        name: String,
+        conv: &DalImageConverter,
+        source: Rc<DynamicImage>,
+        transform: Transform,
+        settings: &ConvSettings,
+        tile: Option<(u32, u32)>,
+    ) -> Self {
+        let thumbnail = thumbnail_url(&img);
+        let indices = conv.indices(&img.to_rgb8());
+        let mut histogram = vec![0; conv.palette().len()];
+        for &i in &indices {
+            histogram[i] += 1;
+        }
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            img,
+            name,
+            thumbnail,
+            preview: Rc::default(),
+            source,
+            transform,
+            tone: None,
+            settings: settings.clone(),
+            tile,
+            palette: conv.palette().to_vec(),
+            indices,
+            histogram,
+            delay_ms: DEFAULT_DELAY_MS,
+            source_hash: None,
+            quality: None,
+            delta_e: Vec::new(),
+        }
+    }
+
+    /// `settings` with the tone of this image, if it has its own
+    pub fn settings_for(&self, settings: &ConvSettings) -> ConvSettings {
+        with_tone(settings, self.tone)
+    }
+
+    /// Whether the thumbnail is smaller than the image
+    pub fn is_downscaled(&self) -> bool {
+        self.img.width().max(self.img.height()) > THUMBNAIL_SIZE
+    }
+
+    /// Full size preview URL, encoded on first use
+    pub fn preview(&self) -> &str {
+        if !self.is_downscaled() {
+            return self.thumbnail_src();
+        }
+        match self.preview.get_or_init(|| preview_url(&self.img)) {
+            Some(url) => url.as_str(),
+            None => self.thumbnail_src(),
+        }
     }
+
+    /// Thumbnail URL, or a placeholder if it couldn't be encoded
+    pub fn thumbnail_src(&self) -> &str {
+        self.thumbnail.as_ref().as_ref().map_or(PLACEHOLDER_URL, ObjectUrl::as_str)
+    }
+
+    /// File name without extension
+    pub fn base_stem(&self) -> &str {
+        match self.name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => &self.name,
+        }
+    }
+
+    /// File name without extension, with the tile position appended for split images
+    pub fn stem(&self) -> String {
+        let stem = self.base_stem();
+        match self.tile {
+            // 1-based, matching how people count boards on a wall
+            Some((r, c)) => format!("{stem}_r{}_c{}", r + 1, c + 1),
+            None => stem.to_string(),
+        }
+    }
+
+    /// Renames the image to a user chosen stem, keeping the original extension.
+    /// Path separators are replaced, so the name stays usable inside ZIPs.
+    pub fn rename(&mut self, stem: &str) {
+        let stem = stem.trim().replace(['/', '\\'], "_");
+        if stem.is_empty() {
+            return;
+        }
+        self.name = match self.name.rsplit_once('.') {
+            Some((old, ext)) if !old.is_empty() => format!("{stem}.{ext}"),
+            _ => stem,
+        };
+    }
+
+    /// Pixels that were left transparent, if the image kept its alpha channel
+    pub fn transparency(&self) -> Option<Vec<bool>> {
+        let DynamicImage::ImageRgba8(img) = &self.img else {
+            return None;
+        };
+        Some(img.pixels().map(|px| px.0[3] < 255).collect())
+    }
+
+    pub fn encode(&self, format: OutputFormat) -> anyhow::Result<Vec<u8>> {
+        format.encode(&self.img)
+    }
+
+    pub fn file_name(&self, format: OutputFormat) -> String {
+        format!("{}.{}", self.stem(), format.extension())
+    }
+
+    pub fn indexed_png(&self) -> anyhow::Result<Vec<u8>> {
+        export::indexed::indexed_png(
+            &self.indices,
+            (self.img.width(), self.img.height()),
+            &self.palette,
+            self.transparency().as_deref(),
+        )
+    }
+
+    /// Sets the pixel at (x, y) to palette entry `index`, making it opaque.
+    /// Edits only touch the converted pixels, re-converting the source
+    /// discards them. Returns whether anything changed.
+    pub fn paint(&mut self, x: u32, y: u32, index: usize) -> bool {
+        let (w, h) = (self.img.width(), self.img.height());
+        if x >= w || y >= h || index >= self.palette.len() {
+            return false;
+        }
+        let [r, g, b] = self.palette[index];
+        let i = (y * w + x) as usize;
+        match &mut self.img {
+            DynamicImage::ImageRgba8(img) => {
+                if self.indices[i] == index && img.get_pixel(x, y).0[3] == 255 {
+                    return false;
+                }
+                img.put_pixel(x, y, Rgba([r, g, b, 255]));
+            }
+            DynamicImage::ImageRgb8(img) => {
+                if self.indices[i] == index {
+                    return false;
+                }
+                img.put_pixel(x, y, Rgb([r, g, b]));
+            }
+            // Converted images are always Rgb8 or Rgba8
+            _ => return false,
+        }
+        self.histogram[self.indices[i]] -= 1;
+        self.histogram[index] += 1;
+        self.indices[i] = index;
+        true
+    }
+
+    /// Records the conversion error against the resized source
+    pub fn measure(&mut self, reference: &DynamicImage) {
+        self.delta_e = img_conv::quality::delta_e(reference, &self.img);
+        self.quality = Some(Quality::from_errors(&self.delta_e));
+    }
+
+    /// Re-encodes the gallery preview after the pixels were edited
+    pub fn refresh_preview(&mut self) {
+        self.thumbnail = thumbnail_url(&self.img);
+        self.preview = Rc::default();
+    }
+
+    /// Used palette entries as (color, count, percentage), most frequent first
+    pub fn usage(&self) -> Vec<([u8; 3], usize, f32)> {
+        let total = self.histogram.iter().sum::<usize>().max(1) as f32;
+        let mut usage: Vec<_> = self
+            .palette
+            .iter()
+            .zip(&self.histogram)
+            .filter(|(_, &n)| n > 0)
+            .map(|(&c, &n)| (c, n, n as f32 * 100.0 / total))
+            .collect();
+        usage.sort_by(|a, b| b.1.cmp(&a.1));
+        usage
+    }
+}
+
+// Packs every converted image into one ZIP download
+fn download_all(images: &[Rc<ImageResult>], format: OutputFormat) -> anyhow::Result<()> {
+    let files = images
+        .iter()
+        .map(|img| Ok((img.file_name(format), img.encode(format)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let zip = export::archive::zip_files(&files)?;
+    download::save("images.zip", "application/zip", &zip);
+    Ok(())
+}
+
+// Writes every converted image as its own file into a folder picked natively
+#[cfg(feature = "desktop")]
+fn save_all_to_folder(images: &[Rc<ImageResult>], format: OutputFormat) {
+    let saved = images
+        .iter()
+        .map(|img| Ok((img.file_name(format), img.encode(format)?)))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .and_then(|files| download::save_to_folder(&files));
+    match saved {
+        Ok(0) => {}
+        Ok(count) => toast::notify(toast::ToastLevel::Info, tr_with("Saved {} images", &[&count])),
+        Err(e) => toast::error(tr_with("Failed to save images: {}", &[&e])),
+    }
+}
+
+// Packs the gallery into a spritesheet PNG plus JSON atlas, shipped as one ZIP
+fn download_spritesheet(images: &[Rc<ImageResult>], columns: u32) -> anyhow::Result<()> {
+    let frames: Vec<(String, &DynamicImage)> = images.iter().map(|img| (img.stem(), &img.img)).collect();
+    let (sheet, atlas) = export::spritesheet::pack(&frames, columns);
+    let png = OutputFormat::Png.encode(&DynamicImage::ImageRgba8(sheet))?;
+    let zip = export::archive::zip_files(&[
+        ("spritesheet.png".to_string(), png),
+        ("spritesheet.json".to_string(), atlas.into_bytes()),
+    ])?;
+    download::save("spritesheet.zip", "application/zip", &zip);
+    Ok(())
+}
+
+// Plays the gallery in order as a looping GIF, using each frame's delay
+fn download_animation(images: &[Rc<ImageResult>]) -> anyhow::Result<()> {
+    let frames: Vec<(&DynamicImage, u32)> = images.iter().map(|img| (&img.img, img.delay_ms)).collect();
+    let gif = export::animation::gif(&frames)?;
+    download::save("animation.gif", "image/gif", &gif);
+    Ok(())
 }
 
 #[derive(Clone, Routable, Debug, PartialEq)]
 enum Route {
     #[route("/")]
     Home {},
+    #[route("/settings")]
+    Settings {},
+    #[route("/compare")]
+    Compare {},
+}
+
+/// State shared by all routes, so switching pages keeps the gallery
+#[derive(Clone, Copy)]
+pub struct AppState {
+    pub images: Signal<Vec<Rc<ImageResult>>>,
+    pub conv: Signal<DalImageConverter>,
+    pub settings: Signal<ConvSettings>,
+    pub format: Signal<OutputFormat>,
+    pub history: Signal<History>,
+    /// Gallery entry shown in the live preview
+    pub selected: Signal<Option<u64>>,
+    /// Gallery entries checked for batch actions
+    pub checked: Signal<HashSet<u64>>,
+    pub mqtt: Signal<MqttSettings>,
 }
 
 fn main() {
     // Init logger
     dioxus_logger::init(Level::INFO).expect("failed to init logger");
     info!("starting app");
+    #[cfg(not(feature = "desktop"))]
     launch(App);
+    // The web build gets Bootstrap from Dioxus.toml, the window needs it in its head
+    #[cfg(feature = "desktop")]
+    LaunchBuilder::desktop()
+        .with_cfg(
+            dioxus::desktop::Config::new()
+                .with_window(dioxus::desktop::WindowBuilder::new().with_title("Dale & Dawson Image Converter"))
+                .with_custom_head(BOOTSTRAP_HEAD.to_string()),
+        )
+        .launch(App);
 }
 
 fn App() -> Element {
+    let images = use_signal(Vec::new);
+    let conv = storage::use_persistent_converter("converter");
+    let settings = storage::use_persistent("settings", ConvSettings::default);
+    let format = storage::use_persistent("output_format", OutputFormat::default);
+    let mqtt = storage::use_persistent("mqtt", MqttSettings::default);
+    let mut history = use_signal(|| History::new(settings.read().clone(), conv.read().clone()));
+    // Palette and size edits come from many inputs, so they are recorded after the fact
+    use_effect(move || history.write().converter_changed(&conv.read()));
+    use_hook(|| {
+        if let Some(lang) = storage::load("language") {
+            *LANGUAGE.write() = lang;
+        }
+    });
+    use_effect(|| storage::save("language", &*LANGUAGE.read()));
+    use_context_provider(|| AppState {
+        images,
+        conv,
+        settings,
+        format,
+        history,
+        selected: Signal::new(None),
+        checked: Signal::new(HashSet::new()),
+        mqtt,
+    });
+
     rsx! {
         div {
             class: "container",
+            div {
+                class: "d-flex justify-content-end gap-2 my-2",
+                history_buttons { history, images, conv, settings }
+                language_select {}
+            }
+            session_keeper { images, history }
+            mqtt_publisher { images, mqtt }
+            shortcuts {}
+            toasts {}
             Router::<Route> {}
         }
     }
 }
 
-fn convert(conv: &DalImageConverter, auto_rotate: bool, buf: &[u8], name: String) -> anyhow::Result<Rc<ImageResult>> {
-    let img = image::load_from_memory(buf)?;
-    let img = conv.process(img, auto_rotate);
-    Ok(Rc::new(ImageResult::new(img, name)))
+fn convert_image(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    source: Rc<DynamicImage>,
+    name: String,
+    transform: Transform,
+) -> Vec<Rc<ImageResult>> {
+    let settings = oriented(settings, transform);
+    let img = transform.apply((*source).clone());
+
+    let tiles = if settings.tiles == (1, 1) {
+        let (reference, img) = conv.process_with_reference(img, &settings);
+        vec![((0, 0), reference, img)]
+    } else {
+        conv.process_tiled(img, &settings)
+    };
+    gallery_entries(conv, &settings, source, name, transform, tiles)
+}
+
+// Like `convert_image`, but maps a few rows at a time and yields to the UI in
+// between, so large boards and walls don't freeze the page while they convert
+async fn convert_image_async(
+    conv: DalImageConverter,
+    settings: ConvSettings,
+    source: Rc<DynamicImage>,
+    name: String,
+    transform: Transform,
+) -> Vec<Rc<ImageResult>> {
+    let settings = oriented(&settings, transform);
+    let mut conversion = Conversion::new(&conv, transform.apply((*source).clone()), &settings);
+    while conversion.step() {
+        timer::sleep(0).await;
+    }
+    gallery_entries(&conv, &settings, source, name, transform, conversion.finish())
+}
+
+// `settings` with an image's own tone in place of theirs, if it has one
+fn with_tone(settings: &ConvSettings, tone: Option<Tone>) -> ConvSettings {
+    ConvSettings {
+        tone: tone.unwrap_or(settings.tone),
+        ..settings.clone()
+    }
+}
+
+// A manual orientation overrides the auto rotate heuristic
+fn oriented(settings: &ConvSettings, transform: Transform) -> ConvSettings {
+    ConvSettings {
+        auto_rotate: settings.auto_rotate && transform.is_identity(),
+        ..settings.clone()
+    }
+}
+
+// Measures the converted boards of one source and wraps them for the gallery.
+// Only split images record which tile they are
+fn gallery_entries(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    source: Rc<DynamicImage>,
+    name: String,
+    transform: Transform,
+    tiles: Vec<((u32, u32), DynamicImage, DynamicImage)>,
+) -> Vec<Rc<ImageResult>> {
+    tiles
+        .into_iter()
+        .map(|(tile, reference, img)| {
+            let tile = (settings.tiles != (1, 1)).then_some(tile);
+            let mut result = ImageResult::new(img, name.clone(), conv, source.clone(), transform, settings, tile);
+            result.measure(&reference);
+            Rc::new(result)
+        })
+        .collect()
+}
+
+// A conversion shared by every tile of one upload with the same orientation and tone
+struct Reconverted {
+    source: Rc<DynamicImage>,
+    transform: Transform,
+    tone: Option<Tone>,
+    // Whether the upload was split into the same boards as it is now
+    same_layout: bool,
+    results: Vec<Rc<ImageResult>>,
+    // Set once a changed tile layout was emitted as a whole
+    emitted: bool,
+}
+
+// Positions in the new results of a group that replace one of its gallery
+// entries. With an unchanged layout every entry takes the tile in its place,
+// otherwise the first entry takes all of them and the others are dropped
+fn replacement_tiles(
+    same_layout: bool,
+    tile: Option<(u32, u32)>,
+    new_tiles: &[Option<(u32, u32)>],
+    emitted: &mut bool,
+) -> Vec<usize> {
+    if same_layout {
+        return new_tiles.iter().position(|t| *t == tile).into_iter().collect();
+    }
+    if std::mem::replace(emitted, true) {
+        return vec![];
+    }
+    (0..new_tiles.len()).collect()
+}
+
+// Runs gallery entries through the current settings again, converting each
+// upload once per orientation and tone. Returns what replaces each entry by its
+// id, see `merge_reconverted`. Entries keep their id, name and delay, removed
+// tiles stay removed. If the tile layout changed, the new tiles of an upload
+// replace its first entry and the other entries are dropped.
+async fn reconvert_all(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    images: &[Rc<ImageResult>],
+) -> HashMap<u64, Vec<Rc<ImageResult>>> {
+    let mut converted: Vec<Reconverted> = vec![];
+    let mut out: HashMap<u64, Vec<Rc<ImageResult>>> = HashMap::new();
+    for prev in images {
+        let shared = |c: &Reconverted| {
+            Rc::ptr_eq(&c.source, &prev.source) && c.transform == prev.transform && c.tone == prev.tone
+        };
+        let group = match converted.iter().position(shared) {
+            Some(i) => &mut converted[i],
+            None => {
+                let image_settings = prev.settings_for(settings);
+                let (source, name) = (prev.source.clone(), prev.name.clone());
+                let results = convert_image_async(conv.clone(), image_settings, source, name, prev.transform).await;
+                converted.push(Reconverted {
+                    source: prev.source.clone(),
+                    transform: prev.transform,
+                    tone: prev.tone,
+                    same_layout: prev.settings.tiles == settings.tiles,
+                    results,
+                    emitted: false,
+                });
+                converted.last_mut().unwrap()
+            }
+        };
+
+        let new_tiles: Vec<_> = group.results.iter().map(|next| next.tile).collect();
+        let replacements = out.entry(prev.id).or_default();
+        for i in replacement_tiles(group.same_layout, prev.tile, &new_tiles, &mut group.emitted) {
+            let mut next = (*group.results[i]).clone();
+            if group.same_layout {
+                next.id = prev.id;
+                next.name = prev.name.clone();
+            }
+            next.source_hash = prev.source_hash;
+            next.delay_ms = prev.delay_ms;
+            next.tone = prev.tone;
+            replacements.push(Rc::new(next));
+        }
+    }
+    out
+}
+
+// Puts re-converted entries in place of the ones they came from, in the gallery
+// as it is now. Entries removed since `snapshot` was taken stay removed, entries
+// edited since then keep their edit, and new uploads are left alone
+fn merge_reconverted(
+    current: &[Rc<ImageResult>],
+    snapshot: &[Rc<ImageResult>],
+    mut replaced: HashMap<u64, Vec<Rc<ImageResult>>>,
+) -> Vec<Rc<ImageResult>> {
+    current
+        .iter()
+        .flat_map(|img| {
+            let unchanged = snapshot.iter().any(|prev| Rc::ptr_eq(prev, img));
+            match replaced.remove(&img.id) {
+                Some(next) if unchanged => next,
+                _ => vec![img.clone()],
+            }
+        })
+        .collect()
+}
+
+// Converts a gallery entry again with a new orientation and tone, keeping its
+// id and delay. Split images are converted as a whole, the entry keeps its tile
+fn reconvert_image(
+    conv: &DalImageConverter,
+    settings: &ConvSettings,
+    img: &ImageResult,
+    transform: Transform,
+    tone: Option<Tone>,
+) -> Option<Rc<ImageResult>> {
+    let settings = with_tone(settings, tone);
+    let results = convert_image(conv, &settings, img.source.clone(), img.name.clone(), transform);
+    let next = results.iter().find(|r| r.tile == img.tile).or(results.first())?;
+    let mut next = (**next).clone();
+    next.id = img.id;
+    next.delay_ms = img.delay_ms;
+    next.source_hash = img.source_hash;
+    next.tone = tone;
+    Some(Rc::new(next))
+}
+
+// Rebuilds an image from an exported index CSV, using the current palette
+fn import_csv(conv: &DalImageConverter, settings: &ConvSettings, text: &str, name: String) -> anyhow::Result<Rc<ImageResult>> {
+    let (indices, (w, h)) = export::grid::parse_csv(text)?;
+    let palette = conv.palette();
+    if let Some(i) = indices.iter().flatten().find(|&&i| i >= palette.len()) {
+        anyhow::bail!("Index {} is outside the palette of {} colors", i, palette.len());
+    }
+
+    let color = |x: u32, y: u32| indices[(y * w + x) as usize].map(|i| palette[i]);
+    let img = if indices.contains(&None) {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, y| match color(x, y) {
+            Some([r, g, b]) => Rgba([r, g, b, 255]),
+            None => Rgba([0, 0, 0, 0]),
+        }))
+    } else {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| Rgb(color(x, y).unwrap_or_default())))
+    };
+    let source = Rc::new(img.clone());
+    Ok(Rc::new(ImageResult::new(img, name, conv, source, Transform::default(), settings, None)))
 }
 
 #[component]
-fn file_picker(mut images: Signal<Vec<Rc<ImageResult>>>) -> Element {
-    let mut auto_rotate = use_signal(|| true);
-    let conv = use_signal(|| DalImageConverter::default());
+fn file_picker(
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    format: Signal<OutputFormat>,
+    mut history: Signal<History>,
+) -> Element {
+    let mut sheet_columns = use_signal(|| 4u32);
+    // Animated upload whose frames still need to be picked
+    let pending = use_signal(|| None::<Animation>);
+    let pending_video = use_signal(|| None::<Video>);
+    let jobs = use_signal(Vec::new);
+    let mut allow_duplicates = use_signal(|| false);
+    // Set while Re-convert All runs, so runs can't overlap
+    let mut reconverting = use_signal(|| false);
+    let uploader = Uploader {
+        images,
+        conv,
+        settings,
+        history,
+        jobs,
+        allow_duplicates,
+        pending,
+        pending_video,
+    };
+
     rsx! {
         form {
             div {
-                class: "form-check form-switch mb-3",
-                label {
-                    class: "form-check-label",
-                    "Auto Rotate"
+                class: "mb-3",
+                input {
+                    r#type: "file",
+                    class: "form-control",
+                    accept: UPLOAD_ACCEPT,
+                    multiple: true,
+                    onchange: move |evt| async move {
+                        if let Some(file_engine) = evt.files() {
+                            uploader.upload(UploadSource::Browser(file_engine)).await;
+                        }
+                    }
+                }
+                div {
+                    class: "border border-2 rounded text-center text-body-secondary p-3 mt-2",
+                    style: "border-style: dashed !important",
+                    prevent_default: "ondragover ondrop",
+                    ondragover: move |_| {},
+                    ondrop: move |evt| async move {
+                        if let Some(file_engine) = evt.files() {
+                            uploader.upload(UploadSource::Browser(file_engine)).await;
+                        }
+                    },
+                    {tr("Drop files here")}
+                }
+                if cfg!(feature = "desktop") {
+                    button {
+                        class: "btn btn-outline-primary btn-sm mt-2",
+                        r#type: "button",
+                        onclick: move |_| async move {
+                            #[cfg(feature = "desktop")]
+                            uploader.pick_files().await;
+                        },
+                        {tr("Open Files…")}
+                    }
                 }
+            }
+            div {
+                class: "form-check mb-3",
                 input {
                     class: "form-check-input",
-                    role: "switch",
                     r#type: "checkbox",
-                    checked: auto_rotate,
-                    onchange: move |evt| {
-                        auto_rotate.set(evt.checked());
-                    }
+                    checked: *allow_duplicates.read(),
+                    onchange: move |evt| allow_duplicates.set(evt.checked()),
                 }
+                label { class: "form-check-label", {tr("Allow Duplicate Uploads")} }
             }
+            queue_panel { jobs }
+            frame_picker { pending, images, conv, settings, history }
+            video_picker { pending: pending_video, images, conv, settings, history }
             div {
                 class: "mb-3",
                 input {
-                    r#type: "file",
+                    class: "button",
+                    r#type: "button",
+                    value: tr("Clear"),
+                    onclick: move |_| {
+                        history.write().record_gallery(&images.read());
+                        images.set(vec![]);
+                    }
+                }
+                input {
+                    class: "button ms-2",
+                    r#type: "button",
+                    value: tr("Re-convert All"),
+                    disabled: images.is_empty() || *reconverting.read(),
+                    onclick: move |_| async move {
+                        reconverting.set(true);
+                        // Snapshots, so no signal stays borrowed while the conversion yields
+                        let (snapshot_conv, snapshot_settings) = (conv.read().clone(), settings.read().clone());
+                        let gallery = images.read().clone();
+                        let results = reconvert_all(&snapshot_conv, &snapshot_settings, &gallery).await;
+                        let next = merge_reconverted(&images.read(), &gallery, results);
+                        history.write().record_gallery(&images.read());
+                        images.set(next);
+                        reconverting.set(false);
+                    }
+                }
+                input {
+                    class: "button ms-2",
+                    r#type: "button",
+                    value: tr("Download All"),
+                    disabled: images.is_empty(),
+                    onclick: move |_| {
+                        if let Err(e) = download_all(&images.read(), *format.read()) {
+                            toast::error(tr_with("Failed to create ZIP: {}", &[&e]));
+                        }
+                    }
+                }
+                if cfg!(feature = "desktop") {
+                    input {
+                        class: "button ms-2",
+                        r#type: "button",
+                        value: tr("Save All to Folder…"),
+                        disabled: images.is_empty(),
+                        onclick: move |_| {
+                            #[cfg(feature = "desktop")]
+                            save_all_to_folder(&images.read(), *format.read());
+                        }
+                    }
+                }
+            }
+            div {
+                class: "input-group mb-3",
+                span { class: "input-group-text", {tr("Spritesheet Columns")} }
+                input {
+                    r#type: "number",
                     class: "form-control",
-                    accept: ".png,.jpg,.jpeg,.webp",
-                    multiple: true,
+                    min: "1",
+                    value: "{sheet_columns}",
                     onchange: move |evt| {
-                        async move {
-                            if let Some(file_engine) = &evt.files() {
-                                let files = file_engine.files();
-                                for file_name in files {
-                                    // Read the data
-                                    let Some(data) = file_engine.read_file(&file_name).await else {
-                                        dioxus_logger::tracing::error!("Failed to read file: {}", file_name);
-                                        continue;
-                                    };
-
-                                    // Convert the data
-                                    match convert(&conv.read(), *auto_rotate.read(), &data, file_name.clone()) {
-                                        Ok(img) => {
-                                            dioxus_logger::tracing::info!("Image loaded: {} {}", img.img.height(), img.img.width());
-                                            images.push(img);
-                                        }
-                                        Err(e) => {
-                                            dioxus_logger::tracing::error!("Failed to load image: {}", e);
-                                        }
-                                    }
-                                }
-                            }
+                        if let Ok(n) = evt.value().parse::<u32>() {
+                            sheet_columns.set(n.max(1));
                         }
                     }
                 }
+                button {
+                    class: "btn btn-outline-secondary",
+                    r#type: "button",
+                    disabled: images.is_empty(),
+                    onclick: move |_| {
+                        if let Err(e) = download_spritesheet(&images.read(), *sheet_columns.read()) {
+                            toast::error(tr_with("Failed to create spritesheet: {}", &[&e]));
+                        }
+                    },
+                    {tr("Download Spritesheet")}
+                }
             }
             div {
                 class: "mb-3",
                 input {
                     class: "button",
                     r#type: "button",
-                    value: "Clear",
+                    value: tr("Download Animation"),
+                    disabled: images.is_empty(),
                     onclick: move |_| {
-                        images.set(vec![]);
+                        if let Err(e) = download_animation(&images.read()) {
+                            toast::error(tr_with("Failed to create animation: {}", &[&e]));
+                        }
                     }
                 }
             }
@@ -127,42 +825,74 @@ fn file_picker(mut images: Signal<Vec<Rc<ImageResult>>>) -> Element {
 
 #[component]
 fn Home() -> Element {
-    let images = use_signal(|| vec![]);
+    let AppState {
+        images,
+        conv,
+        settings,
+        format,
+        history,
+        selected,
+        checked,
+        ..
+    } = use_context();
+    let dragged = use_signal(|| None);
 
     rsx! {
         div {
-            h1 { "Dale & Dawson Image Converter" }
-            file_picker { images }
+            div {
+                class: "d-flex align-items-center mb-3",
+                h1 { class: "me-auto", {tr("Dale & Dawson Image Converter")} }
+                Link { class: "btn btn-outline-secondary me-2", to: Route::Compare {}, {tr("Compare")} }
+                Link { class: "btn btn-outline-primary", to: Route::Settings {}, {tr("Settings")} }
+            }
+            file_picker { images, conv, settings, format, history }
+            batch_bar { images, checked, conv, settings, format, history }
 
             div {
                 class: "row row-cols-1 row-cols-md-3 g-4",
-                for img in images.iter() {
-
-                    div {
-                        class: "col",
-                        div {
-                            class: "card",
-                            img {
-                                class: "card-img-top",
-                                r#src: "{img.base64}",
-                                r#alt: "{img.name}",
-                            }
-                            p {
-                                class: "card-text",
-                                "{img.name}"
-                            }
-                            a {
-                                href: "{img.base64}",
-                                download: "image.png", // Specify the default filename
-                                button {
-                                    class: "btn btn-primary",
-                                    "Download"
-                                }
-                            }
-                        }
-                    }
+                for index in 0..images.len() {
+                    image_card { key: "{images.read()[index].id}", images, index, conv, settings, format, history, dragged, selected, checked }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tiles of a wall of `cols` x `rows` boards, row by row
+    fn layout((cols, rows): (u32, u32)) -> Vec<Option<(u32, u32)>> {
+        (0..rows).flat_map(|r| (0..cols).map(move |c| Some((r, c)))).collect()
+    }
+
+    // What every entry of a split upload is replaced with when its layout changes
+    fn relayout(from: (u32, u32), to: (u32, u32)) -> Vec<Vec<usize>> {
+        let new_tiles = layout(to);
+        let mut emitted = false;
+        layout(from)
+            .into_iter()
+            .map(|tile| replacement_tiles(from == to, tile, &new_tiles, &mut emitted))
+            .collect()
+    }
+
+    #[test]
+    fn relayout_keeps_tiles_in_place() {
+        assert_eq!(relayout((2, 2), (2, 2)), vec![vec![0], vec![1], vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn relayout_2x2_to_3x3_emits_every_new_tile_once() {
+        let replaced = relayout((2, 2), (3, 3));
+        assert_eq!(replaced[0], (0..9).collect::<Vec<_>>());
+        assert!(replaced[1..].iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn relayout_3x3_to_2x2_emits_every_new_tile_once() {
+        let replaced = relayout((3, 3), (2, 2));
+        assert_eq!(replaced[0], (0..4).collect::<Vec<_>>());
+        assert!(replaced[1..].iter().all(Vec::is_empty));
+    }
+}
@@ -0,0 +1,443 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use dioxus::prelude::*;
+use image::DynamicImage;
+
+use crate::{
+    convert_image, download,
+    export::OutputFormat,
+    frame_picker::Animation,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{self, ConvSettings, DalImageConverter, Transform},
+    toast, ImageResult,
+};
+
+// Loads the video at the received object URL into a detached video element.
+// Without a timestamp it replies with the duration, otherwise it seeks there
+// and replies with the frame drawn onto a canvas as a PNG data URL.
+const VIDEO_BRIDGE: &str = r#"
+const [url, time] = await dioxus.recv();
+const video = document.createElement("video");
+video.muted = true;
+video.preload = "auto";
+video.src = url;
+try {
+    await new Promise((resolve, reject) => {
+        video.onloadeddata = resolve;
+        video.onerror = () => reject("Unsupported video");
+    });
+    if (time === null) {
+        dioxus.send({ duration: video.duration });
+    } else {
+        await new Promise((resolve, reject) => {
+            video.onseeked = resolve;
+            video.onerror = () => reject("Seeking failed");
+            video.currentTime = Math.min(time, video.duration);
+        });
+        const canvas = document.createElement("canvas");
+        canvas.width = video.videoWidth;
+        canvas.height = video.videoHeight;
+        canvas.getContext("2d").drawImage(video, 0, 0);
+        dioxus.send({ frame: canvas.toDataURL("image/png") });
+    }
+} catch (e) {
+    dioxus.send({ error: String(e) });
+}
+video.removeAttribute("src");
+video.load();
+"#;
+
+// Lets the browser decode an image format the image crate does not support
+// and replies with its pixels as a PNG data URL
+const IMAGE_BRIDGE: &str = r#"
+const [url] = await dioxus.recv();
+try {
+    const blob = await (await fetch(url)).blob();
+    const bitmap = await createImageBitmap(blob);
+    const canvas = document.createElement("canvas");
+    canvas.width = bitmap.width;
+    canvas.height = bitmap.height;
+    canvas.getContext("2d").drawImage(bitmap, 0, 0);
+    bitmap.close();
+    dioxus.send({ frame: canvas.toDataURL("image/png") });
+} catch (e) {
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+// Source of the decoding worker. It decodes an image off the main thread and
+// scales it down so its longer side is at most `maxSide`, replying with the
+// raw RGBA pixels as base64.
+const DECODE_WORKER: &str = r#"
+onmessage = async (e) => {
+    const { id, url, maxSide } = e.data;
+    try {
+        const blob = await (await fetch(url)).blob();
+        const bitmap = await createImageBitmap(blob);
+        const scale = Math.min(1, maxSide / Math.max(bitmap.width, bitmap.height));
+        const width = Math.max(1, Math.round(bitmap.width * scale));
+        const height = Math.max(1, Math.round(bitmap.height * scale));
+        const canvas = new OffscreenCanvas(width, height);
+        const ctx = canvas.getContext("2d");
+        ctx.imageSmoothingQuality = "high";
+        ctx.drawImage(bitmap, 0, 0, width, height);
+        bitmap.close();
+        const bytes = new Uint8Array(ctx.getImageData(0, 0, width, height).data.buffer);
+        let binary = "";
+        for (let i = 0; i < bytes.length; i += 0x8000) {
+            binary += String.fromCharCode.apply(null, bytes.subarray(i, i + 0x8000));
+        }
+        postMessage({ id, width, height, rgba: btoa(binary) });
+    } catch (err) {
+        postMessage({ id, error: String(err) });
+    }
+};
+"#;
+
+// Starts the decoding worker on first use and relays one request to it
+const WORKER_BRIDGE: &str = r#"
+const [source, url, maxSide] = await dioxus.recv();
+if (typeof OffscreenCanvas === "undefined") {
+    dioxus.send({ error: "OffscreenCanvas is not supported" });
+} else {
+    if (!window.daleDecoder) {
+        const script = URL.createObjectURL(new Blob([source], { type: "text/javascript" }));
+        window.daleDecoder = { worker: new Worker(script), next: 0 };
+    }
+    const decoder = window.daleDecoder;
+    const id = decoder.next++;
+    const reply = await new Promise((resolve) => {
+        const listener = (e) => {
+            if (e.data.id === id) {
+                decoder.worker.removeEventListener("message", listener);
+                resolve(e.data);
+            }
+        };
+        decoder.worker.addEventListener("message", listener);
+        decoder.worker.postMessage({ id, url, maxSide });
+    });
+    dioxus.send(reply);
+}
+"#;
+
+/// Smallest longer side uploads are scaled down to before they are kept as a source
+const MIN_WORKING_SIZE: u32 = 1024;
+
+/// Still image formats the decoding worker handles, with their mime type.
+/// Formats that may be animated stay with the image crate
+const WORKER_FORMATS: [(&str, &str); 4] = [
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("bmp", "image/bmp"),
+];
+
+/// Image formats only decoded by the browser, with their mime type. AVIF
+/// decoding in the image crate needs the native dav1d library, which does
+/// not build for wasm.
+pub const BROWSER_IMAGE_FORMATS: [(&str, &str); 4] = [
+    ("heic", "image/heic"),
+    ("heif", "image/heif"),
+    ("avif", "image/avif"),
+    ("jxl", "image/jxl"),
+];
+
+/// Video formats the browser can usually decode
+pub const VIDEO_EXTENSIONS: [&str; 2] = ["mp4", "webm"];
+
+/// An uploaded video waiting for the user to pick a timestamp
+pub struct Video {
+    pub name: String,
+    /// Object URL of the uploaded bytes, revoked once the video is dismissed
+    pub url: String,
+    /// Length in seconds
+    pub duration: f64,
+    /// Hash of the uploaded file, so uploading it again is detected
+    pub source_hash: Option<u64>,
+}
+
+impl Drop for Video {
+    fn drop(&mut self) {
+        download::revoke_object_url(&self.url);
+    }
+}
+
+pub fn is_video(file_name: &str) -> bool {
+    file_name
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| VIDEO_EXTENSIONS.iter().any(|v| ext.eq_ignore_ascii_case(v)))
+}
+
+/// Runs a script that receives `args` through `dioxus.recv()` and replies
+/// once, failing if the reply carries an `error`
+pub(crate) async fn run_bridge(script: &str, args: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let mut bridge = eval(script);
+    bridge
+        .send(args)
+        .map_err(|e| anyhow!("Failed to reach the browser bridge: {e:?}"))?;
+    let reply = bridge
+        .recv()
+        .await
+        .map_err(|e| anyhow!("No reply from the browser bridge: {e:?}"))?;
+    if let Some(e) = reply.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!("{e}");
+    }
+    Ok(reply)
+}
+
+/// Hands the uploaded bytes to the browser and reads back the video length
+pub async fn load_video(name: String, data: &[u8]) -> anyhow::Result<Video> {
+    let ext = name.rsplit_once('.').map_or("mp4", |(_, ext)| ext).to_ascii_lowercase();
+    let url = download::object_url(&format!("video/{ext}"), data)
+        .map_err(|e| anyhow!("Failed to create object URL: {e}"))?;
+    // Wrap first, so the URL is revoked on failure as well
+    let mut video = Video {
+        name,
+        url,
+        duration: 0.0,
+        source_hash: None,
+    };
+    let reply = run_bridge(VIDEO_BRIDGE, serde_json::json!([video.url, null])).await?;
+    video.duration = reply
+        .get("duration")
+        .and_then(|d| d.as_f64())
+        .filter(|d| d.is_finite())
+        .ok_or_else(|| anyhow!("Video has no known duration"))?;
+    Ok(video)
+}
+
+/// Decodes the frame shown at `time` seconds
+pub async fn grab_frame(video: &str, time: f64) -> anyhow::Result<DynamicImage> {
+    let reply = run_bridge(VIDEO_BRIDGE, serde_json::json!([video, time])).await?;
+    decode_frame(&reply)
+}
+
+// Reads the PNG data URL a bridge replied with
+fn decode_frame(reply: &serde_json::Value) -> anyhow::Result<DynamicImage> {
+    let frame = reply
+        .get("frame")
+        .and_then(|f| f.as_str())
+        .and_then(|f| f.strip_prefix("data:image/png;base64,"))
+        .ok_or_else(|| anyhow!("Browser bridge returned no frame"))?;
+    img_conv::load_image(&BASE64_STANDARD.decode(frame)?)
+}
+
+/// Mime type of an image the browser has to decode, judged by its file name
+pub fn browser_image_mime(file_name: &str) -> Option<&'static str> {
+    let (_, ext) = file_name.rsplit_once('.')?;
+    BROWSER_IMAGE_FORMATS
+        .iter()
+        .find(|(e, _)| ext.eq_ignore_ascii_case(e))
+        .map(|&(_, mime)| mime)
+}
+
+/// Longest side uploads are kept at, a few times the largest output so a later
+/// resize still has detail to work with
+pub fn working_size(conv: &DalImageConverter, settings: &ConvSettings) -> u32 {
+    let (w, h) = conv.dim();
+    let (cols, rows) = settings.tiles;
+    (w * cols.max(1)).max(h * rows.max(1)).saturating_mul(4).max(MIN_WORKING_SIZE)
+}
+
+/// FNV-1a hash of an uploaded file, stable across sessions
+pub fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// A decoded upload, ready to be converted or handed to a picker
+pub enum Upload {
+    Images(Vec<DynamicImage>),
+    Animation(Animation),
+    Video(Video),
+    /// Text of an exported index CSV
+    Csv(String),
+}
+
+/// Decodes an uploaded file according to its name and content. Large still
+/// images are decoded by a worker and scaled down to `max_side`, so a huge
+/// photo doesn't freeze the page
+pub async fn decode_upload(file_name: &str, data: Vec<u8>, max_side: u32) -> anyhow::Result<Upload> {
+    // Videos are decoded by the browser, the user picks a timestamp
+    if is_video(file_name) {
+        return Ok(Upload::Video(load_video(file_name.to_string(), &data).await?));
+    }
+    // Index CSVs skip the conversion, their pixels are already palette colors
+    if file_name.to_ascii_lowercase().ends_with(".csv") {
+        return Ok(Upload::Csv(String::from_utf8(data)?));
+    }
+
+    if let Some(mime) = worker_mime(file_name) {
+        match decode_in_worker(mime, &data, max_side).await {
+            Ok(img) => return Ok(Upload::Images(vec![img])),
+            // The image crate can still decode it, just on the main thread
+            Err(e) => dioxus_logger::tracing::warn!("Decoding worker failed: {}", e),
+        }
+    }
+
+    // Formats the image crate lacks are decoded by the browser
+    let frames = match browser_image_mime(file_name) {
+        Some(mime) => vec![(decode_in_browser(mime, &data).await?, 0)],
+        None => img_conv::load_frames(&data)?,
+    };
+    // Animations wait for a frame to be picked
+    if frames.len() > 1 {
+        let frames = frames.into_iter().map(|(img, delay)| (Rc::new(img), delay)).collect();
+        return Ok(Upload::Animation(Animation {
+            name: file_name.to_string(),
+            frames,
+            source_hash: None,
+        }));
+    }
+    Ok(Upload::Images(frames.into_iter().map(|(img, _)| img).collect()))
+}
+
+/// Decodes an image through the browser's own codecs
+pub async fn decode_in_browser(mime: &str, data: &[u8]) -> anyhow::Result<DynamicImage> {
+    let url = download::object_url(mime, data).map_err(|e| anyhow!("Failed to create object URL: {e}"))?;
+    let reply = run_bridge(IMAGE_BRIDGE, serde_json::json!([url])).await;
+    download::revoke_object_url(&url);
+    decode_frame(&reply?)
+}
+
+fn worker_mime(file_name: &str) -> Option<&'static str> {
+    let (_, ext) = file_name.rsplit_once('.')?;
+    WORKER_FORMATS
+        .iter()
+        .find(|(e, _)| ext.eq_ignore_ascii_case(e))
+        .map(|&(_, mime)| mime)
+}
+
+/// Decodes a still image in the decoding worker, scaled down to `max_side`.
+/// Fully opaque results come back as RGB, like the image crate decodes them
+pub async fn decode_in_worker(mime: &str, data: &[u8], max_side: u32) -> anyhow::Result<DynamicImage> {
+    let url = download::object_url(mime, data).map_err(|e| anyhow!("Failed to create object URL: {e}"))?;
+    let reply = run_bridge(WORKER_BRIDGE, serde_json::json!([DECODE_WORKER, url, max_side])).await;
+    download::revoke_object_url(&url);
+    let reply = reply?;
+
+    let size = |key: &str| reply.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+    let (Some(width), Some(height)) = (size("width"), size("height")) else {
+        anyhow::bail!("Decoding worker returned no size");
+    };
+    let rgba = reply
+        .get("rgba")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Decoding worker returned no pixels"))?;
+    let img = image::RgbaImage::from_raw(width, height, BASE64_STANDARD.decode(rgba)?)
+        .ok_or_else(|| anyhow!("Decoding worker returned a truncated image"))?;
+    if img.pixels().all(|px| px.0[3] == 255) {
+        return Ok(DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(img).to_rgb8()));
+    }
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+fn dismiss(mut pending: Signal<Option<Video>>, mut time: Signal<f64>, mut preview: Signal<Option<String>>) {
+    pending.set(None);
+    time.set(0.0);
+    preview.set(None);
+}
+
+#[component]
+pub fn video_picker(
+    pending: Signal<Option<Video>>,
+    mut images: Signal<Vec<Rc<ImageResult>>>,
+    conv: Signal<DalImageConverter>,
+    settings: Signal<ConvSettings>,
+    mut history: Signal<History>,
+) -> Element {
+    let mut time = use_signal(|| 0.0f64);
+    let mut preview = use_signal(|| None::<String>);
+
+    let (name, url, duration, source_hash) = match pending.read().as_ref() {
+        Some(video) => (video.name.clone(), video.url.clone(), video.duration, video.source_hash),
+        None => return None,
+    };
+    let stem = name.rsplit_once('.').map_or(name.clone(), |(stem, _)| stem.to_string());
+    let current = *time.read();
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h5 { class: "card-title", "{name}" }
+                if let Some(src) = preview.read().as_ref() {
+                    img {
+                        class: "img-fluid mb-2",
+                        style: "max-height: 240px;",
+                        src: "{src}"
+                    }
+                }
+                label {
+                    class: "form-label d-block",
+                    {tr_with("Timestamp {} s of {} s", &[&format!("{current:.1}"), &format!("{duration:.1}")])}
+                }
+                input {
+                    r#type: "range",
+                    class: "form-range mb-2",
+                    min: "0",
+                    max: "{duration}",
+                    step: "0.1",
+                    value: "{current}",
+                    onchange: {
+                        let url = url.clone();
+                        move |evt: Event<FormData>| {
+                            let url = url.clone();
+                            async move {
+                                let Ok(t) = evt.value().parse::<f64>() else {
+                                    return;
+                                };
+                                time.set(t);
+                                match grab_frame(&url, t).await {
+                                    Ok(frame) => {
+                                        let png = OutputFormat::Png.encode(&frame).unwrap_or_default();
+                                        preview.set(Some(download::data_url("image/png", &png)));
+                                    }
+                                    Err(e) => toast::error(tr_with("Failed to grab frame: {}", &[&e])),
+                                }
+                            }
+                        }
+                    }
+                }
+                button {
+                    class: "btn btn-primary",
+                    r#type: "button",
+                    onclick: {
+                        let (url, stem) = (url.clone(), stem.clone());
+                        move |_| {
+                            let url = url.clone();
+                            let stem = stem.clone();
+                            async move {
+                                let t = *time.read();
+                                match grab_frame(&url, t).await {
+                                    Ok(frame) => {
+                                        let name = format!("{stem}_{t:.1}s.png");
+                                        let results = convert_image(&conv.read(), &settings.read(), Rc::new(frame), name, Transform::default());
+                                        history.write().record_gallery(&images.read());
+                                        images.write().extend(results.into_iter().map(|img| {
+                                            let mut img = (*img).clone();
+                                            img.source_hash = source_hash;
+                                            Rc::new(img)
+                                        }));
+                                        dismiss(pending, time, preview);
+                                    }
+                                    Err(e) => toast::error(tr_with("Failed to grab frame: {}", &[&e])),
+                                }
+                            }
+                        }
+                    },
+                    {tr("Convert Frame")}
+                }
+                button {
+                    class: "btn btn-outline-secondary ms-2",
+                    r#type: "button",
+                    onclick: move |_| dismiss(pending, time, preview),
+                    {tr("Cancel")}
+                }
+            }
+        }
+    }
+}
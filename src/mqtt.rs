@@ -0,0 +1,301 @@
+use std::{collections::HashMap, rc::Rc};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    export::OutputFormat,
+    i18n::{tr, tr_with},
+    media_input::run_bridge,
+    toast, ImageResult,
+};
+
+// Seconds the broker waits for traffic before dropping the client, far more
+// than one publish takes
+const KEEP_ALIVE: u16 = 60;
+
+// Connects over a WebSocket speaking the `mqtt` subprotocol, waits for the
+// CONNACK, then sends the prepared PUBLISH and DISCONNECT packets
+const MQTT_BRIDGE: &str = r#"
+const [url, connect, publish] = await dioxus.recv();
+const decode = (s) => Uint8Array.from(atob(s), (c) => c.charCodeAt(0));
+try {
+    const ws = new WebSocket(url, "mqtt");
+    ws.binaryType = "arraybuffer";
+    await new Promise((resolve, reject) => {
+        ws.onopen = resolve;
+        ws.onerror = () => reject(`Failed to connect to ${url}`);
+    });
+    const connack = new Promise((resolve, reject) => {
+        ws.onmessage = (e) => resolve(new Uint8Array(e.data));
+        ws.onclose = () => reject("The broker closed the connection");
+    });
+    ws.send(decode(connect));
+    const ack = await connack;
+    if (ack[0] !== 0x20 || ack[3] !== 0) {
+        ws.close();
+        throw `The broker refused the connection (code ${ack[3]})`;
+    }
+    ws.send(decode(publish));
+    ws.send(new Uint8Array([0xe0, 0]));
+    ws.close();
+    dioxus.send({ done: true });
+} catch (e) {
+    dioxus.send({ error: String(e) });
+}
+"#;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MqttPayload {
+    /// One palette index per byte, row-major
+    #[default]
+    Indices,
+    /// The PNG as base64 text
+    PngBase64,
+}
+
+impl MqttPayload {
+    pub const ALL: [MqttPayload; 2] = [MqttPayload::Indices, MqttPayload::PngBase64];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MqttPayload::Indices => "Palette Indices",
+            MqttPayload::PngBase64 => "PNG (base64)",
+        }
+    }
+}
+
+/// Where finished conversions are published, remembered across visits
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    /// WebSocket URL of the broker, e.g. `ws://homeassistant.local:1884/mqtt`
+    pub url: String,
+    pub topic: String,
+    pub username: String,
+    /// Kept for this visit only, localStorage would hold it in plain text
+    #[serde(skip)]
+    pub password: String,
+    pub payload: MqttPayload,
+    /// Lets the broker hand the last frame to boards that subscribe later
+    pub retain: bool,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            topic: "dale-img/frame".into(),
+            username: String::new(),
+            password: String::new(),
+            payload: MqttPayload::default(),
+            retain: true,
+        }
+    }
+}
+
+// MQTT's variable length encoding, 7 bits per byte
+fn remaining_length(mut len: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn string(s: &str, out: &mut Vec<u8>) {
+    out.extend((s.len() as u16).to_be_bytes());
+    out.extend(s.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    remaining_length(body.len(), &mut out);
+    out.extend(body);
+    out
+}
+
+/// MQTT 3.1.1 CONNECT with a clean session and optional credentials
+fn connect_packet(client_id: &str, username: &str, password: &str) -> Vec<u8> {
+    let mut flags = 0x02;
+    if !username.is_empty() {
+        flags |= 0x80;
+        if !password.is_empty() {
+            flags |= 0x40;
+        }
+    }
+    let mut body = vec![];
+    string("MQTT", &mut body);
+    body.push(4);
+    body.push(flags);
+    body.extend(KEEP_ALIVE.to_be_bytes());
+    string(client_id, &mut body);
+    if flags & 0x80 != 0 {
+        string(username, &mut body);
+    }
+    if flags & 0x40 != 0 {
+        string(password, &mut body);
+    }
+    packet(0x10, &body)
+}
+
+/// QoS 0 PUBLISH
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = vec![];
+    string(topic, &mut body);
+    body.extend(payload);
+    packet(0x30 | u8::from(retain), &body)
+}
+
+/// Publishes one image with the given settings
+pub async fn publish(mqtt: &MqttSettings, img: &ImageResult) -> anyhow::Result<()> {
+    anyhow::ensure!(!mqtt.url.is_empty(), "No broker URL set");
+    anyhow::ensure!(!mqtt.topic.is_empty(), "No topic set");
+    let payload = match mqtt.payload {
+        MqttPayload::Indices => img.indices.iter().map(|&i| i as u8).collect(),
+        MqttPayload::PngBase64 => BASE64_STANDARD.encode(img.encode(OutputFormat::Png)?).into_bytes(),
+    };
+    let connect = connect_packet(&format!("dale-img-{}", img.id), &mqtt.username, &mqtt.password);
+    let publish = publish_packet(&mqtt.topic, &payload, mqtt.retain);
+    let args = serde_json::json!([
+        mqtt.url,
+        BASE64_STANDARD.encode(connect),
+        BASE64_STANDARD.encode(publish),
+    ]);
+    run_bridge(MQTT_BRIDGE, args).await?;
+    Ok(())
+}
+
+/// Publishes every image whose conversion changed since it was last sent.
+/// Images already in the gallery when publishing is turned on are left alone
+#[component]
+pub fn mqtt_publisher(images: Signal<Vec<Rc<ImageResult>>>, mqtt: Signal<MqttSettings>) -> Element {
+    // Last published version of every gallery entry, `None` while disabled
+    let mut published = use_signal(|| None::<HashMap<u64, Rc<ImageResult>>>);
+
+    use_effect(move || {
+        let images = images.read();
+        let mqtt = mqtt.read().clone();
+        let mut published = published.write();
+        if !mqtt.enabled {
+            *published = None;
+            return;
+        }
+        let Some(published) = published.as_mut() else {
+            *published = Some(images.iter().map(|img| (img.id, img.clone())).collect());
+            return;
+        };
+        let mut pending = vec![];
+        for img in images.iter() {
+            // Renames and other edits keep the indices and aren't sent again
+            let changed = published
+                .insert(img.id, img.clone())
+                .map_or(true, |prev| prev.indices != img.indices);
+            if changed {
+                pending.push(img.clone());
+            }
+        }
+        published.retain(|id, _| images.iter().any(|img| img.id == *id));
+        if pending.is_empty() {
+            return;
+        }
+        spawn(async move {
+            for img in pending {
+                if let Err(e) = publish(&mqtt, &img).await {
+                    toast::error(tr_with("Failed to publish to MQTT: {}", &[&e]));
+                    break;
+                }
+            }
+        });
+    });
+
+    None
+}
+
+/// Broker, topic and payload options for publishing to MQTT
+#[component]
+pub fn mqtt_settings(mqtt: Signal<MqttSettings>) -> Element {
+    let current = mqtt.read().clone();
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                h5 { class: "card-title", "MQTT" }
+                div {
+                    class: "form-check mb-2",
+                    input {
+                        class: "form-check-input",
+                        r#type: "checkbox",
+                        checked: current.enabled,
+                        onchange: move |evt| mqtt.write().enabled = evt.checked(),
+                    }
+                    label { class: "form-check-label", {tr("Publish Finished Conversions")} }
+                }
+                input {
+                    class: "form-control form-control-sm mb-2",
+                    placeholder: "ws://homeassistant.local:1884/mqtt",
+                    title: tr("Broker WebSocket URL"),
+                    value: "{current.url}",
+                    onchange: move |evt| mqtt.write().url = evt.value().trim().to_string(),
+                }
+                div {
+                    class: "input-group input-group-sm mb-2",
+                    span { class: "input-group-text", {tr("Topic")} }
+                    input {
+                        class: "form-control",
+                        value: "{current.topic}",
+                        onchange: move |evt| mqtt.write().topic = evt.value().trim().to_string(),
+                    }
+                }
+                div {
+                    class: "input-group input-group-sm mb-2",
+                    input {
+                        class: "form-control",
+                        placeholder: tr("Username"),
+                        value: "{current.username}",
+                        onchange: move |evt| mqtt.write().username = evt.value(),
+                    }
+                    input {
+                        class: "form-control",
+                        r#type: "password",
+                        placeholder: tr("Password"),
+                        value: "{current.password}",
+                        onchange: move |evt| mqtt.write().password = evt.value(),
+                    }
+                }
+                select {
+                    class: "form-select form-select-sm mb-2",
+                    onchange: move |evt| {
+                        if let Some(payload) = evt.value().parse::<usize>().ok().and_then(|i| MqttPayload::ALL.get(i)) {
+                            mqtt.write().payload = *payload;
+                        }
+                    },
+                    for (i, payload) in MqttPayload::ALL.iter().enumerate() {
+                        option { value: "{i}", selected: *payload == current.payload, {tr(payload.label())} }
+                    }
+                }
+                div {
+                    class: "form-check",
+                    input {
+                        class: "form-check-input",
+                        r#type: "checkbox",
+                        checked: current.retain,
+                        onchange: move |evt| mqtt.write().retain = evt.checked(),
+                    }
+                    label { class: "form-check-label", {tr("Retain Last Frame")} }
+                }
+            }
+        }
+    }
+}
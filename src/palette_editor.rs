@@ -0,0 +1,231 @@
+use dioxus::prelude::*;
+
+use crate::{
+    download,
+    i18n::{tr, tr_with},
+    img_conv::{self, palettes, quantize, DalImageConverter},
+    toast,
+};
+
+fn update_palette(mut conv: Signal<DalImageConverter>, f: impl FnOnce(&mut Vec<[u8; 3]>)) {
+    let mut palette = conv.read().palette().to_vec();
+    f(&mut palette);
+    conv.write().set_palette(&palette);
+}
+
+#[component]
+pub fn palette_editor(conv: Signal<DalImageConverter>) -> Element {
+    let palette = conv.read().palette().to_vec();
+    let enabled = conv.read().enabled().to_vec();
+    let penalties = conv.read().penalties().to_vec();
+    let enabled_count = enabled.iter().filter(|&&e| e).count();
+    let len = palette.len();
+    let mut extract_count = use_signal(|| 18usize);
+
+    rsx! {
+        div {
+            class: "mb-3",
+            label {
+                class: "form-label",
+                {tr("Palette")}
+            }
+            select {
+                class: "form-select mb-2",
+                onchange: move |evt| {
+                    if let Some(preset) = evt.value().parse::<usize>().ok().and_then(|i| palettes::PRESETS.get(i)) {
+                        update_palette(conv, |p| *p = preset.colors.to_vec());
+                    }
+                },
+                option {
+                    value: "",
+                    selected: !palettes::PRESETS.iter().any(|p| p.colors == palette.as_slice()),
+                    {tr("Custom")}
+                }
+                for (i, preset) in palettes::PRESETS.iter().enumerate() {
+                    option {
+                        value: "{i}",
+                        selected: preset.colors == palette.as_slice(),
+                        "{preset.name}"
+                    }
+                }
+            }
+            ul {
+                class: "list-group mb-2",
+                for (i, color) in palette.iter().copied().enumerate() {
+                    li {
+                        class: "list-group-item d-flex align-items-center gap-2",
+                        input {
+                            class: "form-check-input mt-0",
+                            r#type: "checkbox",
+                            title: tr("Use this color"),
+                            checked: enabled[i],
+                            // Keep at least one color to map to
+                            disabled: enabled[i] && enabled_count == 1,
+                            onchange: move |evt| {
+                                let mut enabled = conv.read().enabled().to_vec();
+                                enabled[i] = evt.checked();
+                                conv.write().set_enabled(&enabled);
+                            }
+                        }
+                        input {
+                            class: "form-control form-control-color",
+                            r#type: "color",
+                            value: "{palettes::to_hex(color)}",
+                            onchange: move |evt| {
+                                if let Some(c) = palettes::parse_hex(&evt.value()) {
+                                    update_palette(conv, |p| p[i] = c);
+                                }
+                            }
+                        }
+                        code { "{palettes::to_hex(color)}" }
+                        input {
+                            class: "form-control form-control-sm",
+                            style: "max-width: 5rem",
+                            r#type: "number",
+                            min: "0",
+                            step: "1",
+                            title: tr("Penalty added to the color distance (ΔE), higher values make this color rarer"),
+                            value: "{penalties[i]}",
+                            onchange: move |evt| {
+                                if let Ok(v) = evt.value().parse::<f32>() {
+                                    let mut penalties = conv.read().penalties().to_vec();
+                                    penalties[i] = v.max(0.0);
+                                    conv.write().set_penalties(&penalties);
+                                }
+                            }
+                        }
+                        div {
+                            class: "btn-group btn-group-sm ms-auto",
+                            button {
+                                class: "btn btn-outline-secondary",
+                                r#type: "button",
+                                disabled: i == 0,
+                                onclick: move |_| update_palette(conv, |p| p.swap(i - 1, i)),
+                                "↑"
+                            }
+                            button {
+                                class: "btn btn-outline-secondary",
+                                r#type: "button",
+                                disabled: i + 1 == len,
+                                onclick: move |_| update_palette(conv, |p| p.swap(i, i + 1)),
+                                "↓"
+                            }
+                            button {
+                                class: "btn btn-outline-danger",
+                                r#type: "button",
+                                // The converter needs at least one color to map to
+                                disabled: len == 1,
+                                onclick: move |_| update_palette(conv, |p| {
+                                    p.remove(i);
+                                }),
+                                "✕"
+                            }
+                        }
+                    }
+                }
+            }
+            button {
+                class: "btn btn-outline-primary btn-sm",
+                r#type: "button",
+                onclick: move |_| update_palette(conv, |p| p.push([0, 0, 0])),
+                {tr("Add Color")}
+            }
+            a {
+                class: "btn btn-outline-secondary btn-sm ms-2",
+                href: download::data_url("text/plain", palettes::to_gpl(&palette, "Dale").as_bytes()),
+                download: "palette.gpl",
+                {tr("Export .gpl")}
+            }
+            a {
+                class: "btn btn-outline-secondary btn-sm ms-2",
+                href: download::data_url("application/json", palettes::to_json(&palette).as_bytes()),
+                download: "palette.json",
+                {tr("Export .json")}
+            }
+            div {
+                class: "mt-2",
+                label {
+                    class: "form-label",
+                    {tr("Import Palette")}
+                }
+                input {
+                    r#type: "file",
+                    class: "form-control form-control-sm",
+                    accept: ".gpl,.hex,.txt,.json",
+                    onchange: move |evt| {
+                        async move {
+                            let Some(file_engine) = evt.files() else {
+                                return;
+                            };
+                            for file_name in file_engine.files() {
+                                let Some(text) = file_engine.read_file_to_string(&file_name).await else {
+                                    toast::error(tr_with("Failed to read file: {}", &[&file_name]));
+                                    continue;
+                                };
+                                match palettes::parse_palette(&file_name, &text) {
+                                    Ok(palette) => update_palette(conv, |p| *p = palette),
+                                    Err(e) => {
+                                        toast::error(tr_with("Failed to load palette: {}", &[&e]));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mt-2",
+                label {
+                    class: "form-label",
+                    {tr("Extract From Reference Image")}
+                }
+                div {
+                    class: "input-group input-group-sm",
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        style: "max-width: 6rem",
+                        min: "1",
+                        max: "256",
+                        value: "{extract_count}",
+                        onchange: move |evt| {
+                            if let Ok(n) = evt.value().parse::<usize>() {
+                                extract_count.set(n.clamp(1, 256));
+                            }
+                        }
+                    }
+                    input {
+                        r#type: "file",
+                        class: "form-control",
+                        accept: ".png,.jpg,.jpeg,.webp",
+                        onchange: move |evt| {
+                            async move {
+                                let Some(file_engine) = evt.files() else {
+                                    return;
+                                };
+                                let Some(file_name) = file_engine.files().into_iter().next() else {
+                                    return;
+                                };
+                                let Some(data) = file_engine.read_file(&file_name).await else {
+                                    toast::error(tr_with("Failed to read file: {}", &[&file_name]));
+                                    return;
+                                };
+                                match img_conv::load_image(&data) {
+                                    Ok(img) => {
+                                        let palette = quantize::extract_palette(&img, *extract_count.read());
+                                        if !palette.is_empty() {
+                                            update_palette(conv, |p| *p = palette);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        toast::error(tr_with("Failed to load image: {}", &[&e]));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
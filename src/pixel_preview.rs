@@ -0,0 +1,128 @@
+use dioxus::{html::input_data::MouseButton, prelude::*};
+
+use crate::i18n::tr;
+
+/// Screen pixels per image pixel the preview can be zoomed to
+pub const ZOOM_LEVELS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+const DEFAULT_ZOOM: usize = 2;
+
+/// A nearest-neighbor magnified view of a converted image with optional
+/// pixel grid. Larger zoom levels scroll inside the frame to pan.
+///
+/// `overlay` is an image of the same size drawn translucently on top.
+///
+/// When `editable`, clicking or dragging reports the image pixel under the
+/// cursor to `onpaint`, `onstroke` fires once when a drag begins.
+#[component]
+pub fn pixel_preview(
+    src: String,
+    alt: String,
+    width: u32,
+    height: u32,
+    #[props(default)] editable: bool,
+    overlay: Option<String>,
+    onpaint: Option<EventHandler<(u32, u32)>>,
+    onstroke: Option<EventHandler<()>>,
+) -> Element {
+    let mut zoom = use_signal(|| DEFAULT_ZOOM);
+    let mut grid = use_signal(|| false);
+
+    let level = *zoom.read();
+    let scale = ZOOM_LEVELS[level];
+    let (w, h) = (width * scale, height * scale);
+    // Grid lines only become readable once pixels are a few screen pixels wide
+    let cursor = if editable { "crosshair" } else { "default" };
+    let pixel_at = move |evt: &MouseEvent| {
+        if !editable {
+            return None;
+        }
+        let p = evt.element_coordinates();
+        let (x, y) = (p.x.max(0.0) as u32 / scale, p.y.max(0.0) as u32 / scale);
+        (x < width && y < height).then_some((x, y))
+    };
+
+    let grid_style = if *grid.read() && scale >= 4 {
+        format!(
+            "background-image: linear-gradient(to right, rgba(0,0,0,.35) 1px, transparent 1px), linear-gradient(to bottom, rgba(0,0,0,.35) 1px, transparent 1px); background-size: {scale}px {scale}px;"
+        )
+    } else {
+        String::new()
+    };
+
+    rsx! {
+        div {
+            class: "border bg-light",
+            style: "overflow: auto; max-height: 360px;",
+            div {
+                style: "position: relative; width: {w}px; height: {h}px; cursor: {cursor};",
+                onmousedown: move |evt| {
+                    if let (Some(onpaint), Some(p)) = (onpaint, pixel_at(&evt)) {
+                        if let Some(onstroke) = onstroke {
+                            onstroke.call(());
+                        }
+                        onpaint.call(p);
+                    }
+                },
+                onmousemove: move |evt| {
+                    if !evt.held_buttons().contains(MouseButton::Primary) {
+                        return;
+                    }
+                    if let (Some(onpaint), Some(p)) = (onpaint, pixel_at(&evt)) {
+                        onpaint.call(p);
+                    }
+                },
+                img {
+                    src: "{src}",
+                    alt: "{alt}",
+                    width: "{w}",
+                    height: "{h}",
+                    style: "display: block; image-rendering: pixelated;",
+                    draggable: false,
+                }
+                if let Some(overlay) = overlay {
+                    img {
+                        src: "{overlay}",
+                        width: "{w}",
+                        height: "{h}",
+                        style: "position: absolute; inset: 0; opacity: 0.75; image-rendering: pixelated; pointer-events: none;",
+                        draggable: false,
+                    }
+                }
+                div {
+                    style: "position: absolute; inset: 0; pointer-events: none; {grid_style}",
+                }
+            }
+        }
+        div {
+            class: "d-flex align-items-center gap-2 mx-2 my-2",
+            div {
+                class: "btn-group btn-group-sm",
+                button {
+                    class: "btn btn-outline-secondary",
+                    title: tr("Zoom out"),
+                    disabled: level == 0,
+                    onclick: move |_| zoom.set(level.saturating_sub(1)),
+                    "−"
+                }
+                span { class: "btn btn-outline-secondary disabled", "{scale}×" }
+                button {
+                    class: "btn btn-outline-secondary",
+                    title: tr("Zoom in"),
+                    disabled: level + 1 == ZOOM_LEVELS.len(),
+                    onclick: move |_| zoom.set((level + 1).min(ZOOM_LEVELS.len() - 1)),
+                    "+"
+                }
+            }
+            div {
+                class: "form-check mb-0",
+                input {
+                    class: "form-check-input",
+                    r#type: "checkbox",
+                    checked: *grid.read(),
+                    onchange: move |evt| grid.set(evt.checked()),
+                }
+                label { class: "form-check-label", {tr("Grid")} }
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dioxus::prelude::*;
+
+use crate::{
+    i18n::{tr, tr_with},
+    timer,
+    toast::{self, ToastLevel},
+};
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JobStatus {
+    #[default]
+    Queued,
+    Decoding,
+    Converting,
+    Done,
+    Failed,
+    Canceled,
+    /// Already in the gallery
+    Skipped,
+}
+
+impl JobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "Queued",
+            JobStatus::Decoding => "Decoding",
+            JobStatus::Converting => "Converting",
+            JobStatus::Done => "Done",
+            JobStatus::Failed => "Failed",
+            JobStatus::Canceled => "Canceled",
+            JobStatus::Skipped => "Duplicate",
+        }
+    }
+
+    fn badge(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "badge text-bg-secondary",
+            JobStatus::Decoding | JobStatus::Converting => "badge text-bg-primary",
+            JobStatus::Done => "badge text-bg-success",
+            JobStatus::Failed => "badge text-bg-danger",
+            JobStatus::Canceled | JobStatus::Skipped => "badge text-bg-warning",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Canceled | JobStatus::Skipped)
+    }
+}
+
+/// One uploaded file on its way into the gallery
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub id: u64,
+    pub name: String,
+    pub status: JobStatus,
+    pub error: Option<String>,
+}
+
+/// Adds a queued job per file and returns their ids in the same order
+pub fn enqueue(mut jobs: Signal<Vec<Job>>, files: &[String]) -> Vec<u64> {
+    let new: Vec<Job> = files
+        .iter()
+        .map(|name| Job {
+            id: NEXT_JOB.fetch_add(1, Ordering::Relaxed),
+            name: name.clone(),
+            status: JobStatus::Queued,
+            error: None,
+        })
+        .collect();
+    let ids = new.iter().map(|job| job.id).collect();
+    jobs.write().extend(new);
+    ids
+}
+
+fn status(jobs: Signal<Vec<Job>>, id: u64) -> Option<JobStatus> {
+    jobs.read().iter().find(|job| job.id == id).map(|job| job.status)
+}
+
+/// Moves a job to its next step and gives the renderer a chance to show it
+/// before the possibly long step runs. Returns false if the job was canceled
+/// meanwhile, the caller then drops whatever it decoded so far.
+pub async fn set_status(mut jobs: Signal<Vec<Job>>, id: u64, next: JobStatus) -> bool {
+    if status(jobs, id) == Some(JobStatus::Canceled) {
+        return false;
+    }
+    if let Some(job) = jobs.write().iter_mut().find(|job| job.id == id) {
+        job.status = next;
+    }
+    timer::sleep(0).await;
+    status(jobs, id) != Some(JobStatus::Canceled)
+}
+
+/// Leaves out a file that is already in the gallery
+pub fn skip(mut jobs: Signal<Vec<Job>>, id: u64) {
+    if let Some(job) = jobs.write().iter_mut().find(|job| job.id == id) {
+        toast::notify(ToastLevel::Warning, tr_with("Skipped {}, it is already in the gallery", &[&job.name]));
+        job.status = JobStatus::Skipped;
+    }
+}
+
+/// Cancels every job that has not finished yet
+pub fn cancel_all(mut jobs: Signal<Vec<Job>>) {
+    for job in jobs.write().iter_mut().filter(|job| !job.status.is_finished()) {
+        job.status = JobStatus::Canceled;
+    }
+}
+
+pub fn fail(mut jobs: Signal<Vec<Job>>, id: u64, error: &anyhow::Error) {
+    if let Some(job) = jobs.write().iter_mut().find(|job| job.id == id) {
+        toast::error(tr_with("Failed to load {}: {}", &[&job.name, error]));
+        job.status = JobStatus::Failed;
+        job.error = Some(error.to_string());
+    }
+}
+
+/// Overall progress bar with the status of every file of the current batch
+#[component]
+pub fn queue_panel(mut jobs: Signal<Vec<Job>>) -> Element {
+    let total = jobs.read().len();
+    if total == 0 {
+        return None;
+    }
+    let finished = jobs.read().iter().filter(|job| job.status.is_finished()).count();
+    let failed = jobs.read().iter().filter(|job| job.status == JobStatus::Failed).count();
+    let canceled = jobs.read().iter().filter(|job| job.status == JobStatus::Canceled).count();
+    let percent = finished * 100 / total;
+
+    rsx! {
+        div {
+            class: "card mb-3",
+            div {
+                class: "card-body",
+                div {
+                    class: "d-flex align-items-center mb-2",
+                    span {
+                        class: "me-auto",
+                        {tr_with("{} of {} files processed, {} failed, {} canceled", &[&finished, &total, &failed, &canceled])}
+                    }
+                    button {
+                        class: "btn btn-sm btn-outline-danger me-2",
+                        r#type: "button",
+                        disabled: finished == total,
+                        onclick: move |_| cancel_all(jobs),
+                        {tr("Cancel")}
+                    }
+                    button {
+                        class: "btn btn-sm btn-outline-secondary",
+                        r#type: "button",
+                        disabled: finished == 0,
+                        onclick: move |_| jobs.write().retain(|job| !job.status.is_finished()),
+                        {tr("Clear Finished")}
+                    }
+                }
+                div {
+                    class: "progress mb-2",
+                    div {
+                        class: "progress-bar",
+                        role: "progressbar",
+                        style: "width: {percent}%",
+                    }
+                }
+                ul {
+                    class: "list-unstyled small mb-0",
+                    style: "max-height: 200px; overflow-y: auto;",
+                    for job in jobs.read().iter() {
+                        li {
+                            key: "{job.id}",
+                            span { class: "{job.status.badge()} me-2", "{tr(job.status.label())}" }
+                            "{job.name}"
+                            if let Some(error) = &job.error {
+                                span { class: "text-danger ms-2", "{error}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use anyhow::anyhow;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use dioxus::prelude::*;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    download::{self, ObjectUrl},
+    export::OutputFormat,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{self, quality::Quality, ConvSettings, DalImageConverter, Tone, Transform},
+    media_input::run_bridge,
+    timer, toast, ImageResult,
+};
+
+// Quiet time after the last gallery change before the session is written, so
+// a burst of renames or slider commits is saved once
+const SAVE_DEBOUNCE_MS: u32 = 1000;
+
+// Loads, saves or deletes the single gallery record in IndexedDB
+const SESSION_DB: &str = r#"
+const [op, value] = await dioxus.recv();
+const request = (req) => new Promise((resolve, reject) => {
+    req.onsuccess = () => resolve(req.result);
+    req.onerror = () => reject(req.error);
+});
+try {
+    const open = indexedDB.open("dale-img-convert", 1);
+    open.onupgradeneeded = () => open.result.createObjectStore("session");
+    const db = await request(open);
+    const tx = db.transaction("session", op === "load" ? "readonly" : "readwrite");
+    const store = tx.objectStore("session");
+    let result = null;
+    if (op === "load") {
+        result = (await request(store.get("gallery"))) ?? null;
+    } else if (op === "save") {
+        store.put(value, "gallery");
+    } else {
+        store.delete("gallery");
+    }
+    await new Promise((resolve, reject) => {
+        tx.oncomplete = resolve;
+        tx.onerror = () => reject(tx.error);
+        tx.onabort = () => reject(tx.error);
+    });
+    db.close();
+    dioxus.send({ result });
+} catch (e) {
+    dioxus.send({ error: e && e.name ? `${e.name}: ${e.message}` : String(e) });
+}
+"#;
+
+thread_local! {
+    // Sources are only encoded once, keyed by their allocation
+    static ENCODED_SOURCES: RefCell<HashMap<*const DynamicImage, (std::rc::Weak<DynamicImage>, Rc<String>)>> =
+        RefCell::new(HashMap::new());
+    // Converted pixels change together with their thumbnail, so they are keyed by it
+    static ENCODED_IMAGES: RefCell<HashMap<*const Option<ObjectUrl>, (std::rc::Weak<Option<ObjectUrl>>, Rc<String>)>> =
+        RefCell::new(HashMap::new());
+}
+
+fn encode_image(img: &ImageResult) -> Rc<String> {
+    ENCODED_IMAGES.with_borrow_mut(|cache| {
+        cache.retain(|_, (weak, _)| weak.strong_count() > 0);
+        let (_, encoded) = cache.entry(Rc::as_ptr(&img.thumbnail)).or_insert_with(|| {
+            let png = img.encode(OutputFormat::Png).unwrap_or_default();
+            (Rc::downgrade(&img.thumbnail), Rc::new(download::data_url("image/png", &png)))
+        });
+        encoded.clone()
+    })
+}
+
+fn encode_source(source: &Rc<DynamicImage>) -> Rc<String> {
+    ENCODED_SOURCES.with_borrow_mut(|cache| {
+        cache.retain(|_, (weak, _)| weak.strong_count() > 0);
+        let (_, encoded) = cache.entry(Rc::as_ptr(source)).or_insert_with(|| {
+            let png = OutputFormat::Png.encode(source).unwrap_or_default();
+            (Rc::downgrade(source), Rc::new(download::data_url("image/png", &png)))
+        });
+        encoded.clone()
+    })
+}
+
+fn decode_data_url(url: &str) -> anyhow::Result<DynamicImage> {
+    let data = url
+        .strip_prefix("data:image/png;base64,")
+        .ok_or_else(|| anyhow!("Stored image is not a PNG data URL"))?;
+    img_conv::load_image(&BASE64_STANDARD.decode(data)?)
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredImage {
+    name: String,
+    transform: Transform,
+    settings: ConvSettings,
+    tile: Option<(u32, u32)>,
+    delay_ms: u32,
+    #[serde(default)]
+    source_hash: Option<u64>,
+    #[serde(default)]
+    quality: Option<Quality>,
+    #[serde(default)]
+    tone: Option<Tone>,
+    palette: Vec<[u8; 3]>,
+    /// Converted pixels as PNG data URL, so manual edits survive
+    image: String,
+    /// Index into the shared sources, tiles of one upload share theirs
+    source: usize,
+}
+
+/// The gallery as written to IndexedDB
+#[derive(Default, Serialize, Deserialize)]
+pub struct StoredSession {
+    images: Vec<StoredImage>,
+    sources: Vec<String>,
+}
+
+impl StoredSession {
+    pub fn capture(images: &[Rc<ImageResult>]) -> Self {
+        let mut session = Self::default();
+        let mut source_index: HashMap<*const DynamicImage, usize> = HashMap::new();
+        for img in images {
+            let source = *source_index.entry(Rc::as_ptr(&img.source)).or_insert_with(|| {
+                session.sources.push((*encode_source(&img.source)).clone());
+                session.sources.len() - 1
+            });
+            session.images.push(StoredImage {
+                name: img.name.clone(),
+                transform: img.transform,
+                settings: img.settings.clone(),
+                tile: img.tile,
+                delay_ms: img.delay_ms,
+                source_hash: img.source_hash,
+                quality: img.quality,
+                tone: img.tone,
+                palette: img.palette.clone(),
+                image: (*encode_image(img)).clone(),
+                source,
+            });
+        }
+        session
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    pub fn restore(&self) -> anyhow::Result<Vec<Rc<ImageResult>>> {
+        let sources = self
+            .sources
+            .iter()
+            .map(|s| decode_data_url(s).map(Rc::new))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.images
+            .iter()
+            .map(|stored| {
+                let img = decode_data_url(&stored.image)?;
+                let source = sources
+                    .get(stored.source)
+                    .ok_or_else(|| anyhow!("Stored image refers to a missing source"))?;
+                let conv = DalImageConverter::new(&stored.palette, (img.width(), img.height()));
+                let mut result = ImageResult::new(
+                    img,
+                    stored.name.clone(),
+                    &conv,
+                    source.clone(),
+                    stored.transform,
+                    &stored.settings,
+                    stored.tile,
+                );
+                result.delay_ms = stored.delay_ms;
+                result.source_hash = stored.source_hash;
+                result.quality = stored.quality;
+                result.tone = stored.tone;
+                Ok(Rc::new(result))
+            })
+            .collect()
+    }
+}
+
+pub async fn load() -> anyhow::Result<Option<StoredSession>> {
+    let reply = run_bridge(SESSION_DB, serde_json::json!(["load", null])).await?;
+    match reply.get("result") {
+        Some(serde_json::Value::Null) | None => Ok(None),
+        Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+    }
+}
+
+pub async fn save(session: &StoredSession) -> anyhow::Result<()> {
+    run_bridge(SESSION_DB, serde_json::json!(["save", session])).await?;
+    Ok(())
+}
+
+/// Keeps the gallery in IndexedDB and offers to restore it on the next visit.
+/// Nothing is saved until the offer is answered, so an unanswered prompt
+/// never overwrites the stored session.
+#[component]
+pub fn session_keeper(mut images: Signal<Vec<Rc<ImageResult>>>, mut history: Signal<History>) -> Element {
+    let mut offer = use_signal(|| None::<StoredSession>);
+    let mut ready = use_signal(|| false);
+    let mut quota_exceeded = use_signal(|| false);
+    let mut pending = use_signal(|| None::<Task>);
+
+    use_future(move || async move {
+        match load().await {
+            Ok(Some(session)) if !session.is_empty() => offer.set(Some(session)),
+            Ok(_) => ready.set(true),
+            Err(e) => {
+                dioxus_logger::tracing::error!("Failed to load previous session: {}", e);
+                ready.set(true);
+            }
+        }
+    });
+
+    use_effect(move || {
+        let _ = images.read();
+        if !*ready.read() {
+            return;
+        }
+        // Only the latest gallery is written. A save still waiting is dropped,
+        // and IndexedDB commits one that already started before the newer one
+        if let Some(task) = pending.take() {
+            task.cancel();
+        }
+        let task = spawn(async move {
+            timer::sleep(SAVE_DEBOUNCE_MS).await;
+            let session = StoredSession::capture(&images.peek());
+            match save(&session).await {
+                Ok(()) => quota_exceeded.set(false),
+                Err(e) => {
+                    dioxus_logger::tracing::error!("Failed to save session: {}", e);
+                    if e.to_string().contains("QuotaExceededError") {
+                        quota_exceeded.set(true);
+                    }
+                }
+            }
+            pending.set(None);
+        });
+        pending.set(Some(task));
+    });
+
+    let count = offer.read().as_ref().map(StoredSession::len);
+
+    rsx! {
+        if let Some(count) = count {
+            div {
+                class: "alert alert-info d-flex align-items-center gap-2",
+                span { class: "me-auto", {tr_with("Restore previous session with {} images?", &[&count])} }
+                button {
+                    class: "btn btn-sm btn-primary",
+                    r#type: "button",
+                    onclick: move |_| {
+                        if let Some(session) = offer.take() {
+                            match session.restore() {
+                                Ok(restored) => {
+                                    history.write().record_gallery(&images.read());
+                                    images.write().splice(0..0, restored);
+                                }
+                                Err(e) => toast::error(tr_with("Failed to restore session: {}", &[&e])),
+                            }
+                        }
+                        ready.set(true);
+                    },
+                    {tr("Restore")}
+                }
+                button {
+                    class: "btn btn-sm btn-outline-secondary",
+                    r#type: "button",
+                    onclick: move |_| {
+                        offer.set(None);
+                        ready.set(true);
+                    },
+                    {tr("Discard")}
+                }
+            }
+        }
+        if *quota_exceeded.read() {
+            div {
+                class: "alert alert-warning",
+                {tr("The gallery is too large for the browser storage and will not survive a reload. Remove some images to keep it.")}
+            }
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use dioxus::prelude::*;
+
+use crate::{
+    adjust_panel::adjust_panel,
+    curve_panel::curve_panel,
+    eink_panel::eink_settings,
+    export::OutputFormat,
+    histogram_panel::histogram_panel,
+    history::History,
+    i18n::{tr, tr_with},
+    img_conv::{
+        palettes, presets, AlphaMode, BayerSize, ColorMetric, ConvSettings, DalImageConverter, Dither,
+        DitherKernel, FitMode, PixelArtMode, ResizeFilter, Tone,
+    },
+    live_preview::live_preview,
+    mqtt::mqtt_settings,
+    palette_editor::palette_editor,
+    AppState, Route,
+};
+
+/// Conversion settings and export defaults
+#[component]
+pub fn settings_form(
+    mut conv: Signal<DalImageConverter>,
+    mut settings: Signal<ConvSettings>,
+    mut format: Signal<OutputFormat>,
+    mut history: Signal<History>,
+) -> Element {
+    rsx! {
+        form {
+            // Setting inputs change the settings first, the bubbled event records it
+            onchange: move |_| history.write().settings_changed(&settings.read()),
+            onclick: move |_| history.write().settings_changed(&settings.read()),
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Device")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(device) = evt.value().parse::<usize>().ok().and_then(|i| presets::DEVICES.get(i)) {
+                            conv.write().set_device(device);
+                        }
+                    },
+                    option {
+                        value: "",
+                        {tr("Custom")}
+                    }
+                    for (i, device) in presets::DEVICES.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: conv.read().dim() == device.dim && conv.read().palette() == device.palette,
+                            "{device.name}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Dimensions")}
+                }
+                div {
+                    class: "input-group",
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "1",
+                        value: "{conv.read().dim().0}",
+                        onchange: move |evt| {
+                            if let Ok(w) = evt.value().parse::<u32>() {
+                                let h = conv.read().dim().1;
+                                conv.write().set_dim((w, h));
+                            }
+                        }
+                    }
+                    span { class: "input-group-text", "×" }
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "1",
+                        value: "{conv.read().dim().1}",
+                        onchange: move |evt| {
+                            if let Ok(h) = evt.value().parse::<u32>() {
+                                let w = conv.read().dim().0;
+                                conv.write().set_dim((w, h));
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Board Wall (Columns × Rows)")}
+                }
+                div {
+                    class: "input-group",
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "1",
+                        value: "{settings.read().tiles.0}",
+                        onchange: move |evt| {
+                            if let Ok(cols) = evt.value().parse::<u32>() {
+                                settings.write().tiles.0 = cols.max(1);
+                            }
+                        }
+                    }
+                    span { class: "input-group-text", "×" }
+                    input {
+                        r#type: "number",
+                        class: "form-control",
+                        min: "1",
+                        value: "{settings.read().tiles.1}",
+                        onchange: move |evt| {
+                            if let Ok(rows) = evt.value().parse::<u32>() {
+                                settings.write().tiles.1 = rows.max(1);
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "form-check form-switch mb-3",
+                label {
+                    class: "form-check-label",
+                    {tr("Auto Rotate")}
+                }
+                input {
+                    class: "form-check-input",
+                    role: "switch",
+                    r#type: "checkbox",
+                    checked: settings.read().auto_rotate,
+                    onchange: move |evt| {
+                        settings.write().auto_rotate = evt.checked();
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label d-block",
+                    {tr("Fit")}
+                }
+                for (i, fit) in FitMode::ALL.iter().enumerate() {
+                    div {
+                        class: "form-check form-check-inline",
+                        input {
+                            class: "form-check-input",
+                            r#type: "radio",
+                            name: "fit",
+                            id: "fit-{i}",
+                            checked: settings.read().fit == *fit,
+                            onchange: move |_| {
+                                settings.write().fit = FitMode::ALL[i];
+                            }
+                        }
+                        label {
+                            class: "form-check-label",
+                            r#for: "fit-{i}",
+                            "{tr(fit.label())}"
+                        }
+                    }
+                }
+                if matches!(settings.read().fit, FitMode::Letterbox | FitMode::Center) {
+                    div {
+                        class: "d-flex align-items-center gap-2 mt-2",
+                        label {
+                            class: "form-label mb-0",
+                            {tr("Fill Color")}
+                        }
+                        input {
+                            class: "form-control form-control-color",
+                            r#type: "color",
+                            value: "{palettes::to_hex(settings.read().fill_color)}",
+                            onchange: move |evt| {
+                                if let Some(c) = palettes::parse_hex(&evt.value()) {
+                                    settings.write().fill_color = c;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Resize Filter")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(filter) = evt.value().parse::<usize>().ok().and_then(|i| ResizeFilter::ALL.get(i)) {
+                            settings.write().filter = *filter;
+                        }
+                    },
+                    for (i, filter) in ResizeFilter::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: settings.read().filter == *filter,
+                            "{tr(filter.label())}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Pixel Art Scaling")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(mode) = evt.value().parse::<usize>().ok().and_then(|i| PixelArtMode::ALL.get(i)) {
+                            settings.write().pixel_art = *mode;
+                        }
+                    },
+                    for (i, mode) in PixelArtMode::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: settings.read().pixel_art == *mode,
+                            "{tr(mode.label())}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Transparency")}
+                }
+                select {
+                    class: "form-select mb-2",
+                    onchange: move |evt| {
+                        if let Some(mode) = evt.value().parse::<usize>().ok().and_then(|i| AlphaMode::ALL.get(i)) {
+                            settings.write().alpha_mode = *mode;
+                        }
+                    },
+                    for (i, mode) in AlphaMode::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: settings.read().alpha_mode == *mode,
+                            "{tr(mode.label())}"
+                        }
+                    }
+                }
+                if settings.read().alpha_mode == AlphaMode::Composite {
+                    div {
+                        class: "d-flex align-items-center gap-2",
+                        label {
+                            class: "form-label mb-0",
+                            {tr("Background")}
+                        }
+                        input {
+                            class: "form-control form-control-color",
+                            r#type: "color",
+                            value: "{palettes::to_hex(settings.read().alpha_background)}",
+                            onchange: move |evt| {
+                                if let Some(c) = palettes::parse_hex(&evt.value()) {
+                                    settings.write().alpha_background = c;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if matches!(settings.read().alpha_mode, AlphaMode::Threshold | AlphaMode::Keep) {
+                div {
+                    class: "mb-3",
+                    label {
+                        class: "form-label",
+                        {tr_with("Alpha Threshold: {}", &[&settings.read().alpha_threshold])}
+                    }
+                    input {
+                        r#type: "range",
+                        class: "form-range",
+                        min: "1",
+                        max: "255",
+                        value: "{settings.read().alpha_threshold}",
+                        onchange: move |evt| {
+                            if let Ok(v) = evt.value().parse::<u8>() {
+                                settings.write().alpha_threshold = v;
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "mb-3",
+                    label {
+                        class: "form-label d-block",
+                        {tr("Transparent Pixels")}
+                    }
+                    div {
+                        class: "d-flex flex-wrap gap-1",
+                        for color in conv.read().palette().iter().copied() {
+                            button {
+                                r#type: "button",
+                                class: if settings.read().transparent_color == color { "btn btn-sm border border-3 border-primary" } else { "btn btn-sm border" },
+                                style: "width: 2em; height: 2em; background-color: {palettes::to_hex(color)}",
+                                title: "{palettes::to_hex(color)}",
+                                onclick: move |_| {
+                                    settings.write().transparent_color = color;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "form-check form-switch mb-3",
+                label {
+                    class: "form-check-label",
+                    {tr("Linear Light")}
+                }
+                input {
+                    class: "form-check-input",
+                    role: "switch",
+                    r#type: "checkbox",
+                    checked: settings.read().linear_light,
+                    onchange: move |evt| {
+                        settings.write().linear_light = evt.checked();
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Color Distance")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(metric) = evt.value().parse::<usize>().ok().and_then(|i| ColorMetric::ALL.get(i)) {
+                            conv.write().set_metric(*metric);
+                        }
+                    },
+                    for (i, metric) in ColorMetric::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: conv.read().metric() == *metric,
+                            "{tr(metric.label())}"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Dithering")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(dither) = evt.value().parse::<usize>().ok().and_then(|i| Dither::ALL.get(i)) {
+                            settings.write().dither = *dither;
+                        }
+                    },
+                    for (i, dither) in Dither::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: settings.read().dither == *dither,
+                            "{tr(dither.label())}"
+                        }
+                    }
+                }
+            }
+            if settings.read().dither == Dither::ErrorDiffusion {
+                div {
+                    class: "mb-3",
+                    label {
+                        class: "form-label",
+                        {tr("Kernel")}
+                    }
+                    select {
+                        class: "form-select",
+                        onchange: move |evt| {
+                            if let Some(kernel) = evt.value().parse::<usize>().ok().and_then(|i| DitherKernel::ALL.get(i)) {
+                                settings.write().kernel = *kernel;
+                            }
+                        },
+                        for (i, kernel) in DitherKernel::ALL.iter().enumerate() {
+                            option {
+                                value: "{i}",
+                                selected: settings.read().kernel == *kernel,
+                                "{tr(kernel.label())}"
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "form-check form-switch mb-3",
+                    label {
+                        class: "form-check-label",
+                        {tr("Serpentine Scanning")}
+                    }
+                    input {
+                        class: "form-check-input",
+                        role: "switch",
+                        r#type: "checkbox",
+                        checked: settings.read().serpentine,
+                        onchange: move |evt| {
+                            settings.write().serpentine = evt.checked();
+                        }
+                    }
+                }
+            }
+            if settings.read().dither == Dither::Ordered {
+                div {
+                    class: "mb-3",
+                    label {
+                        class: "form-label",
+                        {tr("Bayer Matrix")}
+                    }
+                    select {
+                        class: "form-select",
+                        onchange: move |evt| {
+                            if let Some(size) = evt.value().parse::<usize>().ok().and_then(|i| BayerSize::ALL.get(i)) {
+                                settings.write().bayer_size = *size;
+                            }
+                        },
+                        for (i, size) in BayerSize::ALL.iter().enumerate() {
+                            option {
+                                value: "{i}",
+                                selected: settings.read().bayer_size == *size,
+                                "{tr(size.label())}"
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "form-check form-switch mb-3",
+                label {
+                    class: "form-check-label",
+                    title: tr("Replaces single pixels without a neighbor of the same color"),
+                    {tr("Despeckle")}
+                }
+                input {
+                    class: "form-check-input",
+                    role: "switch",
+                    r#type: "checkbox",
+                    checked: settings.read().despeckle,
+                    onchange: move |evt| {
+                        settings.write().despeckle = evt.checked();
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    title: tr("Smaller areas of one color are merged into their largest neighbor"),
+                    {tr("Minimum Region Size")}
+                }
+                input {
+                    class: "form-control",
+                    r#type: "number",
+                    min: "0",
+                    value: "{settings.read().min_region}",
+                    onchange: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u32>() {
+                            settings.write().min_region = v;
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    title: tr("Uses only the palette colors that fit the image best, 0 uses all of them"),
+                    {tr("Maximum Colors")}
+                }
+                input {
+                    class: "form-control",
+                    r#type: "number",
+                    min: "0",
+                    value: "{settings.read().max_colors}",
+                    onchange: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u32>() {
+                            settings.write().max_colors = v;
+                        }
+                    }
+                }
+            }
+            div {
+                class: "mb-3",
+                label {
+                    class: "form-label",
+                    {tr("Output Format")}
+                }
+                select {
+                    class: "form-select",
+                    onchange: move |evt| {
+                        if let Some(f) = evt.value().parse::<usize>().ok().and_then(|i| OutputFormat::ALL.get(i)) {
+                            format.set(*f);
+                        }
+                    },
+                    for (i, f) in OutputFormat::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: *format.read() == *f,
+                            "{tr(f.label())}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Full page configuration screen, sharing its state with the gallery
+#[component]
+pub fn Settings() -> Element {
+    let AppState {
+        images,
+        conv,
+        settings,
+        format,
+        history,
+        selected,
+        mqtt,
+        ..
+    } = use_context();
+    // Tone of the selected image while its sliders move, shared with the live preview
+    let tone_draft = use_signal(|| None::<Tone>);
+
+    rsx! {
+        div {
+            div {
+                class: "d-flex align-items-center mb-3",
+                h1 { class: "me-auto", {tr("Settings")} }
+                Link { class: "btn btn-outline-primary", to: Route::Home {}, {tr("Back to Gallery")} }
+            }
+            div {
+                class: "row",
+                div {
+                    class: "col-md-8",
+                    palette_editor { conv }
+                    settings_form { conv, settings, format, history }
+                    adjust_panel { settings, history, images, selected, conv, draft: tone_draft }
+                    eink_settings {}
+                }
+                div {
+                    class: "col-md-4",
+                    live_preview { images, selected, conv, settings, tone_draft }
+                    histogram_panel { images, selected, conv, settings, history }
+                    curve_panel { settings, history }
+                    mqtt_settings { mqtt }
+                }
+            }
+        }
+    }
+}
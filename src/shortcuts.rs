@@ -0,0 +1,181 @@
+use std::rc::Rc;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use dioxus::prelude::*;
+
+use crate::{
+    convert_image, download,
+    history::{redo, undo},
+    i18n::tr_with,
+    img_conv::Transform,
+    media_input::{self, Upload},
+    toast, AppState,
+};
+
+// Cards per gallery row on wide screens, matching `row-cols-md-3`
+const GALLERY_COLUMNS: usize = 3;
+
+// Translates key presses and pastes into actions. Text fields keep their own
+// editing keys, so only Ctrl+S is taken from them.
+const KEY_BRIDGE: &str = r#"
+const send = (action, extra) => dioxus.send({ action, ...extra });
+const editing = (e) => e.target.matches("input[type=text], input[type=number], textarea");
+document.addEventListener("keydown", (e) => {
+    const ctrl = e.ctrlKey || e.metaKey;
+    const key = e.key.toLowerCase();
+    if (ctrl && key === "s") {
+        e.preventDefault();
+        send("save");
+        return;
+    }
+    if (editing(e) || e.altKey) {
+        return;
+    }
+    if (ctrl && (key === "z" || key === "y")) {
+        e.preventDefault();
+        send(key === "y" || e.shiftKey ? "redo" : "undo");
+        return;
+    }
+    const actions = {
+        Delete: "delete",
+        Backspace: "delete",
+        ArrowLeft: "prev",
+        ArrowRight: "next",
+        ArrowUp: "up",
+        ArrowDown: "down",
+        Escape: "deselect",
+    };
+    if (!ctrl && actions[e.key] && !e.target.matches("input, select")) {
+        e.preventDefault();
+        send(actions[e.key]);
+    }
+});
+document.addEventListener("paste", async (e) => {
+    if (editing(e)) {
+        return;
+    }
+    for (const file of e.clipboardData.files) {
+        const bytes = new Uint8Array(await file.arrayBuffer());
+        let binary = "";
+        for (let i = 0; i < bytes.length; i += 0x8000) {
+            binary += String.fromCharCode.apply(null, bytes.subarray(i, i + 0x8000));
+        }
+        send("paste", { name: file.name || "pasted.png", data: btoa(binary) });
+    }
+});
+"#;
+
+// Moves the selection by `step` cards, starting at the first card if none is selected
+fn move_selection(state: AppState, step: isize) {
+    let AppState { images, mut selected, .. } = state;
+    let images = images.read();
+    if images.is_empty() {
+        return;
+    }
+    let current = selected
+        .read()
+        .and_then(|id| images.iter().position(|img| img.id == id));
+    let next = match current {
+        Some(i) => i.saturating_add_signed(step).min(images.len() - 1),
+        None => 0,
+    };
+    selected.set(Some(images[next].id));
+}
+
+fn delete_selected(state: AppState) {
+    let AppState {
+        mut images,
+        mut selected,
+        mut history,
+        ..
+    } = state;
+    let Some(id) = *selected.read() else {
+        return;
+    };
+    let Some(index) = images.read().iter().position(|img| img.id == id) else {
+        return;
+    };
+    history.write().record_gallery(&images.read());
+    images.write().remove(index);
+    // Keep a selection, so repeated Delete presses clear a run of cards
+    let next = images.read().get(index).or(images.read().last()).map(|img| img.id);
+    selected.set(next);
+}
+
+fn save_selected(state: AppState) {
+    let id = *state.selected.read();
+    let Some(img) = id.and_then(|id| state.images.read().iter().find(|img| img.id == id).cloned()) else {
+        return;
+    };
+    let format = *state.format.read();
+    match img.encode(format) {
+        Ok(data) => download::save(&img.file_name(format), format.mime(), &data),
+        Err(e) => toast::error(tr_with("Failed to encode image: {}", &[&e])),
+    }
+}
+
+async fn paste(state: AppState, name: String, data: &str) -> anyhow::Result<()> {
+    let AppState {
+        mut images,
+        conv,
+        settings,
+        mut history,
+        mut selected,
+        ..
+    } = state;
+    let data = BASE64_STANDARD.decode(data)?;
+    let max_side = media_input::working_size(&conv.read(), &settings.read());
+    let Upload::Images(frames) = media_input::decode_upload(&name, data, max_side).await? else {
+        anyhow::bail!("Only still images can be pasted");
+    };
+    history.write().record_gallery(&images.read());
+    for img in frames {
+        let results = convert_image(&conv.read(), &settings.read(), Rc::new(img), name.clone(), Transform::default());
+        if let Some(first) = results.first() {
+            selected.set(Some(first.id));
+        }
+        images.write().extend(results);
+    }
+    Ok(())
+}
+
+/// Global keyboard shortcuts for the gallery, acting on the selected card
+#[component]
+pub fn shortcuts() -> Element {
+    let state: AppState = use_context();
+
+    use_future(move || async move {
+        let mut keys = eval(KEY_BRIDGE);
+        while let Ok(msg) = keys.recv().await {
+            let AppState {
+                images,
+                conv,
+                settings,
+                mut selected,
+                history,
+                ..
+            } = state;
+            match msg.get("action").and_then(|a| a.as_str()) {
+                Some("undo") => undo(history, images, conv, settings),
+                Some("redo") => redo(history, images, conv, settings),
+                Some("delete") => delete_selected(state),
+                Some("prev") => move_selection(state, -1),
+                Some("next") => move_selection(state, 1),
+                Some("up") => move_selection(state, -(GALLERY_COLUMNS as isize)),
+                Some("down") => move_selection(state, GALLERY_COLUMNS as isize),
+                Some("deselect") => selected.set(None),
+                Some("save") => save_selected(state),
+                Some("paste") => {
+                    let name = msg.get("name").and_then(|n| n.as_str()).unwrap_or("pasted.png").to_string();
+                    let data = msg.get("data").and_then(|d| d.as_str()).unwrap_or_default();
+                    if let Err(e) = paste(state, name, data).await {
+                        toast::error(tr_with("Failed to paste image: {}", &[&e]));
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+
+    None
+}
@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::img_conv::{ColorMetric, DalImageConverter};
+
+#[cfg(not(feature = "desktop"))]
+fn get_item(key: &str) -> Option<String> {
+    web_sys::window()?.local_storage().ok()??.get_item(key).ok()?
+}
+
+#[cfg(not(feature = "desktop"))]
+fn set_item(key: &str, text: &str) -> Result<(), String> {
+    let storage = web_sys::window()
+        .and_then(|w| w.local_storage().ok()?)
+        .ok_or("No localStorage")?;
+    storage.set_item(key, text).map_err(|e| format!("{e:?}"))
+}
+
+// Desktop builds keep one JSON file per key in the user's config directory
+#[cfg(feature = "desktop")]
+fn item_path(key: &str) -> Option<std::path::PathBuf> {
+    Some(dirs::config_dir()?.join("dale-img-conv").join(format!("{key}.json")))
+}
+
+#[cfg(feature = "desktop")]
+fn get_item(key: &str) -> Option<String> {
+    std::fs::read_to_string(item_path(key)?).ok()
+}
+
+#[cfg(feature = "desktop")]
+fn set_item(key: &str, text: &str) -> Result<(), String> {
+    let path = item_path(key).ok_or("No config directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Reads a JSON value saved under `key`, `None` if missing or unreadable
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let text = get_item(key)?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let result = serde_json::to_string(value)
+        .map_err(|e| format!("{e}"))
+        .and_then(|text| set_item(key, &text));
+    if let Err(e) = result {
+        dioxus_logger::tracing::error!("Failed to save {}: {}", key, e);
+    }
+}
+
+/// Like `use_signal`, but restored from localStorage (or the config
+/// directory on desktop) on startup and saved
+/// back on every change
+pub fn use_persistent<T: Serialize + DeserializeOwned + 'static>(
+    key: &'static str,
+    init: impl FnOnce() -> T,
+) -> Signal<T> {
+    let signal = use_signal(|| load(key).unwrap_or_else(init));
+    use_effect(move || save(key, &*signal.read()));
+    signal
+}
+
+/// The user editable parts of a converter
+#[derive(Serialize, Deserialize)]
+struct ConverterState {
+    palette: Vec<[u8; 3]>,
+    enabled: Vec<bool>,
+    // Missing in states saved before penalties existed
+    #[serde(default)]
+    penalties: Vec<f32>,
+    dim: (u32, u32),
+    metric: ColorMetric,
+}
+
+/// Converter signal that keeps its palette, dimensions and metric across visits
+pub fn use_persistent_converter(key: &'static str) -> Signal<DalImageConverter> {
+    let conv = use_signal(|| {
+        let mut conv = DalImageConverter::default();
+        if let Some(state) = load::<ConverterState>(key).filter(|s| !s.palette.is_empty()) {
+            conv.set_metric(state.metric);
+            conv.set_dim(state.dim);
+            conv.set_palette(&state.palette);
+            if state.enabled.len() == state.palette.len() && state.enabled.contains(&true) {
+                conv.set_enabled(&state.enabled);
+            }
+            if state.penalties.len() == state.palette.len() {
+                conv.set_penalties(&state.penalties);
+            }
+        }
+        conv
+    });
+    use_effect(move || {
+        let conv = conv.read();
+        let state = ConverterState {
+            palette: conv.palette().to_vec(),
+            enabled: conv.enabled().to_vec(),
+            penalties: conv.penalties().to_vec(),
+            dim: conv.dim(),
+            metric: conv.metric(),
+        };
+        save(key, &state);
+    });
+    conv
+}
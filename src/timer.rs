@@ -0,0 +1,8 @@
+/// Waits `ms` milliseconds without blocking the UI, on whichever runtime the
+/// app was built for
+pub async fn sleep(ms: u32) {
+    #[cfg(not(feature = "desktop"))]
+    gloo_timers::future::TimeoutFuture::new(ms).await;
+    #[cfg(feature = "desktop")]
+    tokio::time::sleep(std::time::Duration::from_millis(ms.into())).await;
+}
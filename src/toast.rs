@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dioxus::prelude::*;
+
+use crate::i18n::tr;
+
+static NEXT_TOAST: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastLevel {
+    Error,
+    Warning,
+    Info,
+}
+
+impl ToastLevel {
+    fn class(&self) -> &'static str {
+        match self {
+            ToastLevel::Error => "toast show text-bg-danger",
+            ToastLevel::Warning => "toast show text-bg-warning",
+            ToastLevel::Info => "toast show text-bg-info",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub id: u64,
+    pub level: ToastLevel,
+    pub message: String,
+}
+
+/// Messages shown until the user dismisses them
+pub static TOASTS: GlobalSignal<Vec<Toast>> = Signal::global(Vec::new);
+
+pub fn notify(level: ToastLevel, message: String) {
+    TOASTS.write().push(Toast {
+        id: NEXT_TOAST.fetch_add(1, Ordering::Relaxed),
+        level,
+        message,
+    });
+}
+
+/// Logs an error and shows it to the user
+pub fn error(message: String) {
+    dioxus_logger::tracing::error!("{}", message);
+    notify(ToastLevel::Error, message);
+}
+
+/// Stack of notifications in the bottom corner, each with a dismiss button
+#[component]
+pub fn toasts() -> Element {
+    rsx! {
+        div {
+            class: "toast-container position-fixed bottom-0 end-0 p-3",
+            for toast in TOASTS.read().iter() {
+                div {
+                    key: "{toast.id}",
+                    class: toast.level.class(),
+                    role: "alert",
+                    div {
+                        class: "d-flex",
+                        div { class: "toast-body", "{toast.message}" }
+                        button {
+                            class: "btn-close btn-close-white me-2 m-auto",
+                            r#type: "button",
+                            title: tr("Dismiss"),
+                            onclick: {
+                                let id = toast.id;
+                                move |_| TOASTS.write().retain(|t| t.id != id)
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
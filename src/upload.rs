@@ -0,0 +1,158 @@
+use std::{rc::Rc, sync::Arc};
+
+use dioxus::{html::FileEngine, prelude::*};
+
+use crate::{
+    convert_image_async,
+    frame_picker::Animation,
+    history::History,
+    import_csv,
+    img_conv::{ConvSettings, DalImageConverter, Transform},
+    media_input::{self, Upload, Video},
+    queue::{self, Job, JobStatus},
+    ImageResult,
+};
+
+/// Extensions offered by the native open dialog, matching the file input
+#[cfg(feature = "desktop")]
+const UPLOAD_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "heic", "heif", "avif", "jxl", "mp4", "webm", "csv"];
+
+/// Where the files of one upload come from
+pub enum UploadSource {
+    /// A file input or a drop, read through the webview
+    Browser(Arc<dyn FileEngine>),
+    /// Paths picked in a native dialog
+    #[cfg(feature = "desktop")]
+    Native(Vec<std::path::PathBuf>),
+}
+
+impl UploadSource {
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Self::Browser(engine) => engine.files(),
+            #[cfg(feature = "desktop")]
+            Self::Native(paths) => paths
+                .iter()
+                .map(|p| p.file_name().unwrap_or_default().to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+
+    // Browser files are looked up by name, native ones by position
+    #[cfg_attr(not(feature = "desktop"), allow(unused_variables))]
+    async fn read(&self, index: usize, name: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Browser(engine) => engine.read_file(name).await,
+            #[cfg(feature = "desktop")]
+            Self::Native(paths) => tokio::fs::read(paths.get(index)?).await.ok(),
+        }
+    }
+}
+
+/// Everything an upload writes to, bundled so each entry point can share it
+#[derive(Clone, Copy)]
+pub struct Uploader {
+    pub images: Signal<Vec<Rc<ImageResult>>>,
+    pub conv: Signal<DalImageConverter>,
+    pub settings: Signal<ConvSettings>,
+    pub history: Signal<History>,
+    pub jobs: Signal<Vec<Job>>,
+    pub allow_duplicates: Signal<bool>,
+    /// Animated upload whose frames still need to be picked
+    pub pending: Signal<Option<Animation>>,
+    pub pending_video: Signal<Option<Video>>,
+}
+
+impl Uploader {
+    /// Queues every file of `source`, then decodes and converts them one by one
+    pub async fn upload(mut self, source: UploadSource) {
+        let files = source.names();
+        let mut recorded = false;
+        let ids = queue::enqueue(self.jobs, &files);
+        for (index, (id, file_name)) in ids.into_iter().zip(files).enumerate() {
+            if !queue::set_status(self.jobs, id, JobStatus::Decoding).await {
+                continue;
+            }
+            let Some(data) = source.read(index, &file_name).await else {
+                queue::fail(self.jobs, id, &anyhow::anyhow!("Failed to read file"));
+                continue;
+            };
+            let hash = media_input::content_hash(&data);
+            if !*self.allow_duplicates.read() && self.images.read().iter().any(|img| img.source_hash == Some(hash)) {
+                queue::skip(self.jobs, id);
+                continue;
+            }
+            let max_side = media_input::working_size(&self.conv.read(), &self.settings.read());
+            let upload = match media_input::decode_upload(&file_name, data, max_side).await {
+                Ok(upload) => upload,
+                Err(e) => {
+                    queue::fail(self.jobs, id, &e);
+                    continue;
+                }
+            };
+
+            if !queue::set_status(self.jobs, id, JobStatus::Converting).await {
+                continue;
+            }
+            match upload {
+                Upload::Images(frames) => {
+                    for img in frames {
+                        // Cloned, so neither signal stays borrowed while the conversion yields
+                        let (conv, settings) = (self.conv.read().clone(), self.settings.read().clone());
+                        let results =
+                            convert_image_async(conv, settings, Rc::new(img), file_name.clone(), Transform::default()).await;
+                        for img in results {
+                            dioxus_logger::tracing::info!("Image loaded: {} {}", img.img.height(), img.img.width());
+                            let mut img = (*img).clone();
+                            img.source_hash = Some(hash);
+                            self.add(Rc::new(img), &mut recorded);
+                        }
+                    }
+                }
+                Upload::Animation(mut animation) => {
+                    animation.source_hash = Some(hash);
+                    self.pending.set(Some(animation));
+                }
+                Upload::Video(mut video) => {
+                    video.source_hash = Some(hash);
+                    self.pending_video.set(Some(video));
+                }
+                Upload::Csv(text) => match import_csv(&self.conv.read(), &self.settings.read(), &text, file_name.clone()) {
+                    Ok(img) => {
+                        let mut img = (*img).clone();
+                        img.source_hash = Some(hash);
+                        self.add(Rc::new(img), &mut recorded);
+                    }
+                    Err(e) => {
+                        queue::fail(self.jobs, id, &e);
+                        continue;
+                    }
+                },
+            }
+            queue::set_status(self.jobs, id, JobStatus::Done).await;
+        }
+    }
+
+    // Records the gallery before the first image of an upload is added, so
+    // failed or empty uploads leave no undo step behind
+    fn add(mut self, img: Rc<ImageResult>, recorded: &mut bool) {
+        if !std::mem::replace(recorded, true) {
+            self.history.write().record_gallery(&self.images.read());
+        }
+        self.images.push(img);
+    }
+
+    /// Lets the user pick files in a native dialog and uploads them
+    #[cfg(feature = "desktop")]
+    pub async fn pick_files(self) {
+        let Some(files) = rfd::AsyncFileDialog::new()
+            .add_filter(crate::i18n::tr("Images"), UPLOAD_EXTENSIONS)
+            .pick_files()
+            .await
+        else {
+            return;
+        };
+        let paths = files.into_iter().map(|f| f.path().to_path_buf()).collect();
+        self.upload(UploadSource::Native(paths)).await;
+    }
+}